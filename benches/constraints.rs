@@ -0,0 +1,73 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use cyclone::constraint::{solve_iterative, DistanceConstraint};
+use cyclone::linalg::solve_global;
+use cyclone::particle::Particle;
+use cyclone::vec::Vec3;
+
+/// A triangulated 10x10 grid pinned at two corners, with the free particles nudged off rest.
+fn grid() -> (Vec<Particle<f64>>, Vec<DistanceConstraint<f64>>) {
+    let n = 10;
+    let mut particles = Vec::new();
+    for y in 0..n {
+        for x in 0..n {
+            particles.push(Particle {
+                position: Vec3(x as f64, y as f64, 0.0),
+                inverse_mass: if y == 0 && (x == 0 || x == n - 1) {
+                    0.0
+                } else {
+                    1.0
+                },
+                ..Particle::default()
+            });
+        }
+    }
+    let mut constraints = Vec::new();
+    for y in 0..n {
+        for x in 0..n {
+            let i = y * n + x;
+            let mut neighbors = Vec::new();
+            if x + 1 < n {
+                neighbors.push(i + 1);
+            }
+            if y + 1 < n {
+                neighbors.push(i + n);
+            }
+            if x + 1 < n && y + 1 < n {
+                neighbors.push(i + n + 1);
+            }
+            for j in neighbors {
+                let length = (particles[i].position - particles[j].position).mag();
+                constraints.push(DistanceConstraint::new(i, j, length));
+            }
+        }
+    }
+    for (i, particle) in particles.iter_mut().enumerate() {
+        if particle.inverse_mass > 0.0 {
+            let phase = i as f64;
+            particle.position += Vec3(phase.sin(), phase.cos(), 0.0) * 0.05;
+        }
+    }
+    (particles, constraints)
+}
+
+/// Both solvers are run to a maximum constraint error of about 1e-9.
+fn constraints(c: &mut Criterion) {
+    let (particles, constraints) = grid();
+    c.bench_function("solve_iterative 10x10", |b| {
+        b.iter_batched(
+            || particles.clone(),
+            |mut particles| solve_iterative(&mut particles, black_box(&constraints), 2000),
+            BatchSize::SmallInput,
+        )
+    });
+    c.bench_function("solve_global 10x10", |b| {
+        b.iter_batched(
+            || particles.clone(),
+            |mut particles| solve_global(&mut particles, black_box(&constraints), 10),
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+criterion_group!(benches, constraints);
+criterion_main!(benches);
@@ -0,0 +1,34 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use cyclone::force::{ParticleDrag, ParticleForceRegistry, ParticleGravity};
+use cyclone::particle::Particle;
+use cyclone::vec::Vec3;
+
+/// 200k particles, each with its own gravity and drag.
+fn scene() -> (Vec<Particle<f64>>, ParticleForceRegistry<f64>) {
+    let n = 200_000;
+    let particles: Vec<_> = (0..n)
+        .map(|i| Particle {
+            velocity: Vec3((i as f64).sin(), (i as f64).cos(), 1.0) * 10.0,
+            ..Particle::default()
+        })
+        .collect();
+    let mut registry = ParticleForceRegistry::new();
+    for i in 0..n {
+        registry.add(i, ParticleGravity::new(Vec3(0.0, -9.8, 0.0)));
+        registry.add(i, ParticleDrag::new(0.1, 0.01));
+    }
+    (particles, registry)
+}
+
+fn forces(c: &mut Criterion) {
+    let (mut particles, mut registry) = scene();
+    c.bench_function("update_forces_serial 200k", |b| {
+        b.iter(|| registry.update_forces_serial(&mut particles, 0.01))
+    });
+    c.bench_function("update_forces 200k", |b| {
+        b.iter(|| registry.update_forces(&mut particles, 0.01))
+    });
+}
+
+criterion_group!(benches, forces);
+criterion_main!(benches);
@@ -0,0 +1,28 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use cyclone::force::{ForceFn, ParticleForceRegistry};
+use cyclone::particle::{add_force_all, Particle};
+use cyclone::vec::Vec3;
+
+const WIND: Vec3<f64> = Vec3(3.0, 0.0, 1.0);
+
+/// The same uniform force on 100k particles, from a registry and from `add_force_all`.
+fn uniform(c: &mut Criterion) {
+    let n = 100_000;
+    let mut particles = vec![Particle::<f64>::default(); n];
+    let mut registry = ParticleForceRegistry::new();
+    for i in 0..n {
+        registry.add(
+            i,
+            ForceFn::new(|p: &mut Particle<f64>, _| p.add_force(WIND)),
+        );
+    }
+    c.bench_function("registry uniform 100k", |b| {
+        b.iter(|| registry.update_forces_serial(&mut particles, 0.01))
+    });
+    c.bench_function("add_force_all 100k", |b| {
+        b.iter(|| add_force_all(&mut particles, WIND))
+    });
+}
+
+criterion_group!(benches, uniform);
+criterion_main!(benches);
@@ -0,0 +1,113 @@
+use cyclone::collide::GroundPlaneContacts;
+use cyclone::force::ParticleGravity;
+use cyclone::link::{ParticleCable, ParticleCableConstraint, ParticleRod};
+use cyclone::particle::Particle;
+use cyclone::vec::Vec3;
+use cyclone::world::ParticleWorld;
+use raylib::prelude::*;
+
+const UP: Vector3 = Vector3 {
+    x: 0.0,
+    y: 1.0,
+    z: 0.0,
+};
+
+fn c_to_r(v: Vec3<f32>) -> Vector3 {
+    Vector3 {
+        x: v.0,
+        y: v.1,
+        z: v.2,
+    }
+}
+
+const PLANKS: usize = 6;
+
+/// A rope bridge: pairs of particles held apart by rods, linked end to end by cables, and each
+/// hung from a support above.
+struct Bridge {
+    world: ParticleWorld<f32>,
+    /// Each support's anchor, and the particle hanging from it.
+    supports: Vec<(Vec3<f32>, usize)>,
+    /// The particles joined by each rod and cable.
+    links: Vec<(usize, usize)>,
+}
+
+fn build_bridge() -> Bridge {
+    let mut world = ParticleWorld::new(64);
+    let mut handles = Vec::new();
+    for i in 0..2 * PLANKS {
+        let x = (i / 2) as f32 * 2.0 - 5.0;
+        let z = (i % 2) as f32 * 2.0 - 1.0;
+        let handle = world.add_particle(Particle {
+            position: Vec3(x, 4.0, z),
+            damping: 0.9,
+            ..Particle::default()
+        });
+        world.add_force(handle, ParticleGravity::new(Vec3(0.0, -9.8, 0.0)));
+        handles.push(handle.index());
+    }
+    let mut supports = Vec::new();
+    let mut links = Vec::new();
+    for (i, &particle) in handles.iter().enumerate() {
+        let x = (i / 2) as f32 * 2.2 - 5.5;
+        let z = (i % 2) as f32 * 1.6 - 0.8;
+        let anchor = Vec3(x, 6.0, z);
+        world.add_contact_generator(ParticleCableConstraint::new(particle, anchor, 2.0, 0.5));
+        supports.push((anchor, particle));
+        if i + 2 < handles.len() {
+            world.add_contact_generator(ParticleCable::new(particle, handles[i + 2], 1.9, 0.3));
+            links.push((particle, handles[i + 2]));
+        }
+        if i % 2 == 0 {
+            world.add_contact_generator(ParticleRod::new(particle, handles[i + 1], 2.0));
+            links.push((particle, handles[i + 1]));
+        }
+    }
+    world.add_contact_generator(GroundPlaneContacts::at_height(0.0, 0.1, 0.2));
+    Bridge {
+        world,
+        supports,
+        links,
+    }
+}
+
+fn main() {
+    let Bridge {
+        mut world,
+        supports,
+        links,
+    } = build_bridge();
+    let (mut rl, thread) = raylib::init().size(800, 450).title("Bridge").build();
+    rl.set_target_fps(60);
+    let camera = Camera::perspective(vec3(0.0, 8.0, 14.0), vec3(0.0, 3.0, 0.0), UP, 45.0);
+    while !rl.window_should_close() {
+        if rl.is_key_pressed(KeyboardKey::KEY_SPACE) {
+            // Kick the middle planks upward.
+            for particle in &mut world.particles_mut()[PLANKS - 2..PLANKS + 2] {
+                particle.velocity += Vec3(0.0, 8.0, 0.0);
+            }
+        }
+        world.start_frame();
+        world.run_physics(rl.get_frame_time().min(1.0 / 30.0));
+        let mut d = rl.begin_drawing(&thread);
+        d.clear_background(Color::BLACK);
+        d.draw_fps(10, 10);
+        d.draw_text("Press SPACE to shake the bridge!", 100, 10, 20, Color::GRAY);
+        let mut d3 = d.begin_mode_3D(camera);
+        let particles = world.particles();
+        for &(anchor, particle) in &supports {
+            d3.draw_line_3D(
+                c_to_r(anchor),
+                c_to_r(particles[particle].position),
+                Color::GRAY,
+            );
+        }
+        for &(a, b) in &links {
+            let (a, b) = (particles[a].position, particles[b].position);
+            d3.draw_line_3D(c_to_r(a), c_to_r(b), Color::BROWN);
+        }
+        for particle in particles {
+            d3.draw_sphere(c_to_r(particle.position), 0.1, Color::GOLD);
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use cyclone::particle::Particle;
+use cyclone::pool::ParticlePool;
 use cyclone::vec::Vec3;
 use num::clamp;
 use rand::prelude::*;
@@ -21,60 +22,67 @@ fn c_to_r(v: Vec3<f32>) -> Vector3 {
     }
 }
 
-struct Spark {
-    particle: Particle<f32>,
+/// How long each spark lives and how long it's been alive, indexed by its handle's index.
+#[derive(Clone, Copy, Default)]
+struct Life {
     life: f32,
     age: f32,
 }
 
-fn add_sparks(n_sparks: i32, sparks: &mut Vec<Spark>) {
+fn add_sparks(n_sparks: i32, sparks: &mut ParticlePool<f32>, lives: &mut Vec<Life>) {
     for _ in 1..=n_sparks {
-        let spark = Spark {
-            particle: Particle {
-                position: ZERO,
-                velocity: Vec3(
-                    thread_rng().sample::<f32, _>(StandardNormal) * 20.0,
-                    thread_rng().sample::<f32, _>(StandardNormal) * 20.0,
-                    thread_rng().sample::<f32, _>(StandardNormal) * 20.0,
-                ),
-                acceleration: ZERO,
-                damping: 0.5,
-                inverse_mass: 1.0,
-            },
+        let handle = sparks.spawn(Particle {
+            position: ZERO,
+            velocity: Vec3(
+                thread_rng().sample::<f32, _>(StandardNormal) * 20.0,
+                thread_rng().sample::<f32, _>(StandardNormal) * 20.0,
+                thread_rng().sample::<f32, _>(StandardNormal) * 20.0,
+            ),
+            acceleration: Vec3(0.0, -10.0, 0.0),
+            damping: 0.5,
+            gravity_scale: 1.0,
+            inverse_mass: 1.0,
+            force_accum: ZERO,
+        });
+        if lives.len() <= handle.index() {
+            lives.resize(handle.index() + 1, Life::default());
+        }
+        lives[handle.index()] = Life {
             life: thread_rng().sample::<f32, _>(StandardNormal) * 2.0 + 8.0,
             age: 0.0,
         };
-        sparks.push(spark);
     }
 }
 
-fn integrate_sparks(duration: f32, sparks: &mut Vec<Spark>) {
-    for spark in sparks {
-        spark.age += duration;
-        spark.particle.acceleration = Vec3(0.0, -10.0, 0.0);
-        spark.particle.integrate(duration);
-    }
+fn integrate_sparks(duration: f32, sparks: &mut ParticlePool<f32>, lives: &mut [Life]) {
+    sparks.integrate(duration);
+    sparks.retain(|handle, _| {
+        let life = &mut lives[handle.index()];
+        life.age += duration;
+        life.age < life.life
+    });
 }
 
 fn main() {
-    let mut sparks: Vec<Spark> = Vec::new();
+    let mut sparks = ParticlePool::new();
+    let mut lives = Vec::new();
     let (mut rl, thread) = raylib::init().size(800, 450).title("Fireworks").build();
     rl.set_target_fps(60);
     let camera = Camera::perspective(vec3(0.0, 50.0, 100.0), vec3(0.0, 0.0, 0.0), UP, 45.0);
     while !rl.window_should_close() {
         if rl.is_key_pressed(KeyboardKey::KEY_SPACE) {
-            add_sparks(100, &mut sparks);
+            add_sparks(100, &mut sparks, &mut lives);
         }
-        integrate_sparks(rl.get_frame_time(), &mut sparks);
-        sparks.retain(|spark| spark.age < spark.life);
+        integrate_sparks(rl.get_frame_time(), &mut sparks, &mut lives);
         let mut d = rl.begin_drawing(&thread);
         d.clear_background(Color::BLACK);
         d.draw_fps(10, 10);
         d.draw_text("Press SPACE for Fireworks!", 100, 10, 20, Color::GRAY);
         let mut d3 = d.begin_mode_3D(camera);
-        for spark in &sparks {
-            let life = clamp(1.0 - (spark.age / spark.life), 0.0, 1.0);
-            d3.draw_sphere(c_to_r(spark.particle.position), life, Color::GOLD);
+        for (handle, spark) in sparks.iter_active() {
+            let Life { life, age } = lives[handle.index()];
+            let life = clamp(1.0 - (age / life), 0.0, 1.0);
+            d3.draw_sphere(c_to_r(spark.position), life, Color::GOLD);
         }
     }
 }
@@ -0,0 +1,113 @@
+use crate::vec::Vec3;
+use num::Float;
+use num_traits::NumAssign;
+
+/// An axis-aligned bounding box.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb<T: Float> {
+    pub min: Vec3<T>,
+    pub max: Vec3<T>,
+}
+
+impl<T: Float + NumAssign> Aabb<T> {
+    pub fn new(min: Vec3<T>, max: Vec3<T>) -> Self {
+        Self { min, max }
+    }
+
+    pub fn size(&self) -> Vec3<T> {
+        self.max - self.min
+    }
+
+    /// Points on the boundary are contained.
+    pub fn contains(&self, point: Vec3<T>) -> bool {
+        self.min.0 <= point.0
+            && self.min.1 <= point.1
+            && self.min.2 <= point.2
+            && point.0 <= self.max.0
+            && point.1 <= self.max.1
+            && point.2 <= self.max.2
+    }
+
+    /// Boxes that only touch are considered overlapping.
+    pub fn overlaps(&self, other: &Self) -> bool {
+        self.min.0 <= other.max.0
+            && self.min.1 <= other.max.1
+            && self.min.2 <= other.max.2
+            && other.min.0 <= self.max.0
+            && other.min.1 <= self.max.1
+            && other.min.2 <= self.max.2
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingSphere<T: Float> {
+    pub center: Vec3<T>,
+    pub radius: T,
+}
+
+impl<T: Float + NumAssign> BoundingSphere<T> {
+    pub fn new(center: Vec3<T>, radius: T) -> Self {
+        Self { center, radius }
+    }
+
+    /// Spheres that only touch are considered overlapping.
+    pub fn overlaps(&self, other: &Self) -> bool {
+        (self.center - other.center).mag_squared() <= (self.radius + other.radius).powi(2)
+    }
+}
+
+/// Colliders that can report the tightest axis-aligned box around themselves.
+///
+/// The broad phase works in terms of these boxes, so it doesn't need to know about specific
+/// collider shapes.
+#[allow(clippy::module_name_repetitions)]
+pub trait BoundingBox<T: Float> {
+    fn aabb(&self) -> Aabb<T>;
+}
+
+impl<T: Float> BoundingBox<T> for Aabb<T> {
+    fn aabb(&self) -> Aabb<T> {
+        *self
+    }
+}
+
+impl<T: Float + NumAssign> BoundingBox<T> for BoundingSphere<T> {
+    fn aabb(&self) -> Aabb<T> {
+        let extent = Vec3(self.radius, self.radius, self.radius);
+        Aabb::new(self.center - extent, self.center + extent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bounds::{Aabb, BoundingBox, BoundingSphere};
+    use crate::vec::Vec3;
+
+    #[test]
+    fn sphere_aabb() {
+        let sphere = BoundingSphere::new(Vec3(1.0, 2.0, 3.0), 0.5);
+        let aabb = sphere.aabb();
+        assert_eq!(aabb.size(), Vec3(1.0, 1.0, 1.0));
+        assert_eq!(aabb.min, Vec3(0.5, 1.5, 2.5));
+        assert!(aabb.contains(Vec3(1.5, 2.0, 3.0)));
+        assert!(!aabb.contains(Vec3(1.5, 2.0, 3.5 + 1e-9)));
+    }
+
+    #[test]
+    fn aabb_aabb() {
+        let aabb = Aabb::new(Vec3(0.0, 0.0, 0.0), Vec3(1.0, 2.0, 3.0));
+        assert_eq!(aabb.aabb(), aabb);
+    }
+
+    #[test]
+    fn overlaps() {
+        let a = Aabb::new(Vec3(0.0, 0.0, 0.0), Vec3(1.0, 1.0, 1.0));
+        let b = Aabb::new(Vec3(1.0, 0.5, 0.5), Vec3(2.0, 2.0, 2.0));
+        let c = Aabb::new(Vec3(1.5, 0.5, 0.5), Vec3(2.0, 2.0, 2.0));
+        assert!(a.overlaps(&b));
+        assert!(!a.overlaps(&c));
+        let s = BoundingSphere::new(Vec3(0.0, 0.0, 0.0), 1.0);
+        assert!(s.overlaps(&BoundingSphere::new(Vec3(0.0, 3.0, 0.0), 2.0)));
+        assert!(!s.overlaps(&BoundingSphere::new(Vec3(0.0, 3.0, 0.0), 1.5)));
+    }
+}
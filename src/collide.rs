@@ -0,0 +1,1408 @@
+use crate::bounds::{Aabb, BoundingBox};
+use crate::contact::{ParticleContact, ParticleContactGenerator};
+use crate::material::MaterialId;
+use crate::math;
+use crate::particle::Particle;
+use crate::quaternion::Quaternion;
+use crate::spatial::SpatialHash;
+use crate::vec::Vec3;
+use arrayvec::ArrayVec;
+use num::Float;
+use num_traits::NumAssign;
+
+/// The plane of points `p` where `p.dot(normal) == offset`.
+///
+/// When treated as a half-space, everything behind the plane (opposite `normal`) is solid.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Plane<T: Float> {
+    /// Must be unit length.
+    pub normal: Vec3<T>,
+    pub offset: T,
+}
+
+impl<T: Float + NumAssign> Plane<T> {
+    pub fn new(normal: Vec3<T>, offset: T) -> Self {
+        Self { normal, offset }
+    }
+
+    /// Signed distance from the plane; positive in front of it.
+    pub fn distance(&self, point: Vec3<T>) -> T {
+        point.dot(self.normal) - self.offset
+    }
+}
+
+/// A point of contact between two colliders.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Contact<T: Float> {
+    pub point: Vec3<T>,
+    /// Points away from the second collider, toward the first.
+    pub normal: Vec3<T>,
+    pub penetration: T,
+}
+
+/// A line segment from `a` to `b`, thickened by `radius`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Capsule<T: Float> {
+    pub a: Vec3<T>,
+    pub b: Vec3<T>,
+    pub radius: T,
+}
+
+impl<T: Float + NumAssign> Capsule<T> {
+    pub fn new(a: Vec3<T>, b: Vec3<T>, radius: T) -> Self {
+        Self { a, b, radius }
+    }
+}
+
+impl<T: Float + NumAssign> BoundingBox<T> for Capsule<T> {
+    fn aabb(&self) -> Aabb<T> {
+        let extent = Vec3(self.radius, self.radius, self.radius);
+        let min = Vec3(
+            self.a.0.min(self.b.0),
+            self.a.1.min(self.b.1),
+            self.a.2.min(self.b.2),
+        );
+        let max = Vec3(
+            self.a.0.max(self.b.0),
+            self.a.1.max(self.b.1),
+            self.a.2.max(self.b.2),
+        );
+        Aabb::new(min - extent, max + extent)
+    }
+}
+
+/// Contacts between a capsule and the half-space behind `plane`.
+///
+/// The deepest point of a capsule is always at one of its ends, so there is one contact for
+/// each end that dips below the plane: two when the capsule lies flat, one when it leans.
+pub fn capsule_and_half_space<T: Float + NumAssign>(
+    capsule: &Capsule<T>,
+    plane: &Plane<T>,
+) -> Vec<Contact<T>> {
+    let mut contacts = Vec::new();
+    for &end in &[capsule.a, capsule.b] {
+        let distance = plane.distance(end) - capsule.radius;
+        if distance < T::zero() {
+            contacts.push(Contact {
+                point: end - plane.normal * (distance + capsule.radius),
+                normal: plane.normal,
+                penetration: -distance,
+            });
+        }
+    }
+    contacts
+}
+
+/// A box with its center at `center`, turned by `orientation`, and reaching `half_size` along
+/// each of its own axes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CollisionBox<T: Float> {
+    pub center: Vec3<T>,
+    /// Must be unit length.
+    pub orientation: Quaternion<T>,
+    pub half_size: Vec3<T>,
+}
+
+impl<T: Float + NumAssign> CollisionBox<T> {
+    pub fn new(center: Vec3<T>, orientation: Quaternion<T>, half_size: Vec3<T>) -> Self {
+        Self {
+            center,
+            orientation,
+            half_size,
+        }
+    }
+
+    /// The box's own X, Y, and Z axes, as unit vectors.
+    pub fn axes(&self) -> [Vec3<T>; 3] {
+        let (x, y, z) = self.orientation.to_columns();
+        [x, y, z]
+    }
+
+    fn half_sizes(&self) -> [T; 3] {
+        [self.half_size.0, self.half_size.1, self.half_size.2]
+    }
+
+    /// How far the box reaches along the unit `axis`, each way from its center.
+    fn extent_along(&self, axes: &[Vec3<T>; 3], axis: Vec3<T>) -> T {
+        let half = self.half_sizes();
+        (0..3).fold(T::zero(), |extent, i| {
+            extent + half[i] * axes[i].dot(axis).abs()
+        })
+    }
+
+    /// The point at `local` in the box's own coordinates.
+    fn point_at(&self, axes: &[Vec3<T>; 3], local: [T; 3]) -> Vec3<T> {
+        self.center + axes[0] * local[0] + axes[1] * local[1] + axes[2] * local[2]
+    }
+}
+
+impl<T: Float + NumAssign> BoundingBox<T> for CollisionBox<T> {
+    fn aabb(&self) -> Aabb<T> {
+        let axes = self.axes();
+        let (zero, one) = (T::zero(), T::one());
+        let extent = Vec3(
+            self.extent_along(&axes, Vec3(one, zero, zero)),
+            self.extent_along(&axes, Vec3(zero, one, zero)),
+            self.extent_along(&axes, Vec3(zero, zero, one)),
+        );
+        Aabb::new(self.center - extent, self.center + extent)
+    }
+}
+
+/// Contacts between two boxes, found with the separating axis test.
+///
+/// Two boxes overlap unless some axis separates their projections, and there are only fifteen
+/// axes worth checking: the three face normals of each box, and the cross products of each
+/// pair of their edges. The axis on which they overlap least becomes the contact normal. A face
+/// normal means a face of one box is pressed into the other, and every corner of the region
+/// where they meet gets a contact, up to eight of them; `reduce_manifold` can pick out four.
+/// A pair of edges means the boxes touch where those edges cross, which gets one contact.
+/// Boxes that don't touch give no contacts, and neither do boxes with NaN in their placement.
+pub fn box_and_box<T: Float + NumAssign>(
+    a: &CollisionBox<T>,
+    b: &CollisionBox<T>,
+) -> ArrayVec<Contact<T>, 8> {
+    let (axes_a, axes_b) = (a.axes(), b.axes());
+    let to_center = b.center - a.center;
+    // Edges so close to parallel that their cross product is this short define no axis.
+    let parallel = T::from(1e-3).unwrap();
+    let mut best = (T::infinity(), 0);
+    let mut best_face = 0;
+    for index in 0..15 {
+        let axis = match index {
+            0..=2 => axes_a[index],
+            3..=5 => axes_b[index - 3],
+            _ => axes_a[(index - 6) / 3].cross(axes_b[(index - 6) % 3]),
+        };
+        if axis.mag_squared() < parallel {
+            continue;
+        }
+        let axis = axis.norm();
+        let penetration = a.extent_along(&axes_a, axis) + b.extent_along(&axes_b, axis)
+            - to_center.dot(axis).abs();
+        if penetration < T::zero() || penetration.is_nan() {
+            return ArrayVec::new();
+        }
+        if penetration < best.0 {
+            best = (penetration, index);
+        }
+        if index == 5 {
+            best_face = best.1;
+        }
+    }
+    let (penetration, index) = best;
+    match index {
+        0..=2 => face_contacts(a, &axes_a, b, &axes_b, index, false),
+        3..=5 => face_contacts(b, &axes_b, a, &axes_a, index - 3, true),
+        _ => {
+            let (edge_a, edge_b) = ((index - 6) / 3, (index - 6) % 3);
+            let mut normal = axes_a[edge_a].cross(axes_b[edge_b]).norm();
+            if normal.dot(to_center) > T::zero() {
+                normal = normal.invert();
+            }
+            // The middle of the edge of each box that's nearest the other box.
+            let (mut on_a, mut on_b) = (a.half_sizes(), b.half_sizes());
+            for i in 0..3 {
+                if i == edge_a {
+                    on_a[i] = T::zero();
+                } else if axes_a[i].dot(normal) > T::zero() {
+                    on_a[i] = -on_a[i];
+                }
+                if i == edge_b {
+                    on_b[i] = T::zero();
+                } else if axes_b[i].dot(normal) < T::zero() {
+                    on_b[i] = -on_b[i];
+                }
+            }
+            let point = closest_between_edges(
+                (
+                    a.point_at(&axes_a, on_a),
+                    axes_a[edge_a],
+                    a.half_sizes()[edge_a],
+                ),
+                (
+                    b.point_at(&axes_b, on_b),
+                    axes_b[edge_b],
+                    b.half_sizes()[edge_b],
+                ),
+                best_face > 2,
+            );
+            let mut contacts = ArrayVec::new();
+            contacts.push(Contact {
+                point,
+                normal,
+                penetration,
+            });
+            contacts
+        }
+    }
+}
+
+/// Contacts where a face of `reference`, the one along its axis `face`, presses into
+/// `incident`. The normals point toward `reference`, or toward `incident` if `flip`.
+fn face_contacts<T: Float + NumAssign>(
+    reference: &CollisionBox<T>,
+    reference_axes: &[Vec3<T>; 3],
+    incident: &CollisionBox<T>,
+    incident_axes: &[Vec3<T>; 3],
+    face: usize,
+    flip: bool,
+) -> ArrayVec<Contact<T>, 8> {
+    let mut normal = reference_axes[face];
+    if normal.dot(incident.center - reference.center) < T::zero() {
+        normal = normal.invert();
+    }
+    // The incident box's face that most nearly faces back at the reference face.
+    let dots = [
+        incident_axes[0].dot(normal),
+        incident_axes[1].dot(normal),
+        incident_axes[2].dot(normal),
+    ];
+    let mut k = 0;
+    for i in 1..3 {
+        if dots[i].abs() > dots[k].abs() {
+            k = i;
+        }
+    }
+    let half = incident.half_sizes();
+    let mut corner = [T::zero(); 3];
+    corner[k] = if dots[k] > T::zero() {
+        -half[k]
+    } else {
+        half[k]
+    };
+    let (u, v) = ((k + 1) % 3, (k + 2) % 3);
+    let mut polygon = ArrayVec::<Vec3<T>, 8>::new();
+    for &(flip_u, flip_v) in &[(false, false), (true, false), (true, true), (false, true)] {
+        corner[u] = if flip_u { -half[u] } else { half[u] };
+        corner[v] = if flip_v { -half[v] } else { half[v] };
+        polygon.push(incident.point_at(incident_axes, corner));
+    }
+    // Trim it to the sides of the reference face.
+    let reference_half = reference.half_sizes();
+    for &side in &[(face + 1) % 3, (face + 2) % 3] {
+        for &direction in &[reference_axes[side], reference_axes[side].invert()] {
+            let offset = direction.dot(reference.center) + reference_half[side];
+            polygon = clip(&polygon, &Plane::new(direction, offset));
+        }
+    }
+    let surface = Plane::new(normal, normal.dot(reference.center) + reference_half[face]);
+    polygon
+        .into_iter()
+        .filter_map(|point| {
+            let penetration = -surface.distance(point);
+            if penetration < T::zero() {
+                return None;
+            }
+            Some(Contact {
+                point,
+                normal: if flip { normal } else { normal.invert() },
+                penetration,
+            })
+        })
+        .collect()
+}
+
+/// The part of the convex `polygon` behind `plane`, which has at most one more corner than
+/// `polygon`; so `polygon` must have fewer than eight.
+fn clip<T: Float + NumAssign>(polygon: &[Vec3<T>], plane: &Plane<T>) -> ArrayVec<Vec3<T>, 8> {
+    let mut clipped = ArrayVec::new();
+    for (index, &current) in polygon.iter().enumerate() {
+        let previous = polygon[(index + polygon.len() - 1) % polygon.len()];
+        let (d_previous, d_current) = (plane.distance(previous), plane.distance(current));
+        if (d_previous < T::zero() && d_current > T::zero())
+            || (d_previous > T::zero() && d_current < T::zero())
+        {
+            let t = d_previous / (d_previous - d_current);
+            clipped.push(previous + (current - previous) * t);
+        }
+        if d_current <= T::zero() {
+            clipped.push(current);
+        }
+    }
+    clipped
+}
+
+/// Halfway between the closest points of two edges, each given by its middle, its unit
+/// direction, and its half length.
+///
+/// If the closest points of the lines through the edges fall off either edge, or the edges are
+/// parallel, the edges only touch at a corner, so the middle of the first edge is used if
+/// `prefer_a` and the middle of the second otherwise.
+fn closest_between_edges<T: Float + NumAssign>(
+    (middle_a, direction_a, half_a): (Vec3<T>, Vec3<T>, T),
+    (middle_b, direction_b, half_b): (Vec3<T>, Vec3<T>, T),
+    prefer_a: bool,
+) -> Vec3<T> {
+    let fallback = if prefer_a { middle_a } else { middle_b };
+    let cos = direction_a.dot(direction_b);
+    let between = middle_a - middle_b;
+    let (along_a, along_b) = (direction_a.dot(between), direction_b.dot(between));
+    let denominator = T::one() - cos * cos;
+    if denominator.abs() < T::from(1e-4).unwrap() {
+        return fallback;
+    }
+    let t_a = (cos * along_b - along_a) / denominator;
+    let t_b = (along_b - cos * along_a) / denominator;
+    if t_a.abs() > half_a || t_b.abs() > half_b {
+        return fallback;
+    }
+    let two = T::one() + T::one();
+    (middle_a + direction_a * t_a + middle_b + direction_b * t_b) / two
+}
+
+/// At most four of `points`, chosen to keep a manifold of contacts stable while resolving
+/// fewer of them.
+///
+/// The deepest point is always kept. The others spread out as far as they can: the point
+/// farthest from the deepest, then the one making the largest triangle with those two, then
+/// the one lying farthest outside that triangle. Four points spanning the contact area hold a
+/// box as steady as all of them would. A point that would add nothing is left out, so fewer
+/// are kept when the points all coincide, lie along a line, or lie within the first three's
+/// triangle; no point is ever kept twice.
+pub fn reduce_manifold<T: Float + NumAssign>(points: &[Contact<T>]) -> ArrayVec<Contact<T>, 4> {
+    if points.len() <= 4 {
+        return points.iter().copied().collect();
+    }
+    let mut chosen = ArrayVec::<usize, 4>::new();
+    // The best point not chosen yet, if any scores above zero.
+    let most = |chosen: &[usize], score: &dyn Fn(&Contact<T>) -> T| {
+        let mut best: Option<(usize, T)> = None;
+        for (index, point) in points.iter().enumerate() {
+            let score = score(point);
+            let better = match best {
+                Some((_, best)) => score > best,
+                None => score > T::zero(),
+            };
+            if better && !chosen.contains(&index) {
+                best = Some((index, score));
+            }
+        }
+        best.map(|(index, _)| index)
+    };
+    let reduced = |chosen: &[usize]| chosen.iter().map(|&index| points[index]).collect();
+    let mut deepest = 0;
+    for (index, point) in points.iter().enumerate() {
+        if point.penetration > points[deepest].penetration {
+            deepest = index;
+        }
+    }
+    chosen.push(deepest);
+    let a = points[deepest].point;
+    let farthest = match most(&chosen, &|point| (point.point - a).mag_squared()) {
+        Some(farthest) => farthest,
+        None => return reduced(&chosen),
+    };
+    chosen.push(farthest);
+    let b = points[farthest].point;
+    let widest = match most(&chosen, &|point| {
+        (b - a).cross(point.point - a).mag_squared()
+    }) {
+        Some(widest) => widest,
+        None => return reduced(&chosen),
+    };
+    chosen.push(widest);
+    let c = points[widest].point;
+    let normal = (b - a).cross(c - a);
+    // How far outside the triangle a point is, beyond its nearest edge; negative inside.
+    let outside = |point: &Contact<T>| {
+        let edges = [(a, b), (b, c), (c, a)];
+        edges
+            .iter()
+            .map(|&(from, to)| -(to - from).cross(point.point - from).dot(normal))
+            .fold(T::neg_infinity(), T::max)
+    };
+    if let Some(outermost) = most(&chosen, &outside) {
+        chosen.push(outermost);
+    }
+    reduced(&chosen)
+}
+
+/// When a sphere of `radius` moving from `start` to `end` first touches `plane`, as a fraction
+/// of the way along, or `None` if it never does.
+///
+/// The plane is thin, touchable from either side, so a sphere that starts out touching it
+/// returns `Some(0)`. For stopping a fast particle that would otherwise pass through a wall in a
+/// single step: sweep `Particle::integrate_reporting`'s displacement and move it only this far.
+pub fn swept_sphere_plane<T: Float + NumAssign>(
+    start: Vec3<T>,
+    end: Vec3<T>,
+    radius: T,
+    plane: &Plane<T>,
+) -> Option<T> {
+    let from = plane.distance(start);
+    let to = plane.distance(end);
+    if from.abs() <= radius {
+        return Some(T::zero());
+    }
+    // The distance at which the sphere touches, on the side it starts from.
+    let touching = if from > T::zero() { radius } else { -radius };
+    let stays_clear = if from > T::zero() {
+        to > touching
+    } else {
+        to < touching
+    };
+    if stays_clear {
+        return None;
+    }
+    Some((from - touching) / (from - to))
+}
+
+/// When two spheres, each moving steadily from its start to its end over the same step, first
+/// touch, as a fraction of the way through the step, or `None` if they never do.
+///
+/// Spheres that start out touching or overlapping return `Some(0)`. This is the moving-vs-moving
+/// counterpart of `swept_sphere_plane`, for fast particles that would pass through each other
+/// between one step and the next.
+pub fn swept_sphere_sphere<T: Float + NumAssign>(
+    start_a: Vec3<T>,
+    end_a: Vec3<T>,
+    radius_a: T,
+    start_b: Vec3<T>,
+    end_b: Vec3<T>,
+    radius_b: T,
+) -> Option<T> {
+    // In b's frame of reference, a moves from `offset` by `motion`, and they touch when its
+    // distance from b is the sum of the radii.
+    let offset = start_a - start_b;
+    let motion = (end_a - start_a) - (end_b - start_b);
+    let reach = radius_a + radius_b;
+    let c = offset.mag_squared() - reach * reach;
+    if c <= T::zero() {
+        return Some(T::zero());
+    }
+    let a = motion.mag_squared();
+    let half_b = offset.dot(motion);
+    if a.is_zero() || half_b >= T::zero() {
+        // Not moving relative to each other, or moving apart.
+        return None;
+    }
+    let discriminant = half_b * half_b - a * c;
+    if discriminant < T::zero() {
+        return None;
+    }
+    let t = (-half_b - math::sqrt(discriminant)) / a;
+    if t <= T::one() {
+        Some(t)
+    } else {
+        None
+    }
+}
+
+/// Which collisions a particle, or a piece of scenery, takes part in.
+///
+/// Two things collide only if each one's `layer` shares a bit with the other's `mask`, so
+/// either one can opt out. The default has every bit set, and collides with everything else
+/// that doesn't opt out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CollisionFilter {
+    /// The layers this belongs to, one per bit.
+    pub layer: u32,
+    /// The layers this collides with.
+    pub mask: u32,
+}
+
+impl Default for CollisionFilter {
+    fn default() -> Self {
+        Self::new(u32::MAX, u32::MAX)
+    }
+}
+
+impl CollisionFilter {
+    pub fn new(layer: u32, mask: u32) -> Self {
+        Self { layer, mask }
+    }
+
+    pub fn collides(self, other: Self) -> bool {
+        self.layer & other.mask != 0 && other.layer & self.mask != 0
+    }
+
+    /// The filter at `index`, or the default past the end of `filters`.
+    pub(crate) fn at(filters: &[Self], index: usize) -> Self {
+        filters.get(index).copied().unwrap_or_default()
+    }
+}
+
+/// Finds the radius of a particle, given its index and the particle itself.
+pub type RadiusFn<T> = Box<dyn Fn(usize, &Particle<T>) -> T>;
+
+/// Keeps particles, treated as spheres, from falling through the half-space behind `plane`.
+///
+/// Every particle whose center is within its radius of the plane, or behind it, gets a contact
+/// pushing it out along the plane's normal. That includes particles exactly touching the plane,
+/// so that one resting on it stays in contact from frame to frame. Particles have a radius of
+/// `radius` unless `with_radius_fn` gives them their own. The plane is frictionless unless
+/// `with_friction` says otherwise, and collides with every particle whose `CollisionFilter`
+/// collides with `filter`. In a `ParticleWorld`, a plane `with_material` takes its restitution
+/// and friction with particles that have materials from the world's `MaterialTable` instead.
+pub struct GroundPlaneContacts<T: Float> {
+    pub plane: Plane<T>,
+    pub radius: T,
+    pub restitution: T,
+    pub static_friction: T,
+    pub dynamic_friction: T,
+    pub filter: CollisionFilter,
+    pub material: Option<MaterialId>,
+    radius_fn: Option<RadiusFn<T>>,
+}
+
+impl<T: Float + NumAssign> GroundPlaneContacts<T> {
+    pub fn new(plane: Plane<T>, radius: T, restitution: T) -> Self {
+        Self {
+            plane,
+            radius,
+            restitution,
+            static_friction: T::zero(),
+            dynamic_friction: T::zero(),
+            filter: CollisionFilter::default(),
+            material: None,
+            radius_fn: None,
+        }
+    }
+
+    pub fn with_filter(mut self, filter: CollisionFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn with_material(mut self, material: MaterialId) -> Self {
+        self.material = Some(material);
+        self
+    }
+
+    fn radius_of(&self, index: usize, particle: &Particle<T>) -> T {
+        match &self.radius_fn {
+            Some(radius_fn) => radius_fn(index, particle),
+            None => self.radius,
+        }
+    }
+
+    /// Gives the plane's contacts these friction coefficients; see `ParticleContact`.
+    pub fn with_friction(mut self, static_friction: T, dynamic_friction: T) -> Self {
+        self.static_friction = static_friction;
+        self.dynamic_friction = dynamic_friction;
+        self
+    }
+
+    /// A flat floor at height `y`.
+    pub fn at_height(y: T, radius: T, restitution: T) -> Self {
+        let plane = Plane::new(Vec3(T::zero(), T::one(), T::zero()), y);
+        Self::new(plane, radius, restitution)
+    }
+
+    /// Finds each particle's radius with `radius_fn`, given its index and the particle.
+    pub fn with_radius_fn<F>(mut self, radius_fn: F) -> Self
+    where
+        F: Fn(usize, &Particle<T>) -> T + 'static,
+    {
+        self.radius_fn = Some(Box::new(radius_fn));
+        self
+    }
+}
+
+impl<T: Float + NumAssign> ParticleContactGenerator<T> for GroundPlaneContacts<T> {
+    fn add_contacts(
+        &self,
+        particles: &[Particle<T>],
+        contacts: &mut Vec<ParticleContact<T>>,
+        limit: usize,
+    ) -> usize {
+        self.add_filtered_contacts(particles, &[], contacts, limit)
+    }
+
+    fn add_filtered_contacts(
+        &self,
+        particles: &[Particle<T>],
+        filters: &[CollisionFilter],
+        contacts: &mut Vec<ParticleContact<T>>,
+        limit: usize,
+    ) -> usize {
+        let mut added = 0;
+        for (index, particle) in particles.iter().enumerate() {
+            if added == limit {
+                break;
+            }
+            if !self.filter.collides(CollisionFilter::at(filters, index)) {
+                continue;
+            }
+            let radius = self.radius_of(index, particle);
+            let penetration = radius - self.plane.distance(particle.position);
+            if penetration >= T::zero() {
+                contacts.push(ParticleContact {
+                    particles: (index, None),
+                    restitution: self.restitution,
+                    contact_normal: self.plane.normal,
+                    penetration,
+                    static_friction: self.static_friction,
+                    dynamic_friction: self.dynamic_friction,
+                });
+                added += 1;
+            }
+        }
+        added
+    }
+
+    /// Catches particles that pass from in front of the plane to behind it in a single step.
+    fn sweep(
+        &self,
+        particles: &[Particle<T>],
+        starts: &[Vec3<T>],
+        filters: &[CollisionFilter],
+        index: usize,
+    ) -> Option<(T, ParticleContact<T>)> {
+        if !self.filter.collides(CollisionFilter::at(filters, index)) {
+            return None;
+        }
+        let (start, particle) = (starts[index], &particles[index]);
+        let radius = self.radius_of(index, particle);
+        let touching_at_start = self.plane.distance(start) <= radius;
+        if touching_at_start || self.plane.distance(particle.position) >= radius {
+            return None;
+        }
+        let t = swept_sphere_plane(start, particle.position, radius, &self.plane)?;
+        let contact = ParticleContact {
+            particles: (index, None),
+            restitution: self.restitution,
+            contact_normal: self.plane.normal,
+            penetration: T::zero(),
+            static_friction: self.static_friction,
+            dynamic_friction: self.dynamic_friction,
+        };
+        Some((t, contact))
+    }
+
+    fn uses_materials(&self) -> bool {
+        true
+    }
+
+    fn scenery_material(&self) -> Option<MaterialId> {
+        self.material
+    }
+}
+
+/// Spheres centered on particles, which bounce off one another.
+///
+/// Each entry of `spheres` is a particle's index and its sphere's radius. Every pair of spheres
+/// that overlap, or exactly touch, gets a contact pushing them apart along the line between
+/// their centers. As a contact generator this checks every pair, which is fine for a few dozen
+/// spheres; for more, use `with_spatial_hash`, or find the pairs that might overlap with some
+/// other broad phase and pass them to `add_pair_contacts`. Spheres are frictionless unless
+/// `with_friction` says otherwise. In a `ParticleWorld`, two spheres whose particles both have
+/// materials take their restitution and friction from the world's `MaterialTable` instead.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParticleSphereCollisions<T: Float> {
+    pub spheres: Vec<(usize, T)>,
+    pub restitution: T,
+    pub static_friction: T,
+    pub dynamic_friction: T,
+    /// The cell size of the `SpatialHash` to find pairs with, if any.
+    cell_size: Option<T>,
+}
+
+impl<T: Float + NumAssign> ParticleSphereCollisions<T> {
+    pub fn new(restitution: T) -> Self {
+        Self {
+            spheres: Vec::new(),
+            restitution,
+            static_friction: T::zero(),
+            dynamic_friction: T::zero(),
+            cell_size: None,
+        }
+    }
+
+    /// Gives the spheres' contacts these friction coefficients; see `ParticleContact`.
+    pub fn with_friction(mut self, static_friction: T, dynamic_friction: T) -> Self {
+        self.static_friction = static_friction;
+        self.dynamic_friction = dynamic_friction;
+        self
+    }
+
+    /// As a contact generator, only checks the pairs a `SpatialHash` of `cell_size` finds.
+    pub fn with_spatial_hash(mut self, cell_size: T) -> Self {
+        self.cell_size = Some(cell_size);
+        self
+    }
+
+    /// Adds a sphere of `radius` around the particle at `index`.
+    pub fn add(&mut self, index: usize, radius: T) {
+        self.spheres.push((index, radius));
+    }
+
+    /// Like `add_contacts`, but only checks `pairs`, which are indices into `spheres`.
+    ///
+    /// Spheres whose centers coincide have no line between them, so they're pushed apart along
+    /// +Y instead.
+    pub fn add_pair_contacts<I>(
+        &self,
+        particles: &[Particle<T>],
+        pairs: I,
+        contacts: &mut Vec<ParticleContact<T>>,
+        limit: usize,
+    ) -> usize
+    where
+        I: IntoIterator<Item = (usize, usize)>,
+    {
+        let mut added = 0;
+        for (a, b) in pairs {
+            if added == limit {
+                break;
+            }
+            let ((a, radius_a), (b, radius_b)) = (self.spheres[a], self.spheres[b]);
+            let offset = particles[a].position - particles[b].position;
+            let distance = offset.mag();
+            let penetration = radius_a + radius_b - distance;
+            if penetration < T::zero() {
+                continue;
+            }
+            let contact_normal = if distance.is_zero() {
+                Vec3(T::zero(), T::one(), T::zero())
+            } else {
+                offset / distance
+            };
+            contacts.push(ParticleContact {
+                particles: (a, Some(b)),
+                restitution: self.restitution,
+                contact_normal,
+                penetration,
+                static_friction: self.static_friction,
+                dynamic_friction: self.dynamic_friction,
+            });
+            added += 1;
+        }
+        added
+    }
+}
+
+impl<T: Float + NumAssign> ParticleContactGenerator<T> for ParticleSphereCollisions<T> {
+    fn add_contacts(
+        &self,
+        particles: &[Particle<T>],
+        contacts: &mut Vec<ParticleContact<T>>,
+        limit: usize,
+    ) -> usize {
+        self.add_filtered_contacts(particles, &[], contacts, limit)
+    }
+
+    /// Pairs of spheres whose particles' filters don't collide are skipped as soon as the
+    /// broad phase finds them.
+    fn add_filtered_contacts(
+        &self,
+        particles: &[Particle<T>],
+        filters: &[CollisionFilter],
+        contacts: &mut Vec<ParticleContact<T>>,
+        limit: usize,
+    ) -> usize {
+        let collides = |&(a, b): &(usize, usize)| {
+            let filter = |sphere: usize| CollisionFilter::at(filters, self.spheres[sphere].0);
+            filter(a).collides(filter(b))
+        };
+        if let Some(cell_size) = self.cell_size {
+            let positions: Vec<_> = self
+                .spheres
+                .iter()
+                .map(|&(index, _)| particles[index].position)
+                .collect();
+            let radii: Vec<_> = self.spheres.iter().map(|&(_, radius)| radius).collect();
+            let mut hash = SpatialHash::new(cell_size);
+            hash.rebuild(&positions, &radii);
+            let pairs = hash.candidate_pairs().filter(collides);
+            return self.add_pair_contacts(particles, pairs, contacts, limit);
+        }
+        let n = self.spheres.len();
+        let pairs = (0..n).flat_map(|a| (a + 1..n).map(move |b| (a, b)));
+        self.add_pair_contacts(particles, pairs.filter(collides), contacts, limit)
+    }
+
+    /// Checks the particle's spheres against every other sphere, both moving in straight lines
+    /// over the step, with `swept_sphere_sphere`.
+    fn sweep(
+        &self,
+        particles: &[Particle<T>],
+        starts: &[Vec3<T>],
+        filters: &[CollisionFilter],
+        index: usize,
+    ) -> Option<(T, ParticleContact<T>)> {
+        let mut earliest: Option<(T, ParticleContact<T>)> = None;
+        for &(a, radius_a) in self.spheres.iter().filter(|&&(a, _)| a == index) {
+            for &(b, radius_b) in &self.spheres {
+                let filter = |index| CollisionFilter::at(filters, index);
+                if b == index || !filter(a).collides(filter(b)) {
+                    continue;
+                }
+                let (end_a, end_b) = (particles[a].position, particles[b].position);
+                let hit =
+                    swept_sphere_sphere(starts[a], end_a, radius_a, starts[b], end_b, radius_b);
+                let t = match hit {
+                    Some(t) if t > T::zero() => t,
+                    _ => continue,
+                };
+                if let Some((best, _)) = earliest {
+                    if best <= t {
+                        continue;
+                    }
+                }
+                let offset =
+                    starts[a] + (end_a - starts[a]) * t - (starts[b] + (end_b - starts[b]) * t);
+                let distance = offset.mag();
+                let contact_normal = if distance.is_zero() {
+                    Vec3(T::zero(), T::one(), T::zero())
+                } else {
+                    offset / distance
+                };
+                let contact = ParticleContact {
+                    particles: (a, Some(b)),
+                    restitution: self.restitution,
+                    contact_normal,
+                    penetration: T::zero(),
+                    static_friction: self.static_friction,
+                    dynamic_friction: self.dynamic_friction,
+                };
+                earliest = Some((t, contact));
+            }
+        }
+        earliest
+    }
+
+    fn uses_materials(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bounds::BoundingBox;
+    use crate::collide::{
+        box_and_box, capsule_and_half_space, reduce_manifold, swept_sphere_plane,
+        swept_sphere_sphere, Capsule, CollisionBox, CollisionFilter, Contact, GroundPlaneContacts,
+        ParticleSphereCollisions, Plane,
+    };
+    use crate::contact::{ParticleContact, ParticleContactGenerator, ParticleContactResolver};
+    use crate::particle::Particle;
+    use crate::quaternion::Quaternion;
+    use crate::vec::Vec3;
+    use crate::world::ParticleWorld;
+    use assert_approx_eq::assert_approx_eq;
+
+    fn unit_box(center: Vec3<f64>, orientation: Quaternion<f64>) -> CollisionBox<f64> {
+        CollisionBox::new(center, orientation, Vec3(1.0, 1.0, 1.0))
+    }
+
+    #[test]
+    fn box_on_box() {
+        let a = unit_box(Vec3(0.0, 0.0, 0.0), Quaternion::identity());
+        let b = unit_box(Vec3(0.3, 1.9, 0.0), Quaternion::identity());
+        let contacts = box_and_box(&a, &b);
+        // The overlap is the part of b's bottom face over a, x from -0.7 to 1.
+        assert_eq!(contacts.len(), 4);
+        for contact in &contacts {
+            assert_eq!(contact.normal, Vec3(0.0, -1.0, 0.0));
+            assert_approx_eq!(contact.penetration, 0.1);
+            assert_approx_eq!(contact.point.1, 0.9);
+            assert_approx_eq!(contact.point.2.abs(), 1.0);
+            let x = contact.point.0;
+            assert!((x - -0.7).abs() < 1e-9 || (x - 1.0).abs() < 1e-9);
+        }
+        for contact in box_and_box(&b, &a) {
+            assert_eq!(contact.normal, Vec3(0.0, 1.0, 0.0));
+        }
+        let c = unit_box(Vec3(0.3, 2.1, 0.0), Quaternion::identity());
+        assert!(box_and_box(&a, &c).is_empty());
+    }
+
+    #[test]
+    fn box_nan() {
+        let a = unit_box(Vec3(0.0, 0.0, 0.0), Quaternion::identity());
+        let b = unit_box(Vec3(0.3, f64::NAN, 0.0), Quaternion::identity());
+        assert!(box_and_box(&a, &b).is_empty());
+        let c = unit_box(
+            Vec3(0.3, 1.9, 0.0),
+            Quaternion::new(f64::NAN, 0.0, 0.0, 0.0),
+        );
+        assert!(box_and_box(&a, &c).is_empty());
+    }
+
+    #[test]
+    fn box_edge_on_edge() {
+        let (sin, cos) = (std::f64::consts::PI / 8.0).sin_cos();
+        let root_two = 2.0_f64.sqrt();
+        // a's top edge runs along z, and b's bottom edge along x, crossing just below it.
+        let a = unit_box(Vec3(0.0, 0.0, 0.0), Quaternion::new(cos, 0.0, 0.0, sin));
+        let b = unit_box(
+            Vec3(0.0, 2.0 * root_two - 0.1, 0.0),
+            Quaternion::new(cos, sin, 0.0, 0.0),
+        );
+        let contacts = box_and_box(&a, &b);
+        assert_eq!(contacts.len(), 1);
+        let contact = contacts[0];
+        assert_approx_eq!(contact.penetration, 0.1);
+        assert!(contact
+            .normal
+            .approx_eq_relative(Vec3(0.0, -1.0, 0.0), 1e-9));
+        assert!(contact
+            .point
+            .approx_eq_relative(Vec3(0.0, root_two - 0.05, 0.0), 1e-9));
+    }
+
+    #[test]
+    fn manifold() {
+        let mut points: Vec<_> = [-1.0, 0.0, 1.0]
+            .iter()
+            .flat_map(|&x| [-1.0, 0.0, 1.0].iter().map(move |&z| Vec3(x, 0.0, z)))
+            .filter(|&point| point != Vec3(0.0, 0.0, 0.0))
+            .map(|point| Contact {
+                point,
+                normal: Vec3(0.0, 1.0, 0.0),
+                penetration: 0.1,
+            })
+            .collect();
+        points[6].penetration = 0.3;
+        let deepest = points[6];
+        assert_eq!(deepest.point, Vec3(1.0, 0.0, 0.0));
+        let reduced = reduce_manifold(&points);
+        assert_eq!(reduced.len(), 4);
+        assert!(reduced.contains(&deepest));
+        // The rest reach the far side of the square, spanning all of it.
+        let xs: Vec<_> = reduced.iter().map(|contact| contact.point.0).collect();
+        let zs: Vec<_> = reduced.iter().map(|contact| contact.point.2).collect();
+        assert!(xs.contains(&-1.0) && xs.contains(&1.0));
+        assert!(zs.contains(&-1.0) && zs.contains(&1.0));
+        for (i, a) in reduced.iter().enumerate() {
+            assert!(reduced[i + 1..].iter().all(|b| a != b));
+        }
+        assert_eq!(reduce_manifold(&points[..3]).len(), 3);
+    }
+
+    fn contacts_at(points: &[Vec3<f64>]) -> Vec<Contact<f64>> {
+        points
+            .iter()
+            .map(|&point| Contact {
+                point,
+                normal: Vec3(0.0, 1.0, 0.0),
+                penetration: 0.1,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn manifold_interior() {
+        // A triangle with points inside it and along its edges, which add nothing to it.
+        let points = contacts_at(&[
+            Vec3(0.0, 0.0, 0.0),
+            Vec3(4.0, 0.0, 0.0),
+            Vec3(0.0, 0.0, 4.0),
+            Vec3(1.0, 0.0, 1.0),
+            Vec3(2.0, 0.0, 0.0),
+            Vec3(0.5, 0.0, 2.0),
+        ]);
+        let reduced = reduce_manifold(&points);
+        assert_eq!(reduced.len(), 3);
+        for corner in &points[..3] {
+            assert!(reduced.contains(corner));
+        }
+    }
+
+    #[test]
+    fn manifold_degenerate() {
+        let line: Vec<_> = (0..6).map(|x| Vec3(f64::from(x), 0.0, 0.0)).collect();
+        let reduced = reduce_manifold(&contacts_at(&line));
+        assert_eq!(reduced.len(), 2);
+        assert_ne!(reduced[0], reduced[1]);
+        let same = reduce_manifold(&contacts_at(&[Vec3(1.0, 2.0, 3.0); 5]));
+        assert_eq!(same.len(), 1);
+    }
+
+    #[test]
+    fn plane_distance() {
+        let plane = Plane::new(Vec3(0.0, 1.0, 0.0), 2.0);
+        assert_eq!(plane.distance(Vec3(5.0, 3.0, 5.0)), 1.0);
+        assert_eq!(plane.distance(Vec3(5.0, 0.0, 5.0)), -2.0);
+    }
+
+    #[test]
+    fn capsule_aabb() {
+        let capsule = Capsule::new(Vec3(1.0, 0.0, 0.0), Vec3(-1.0, 2.0, 0.0), 0.5);
+        let aabb = capsule.aabb();
+        assert_eq!(aabb.min, Vec3(-1.5, -0.5, -0.5));
+        assert_eq!(aabb.max, Vec3(1.5, 2.5, 0.5));
+    }
+
+    #[test]
+    fn box_aabb() {
+        let aligned = CollisionBox::new(
+            Vec3(1.0, 2.0, 3.0),
+            Quaternion::identity(),
+            Vec3(0.5, 1.0, 2.0),
+        );
+        let aabb = aligned.aabb();
+        assert_eq!(aabb.min, Vec3(0.5, 1.0, 1.0));
+        assert_eq!(aabb.max, Vec3(1.5, 3.0, 5.0));
+        // Turned 45° about y, the unit box's corners reach √2 along x and z.
+        let turned = unit_box(
+            Vec3(0.0, 0.0, 0.0),
+            Quaternion::from_axis_angle(Vec3(0.0, 1.0, 0.0), std::f64::consts::FRAC_PI_4),
+        );
+        let aabb = turned.aabb();
+        let root_two = 2.0_f64.sqrt();
+        assert!(aabb
+            .max
+            .approx_eq_relative(Vec3(root_two, 1.0, root_two), 1e-9));
+        assert!(aabb.min.approx_eq_relative(aabb.max.invert(), 1e-9));
+    }
+
+    #[test]
+    fn capsule_lying_flat() {
+        let plane = Plane::new(Vec3(0.0_f64, 1.0, 0.0), 0.0);
+        let capsule = Capsule::new(Vec3(-1.0, 0.4, 0.0), Vec3(1.0, 0.4, 0.0), 0.5);
+        let contacts = capsule_and_half_space(&capsule, &plane);
+        assert_eq!(contacts.len(), 2);
+        for (contact, x) in contacts.iter().zip(&[-1.0, 1.0]) {
+            assert_eq!(contact.normal, Vec3(0.0, 1.0, 0.0));
+            assert_approx_eq!(contact.penetration, 0.1);
+            assert_approx_eq!(contact.point.0, *x);
+            assert_approx_eq!(contact.point.1, 0.0);
+        }
+    }
+
+    #[test]
+    fn capsule_standing() {
+        let plane = Plane::new(Vec3(0.0_f64, 1.0, 0.0), 0.0);
+        let capsule = Capsule::new(Vec3(0.0, 3.0, 0.0), Vec3(0.0, 0.25, 0.0), 0.5);
+        let contacts = capsule_and_half_space(&capsule, &plane);
+        assert_eq!(contacts.len(), 1);
+        assert_approx_eq!(contacts[0].penetration, 0.25);
+        assert_eq!(contacts[0].point, Vec3(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn capsule_above() {
+        let plane = Plane::new(Vec3(0.0_f64, 1.0, 0.0), 0.0);
+        let capsule = Capsule::new(Vec3(0.0, 3.0, 0.0), Vec3(0.0, 0.5, 0.0), 0.5);
+        assert!(capsule_and_half_space(&capsule, &plane).is_empty());
+    }
+
+    #[test]
+    fn swept_sphere_tunneling() {
+        let plane = Plane::new(Vec3(0.0_f64, 1.0, 0.0), 1.0);
+        let start = Vec3(0.0, 6.0, 0.0);
+        let end = Vec3(2.0, -4.0, 0.0);
+        // The sphere's bottom reaches the plane after falling 4.5 of the 10.
+        let t = swept_sphere_plane(start, end, 0.5, &plane).unwrap();
+        assert_approx_eq!(t, 0.45);
+        let hit = start + (end - start) * t;
+        assert_approx_eq!(plane.distance(hit), 0.5);
+        // From below, the sphere's top hits first.
+        assert_approx_eq!(swept_sphere_plane(end, start, 0.5, &plane).unwrap(), 0.45);
+    }
+
+    #[test]
+    fn swept_sphere_misses() {
+        let plane = Plane::new(Vec3(0.0_f64, 1.0, 0.0), 0.0);
+        let sweep = |from, to| swept_sphere_plane(from, to, 0.5, &plane);
+        assert_eq!(sweep(Vec3(0.0, 3.0, 0.0), Vec3(0.0, 1.0, 0.0)), None);
+        assert_eq!(sweep(Vec3(0.0, 3.0, 0.0), Vec3(9.0, 3.0, 0.0)), None);
+        assert_eq!(sweep(Vec3(0.0, -3.0, 0.0), Vec3(0.0, -0.6, 0.0)), None);
+        assert_eq!(sweep(Vec3(0.0, 0.2, 0.0), Vec3(0.0, 9.0, 0.0)), Some(0.0));
+        assert_eq!(sweep(Vec3(0.0, 2.5, 0.0), Vec3(0.0, 0.5, 0.0)), Some(1.0));
+    }
+
+    #[test]
+    fn swept_spheres_head_on() {
+        // Closing at 20 per step from 10 apart, they'd pass straight through each other.
+        let (start_a, end_a) = (Vec3(-5.0_f64, 0.0, 0.0), Vec3(5.0, 0.0, 0.0));
+        let (start_b, end_b) = (Vec3(5.0, 0.0, 0.0), Vec3(-5.0, 0.0, 0.0));
+        let t = swept_sphere_sphere(start_a, end_a, 0.5, start_b, end_b, 1.5).unwrap();
+        // They touch when 2 apart, after closing 8 of the 20.
+        assert_approx_eq!(t, 0.4);
+        let a = start_a + (end_a - start_a) * t;
+        let b = start_b + (end_b - start_b) * t;
+        assert_approx_eq!((a - b).mag(), 2.0);
+        // Only one moving, at an angle.
+        let t = swept_sphere_sphere(
+            Vec3(0.0_f64, 3.0, 0.0),
+            Vec3(0.0, -3.0, 0.0),
+            0.5,
+            Vec3(0.6, 0.0, 0.0),
+            Vec3(0.6, 0.0, 0.0),
+            0.5,
+        );
+        assert_approx_eq!(t.unwrap(), (3.0 - 0.8) / 6.0);
+    }
+
+    #[test]
+    fn swept_spheres_miss() {
+        let still = Vec3(0.0_f64, 0.0, 0.0);
+        let sweep = |from, to| swept_sphere_sphere(from, to, 0.5, still, still, 0.5);
+        assert_eq!(sweep(Vec3(5.0, 0.0, 0.0), Vec3(2.0, 0.0, 0.0)), None);
+        assert_eq!(sweep(Vec3(2.0, 0.0, 0.0), Vec3(9.0, 0.0, 0.0)), None);
+        assert_eq!(sweep(Vec3(-5.0, 1.5, 0.0), Vec3(5.0, 1.5, 0.0)), None);
+        assert_eq!(sweep(Vec3(3.0, 0.0, 0.0), Vec3(3.0, 0.0, 0.0)), None);
+        assert_eq!(sweep(Vec3(0.5, 0.0, 0.0), Vec3(9.0, 0.0, 0.0)), Some(0.0));
+        assert_eq!(sweep(Vec3(3.0, 0.0, 0.0), Vec3(1.0, 0.0, 0.0)), Some(1.0));
+    }
+
+    #[test]
+    fn ground_bounce() {
+        let mut world = ParticleWorld::new(4).with_iterations(2);
+        world.add_particle(Particle {
+            position: Vec3(0.0, 2.5, 0.0),
+            acceleration: Vec3(0.0, -10.0, 0.0),
+            ..Particle::default()
+        });
+        world.add_contact_generator(GroundPlaneContacts::at_height(0.0, 0.5, 0.5));
+        let mut bounced = false;
+        let mut peak = 0.0_f64;
+        for step in 0..10_000 {
+            world.integrate(0.001);
+            if world.generate_contacts() > 0 {
+                bounced = true;
+            } else if bounced && peak.is_finite() {
+                peak = peak.max(world.particles()[0].position.1);
+                if world.particles()[0].velocity.1 < 0.0 {
+                    // Only the first bounce.
+                    assert_approx_eq!(peak - 0.5, 2.0 * 0.25, 0.02);
+                    peak = f64::INFINITY;
+                }
+            }
+            world.resolve_contacts(0.001);
+            let particle = world.particles()[0];
+            assert!(particle.position.1 > 0.5 - 0.05);
+            if step >= 9000 {
+                // Resting, give or take a couple of frames' worth of gravity.
+                assert_approx_eq!(particle.position.1, 0.5, 1e-4);
+                assert_approx_eq!(particle.velocity.1, 0.0, 0.02);
+            }
+        }
+        assert!(peak.is_infinite());
+    }
+
+    /// A particle after two seconds on a plane tilted by `angle` about +Z, sloping down
+    /// toward +X, with its velocity along the slope.
+    fn on_slope(angle: f64, static_friction: f64, dynamic_friction: f64) -> (Particle<f64>, f64) {
+        let normal = Vec3(angle.sin(), angle.cos(), 0.0);
+        let downhill = Vec3(angle.cos(), -angle.sin(), 0.0);
+        let ground = GroundPlaneContacts::new(Plane::new(normal, 0.0), 0.5, 0.0)
+            .with_friction(static_friction, dynamic_friction);
+        let mut world = ParticleWorld::new(1);
+        // Slightly sunk into the slope, within the slop, so it never loses contact.
+        world.resolver_mut().slop = 0.01;
+        world.add_particle(Particle {
+            position: normal * 0.495,
+            acceleration: Vec3(0.0, -10.0, 0.0),
+            ..Particle::default()
+        });
+        world.add_contact_generator(ground);
+        for _ in 0..200 {
+            world.start_frame();
+            world.run_physics(0.01);
+            assert_approx_eq!(world.particles()[0].position.dot(normal), 0.495, 1e-9);
+        }
+        let particle = world.particles()[0];
+        (particle, particle.velocity.dot(downhill))
+    }
+
+    #[test]
+    fn friction_holds_on_shallow_slope() {
+        // tan(20°) is about 0.36.
+        let angle = 20_f64.to_radians();
+        let (particle, speed) = on_slope(angle, 0.5, 0.4);
+        assert_approx_eq!(speed, 0.0, 1e-12);
+        let start = Vec3(angle.sin(), angle.cos(), 0.0) * 0.495;
+        assert!(particle.position.approx_eq_relative(start, 1e-9));
+        // Without friction it slides away.
+        let (_, speed) = on_slope(angle, 0.0, 0.0);
+        assert_approx_eq!(speed, 2.0 * 10.0 * angle.sin(), 1e-9);
+    }
+
+    #[test]
+    fn friction_slows_on_steep_slope() {
+        // tan(40°) is about 0.84.
+        let angle = 40_f64.to_radians();
+        let (_, speed) = on_slope(angle, 0.5, 0.3);
+        let acceleration = 10.0 * (angle.sin() - 0.3 * angle.cos());
+        assert_approx_eq!(speed, 2.0 * acceleration, 1e-9);
+    }
+
+    #[test]
+    fn ground_radii() {
+        let particles = vec![
+            Particle {
+                position: Vec3(0.0, 3.0_f64, 0.0),
+                ..Particle::default()
+            },
+            Particle {
+                position: Vec3(0.0, 0.8, 0.0),
+                ..Particle::default()
+            },
+            Particle {
+                position: Vec3(0.0, -1.0, 0.0),
+                ..Particle::default()
+            },
+        ];
+        let ground = GroundPlaneContacts::at_height(0.5, 0.5, 0.0);
+        let mut contacts = Vec::new();
+        assert_eq!(ground.add_contacts(&particles, &mut contacts, 10), 2);
+        assert_eq!(contacts[0].particles, (1, None));
+        assert_approx_eq!(contacts[0].penetration, 0.2);
+        assert_approx_eq!(contacts[1].penetration, 2.0);
+        let ground = ground.with_radius_fn(|index, _| if index == 0 { 3.0 } else { 0.1 });
+        contacts.clear();
+        assert_eq!(ground.add_contacts(&particles, &mut contacts, 10), 2);
+        assert_eq!(contacts[0].particles, (0, None));
+        assert_approx_eq!(contacts[0].penetration, 0.5);
+        assert_eq!(contacts[1].particles, (2, None));
+    }
+
+    fn spheres(
+        positions: &[f64],
+        velocities: &[f64],
+    ) -> (Vec<Particle<f64>>, ParticleSphereCollisions<f64>) {
+        let mut collisions = ParticleSphereCollisions::new(1.0);
+        let particles = positions
+            .iter()
+            .zip(velocities)
+            .enumerate()
+            .map(|(index, (&x, &vx))| {
+                collisions.add(index, 1.0);
+                Particle {
+                    position: Vec3(x, 0.0, 0.0),
+                    velocity: Vec3(vx, 0.0, 0.0),
+                    ..Particle::default()
+                }
+            })
+            .collect();
+        (particles, collisions)
+    }
+
+    #[test]
+    fn spheres_separate() {
+        let (mut particles, collisions) = spheres(&[-0.5, 0.5, 10.0], &[0.0, 0.0, 0.0]);
+        let mut contacts = Vec::new();
+        assert_eq!(collisions.add_contacts(&particles, &mut contacts, 10), 1);
+        assert_eq!(contacts[0].contact_normal, Vec3(-1.0, 0.0, 0.0));
+        assert_eq!(contacts[0].penetration, 1.0);
+        ParticleContactResolver::new(4, 0.0, 1.0).resolve_contacts(
+            &mut contacts,
+            &mut particles,
+            0.01,
+        );
+        assert_approx_eq!(particles[0].position.0, -1.0);
+        assert_approx_eq!(particles[1].position.0, 1.0);
+        assert_eq!(particles[2].position.0, 10.0);
+    }
+
+    #[test]
+    fn spheres_swap_velocities() {
+        let (mut particles, collisions) = spheres(&[-1.0, 0.9], &[1.0, -3.0]);
+        let mut contacts = Vec::new();
+        collisions.add_contacts(&particles, &mut contacts, 10);
+        ParticleContactResolver::new(4, 0.0, 1.0).resolve_contacts(
+            &mut contacts,
+            &mut particles,
+            0.01,
+        );
+        assert_approx_eq!(particles[0].velocity.0, -3.0);
+        assert_approx_eq!(particles[1].velocity.0, 1.0);
+    }
+
+    #[test]
+    fn spheres_coincident() {
+        let (mut particles, collisions) = spheres(&[2.0, 2.0], &[0.0, 0.0]);
+        let mut contacts = Vec::new();
+        collisions.add_contacts(&particles, &mut contacts, 10);
+        assert_eq!(contacts[0].contact_normal.mag(), 1.0);
+        assert_eq!(contacts[0].penetration, 2.0);
+        ParticleContactResolver::new(4, 0.0, 1.0).resolve_contacts(
+            &mut contacts,
+            &mut particles,
+            0.01,
+        );
+        let distance = (particles[1].position - particles[0].position).mag();
+        assert_approx_eq!(distance, 2.0);
+    }
+
+    #[test]
+    fn spheres_hashed() {
+        let positions: Vec<_> = (0..40).map(|i| f64::from(i) * 0.37 % 5.0).collect();
+        let (particles, collisions) = spheres(&positions, &vec![0.0; 40]);
+        let (mut every, mut hashed) = (Vec::new(), Vec::new());
+        collisions.add_contacts(&particles, &mut every, 1000);
+        let collisions = collisions.with_spatial_hash(2.0);
+        collisions.add_contacts(&particles, &mut hashed, 1000);
+        let key = |contact: &ParticleContact<f64>| contact.particles;
+        every.sort_by_key(key);
+        hashed.sort_by_key(key);
+        assert!(!every.is_empty());
+        assert_eq!(every, hashed);
+    }
+
+    #[test]
+    fn sphere_pairs() {
+        let (particles, collisions) = spheres(&[0.0, 0.5, 1.0], &[0.0, 0.0, 0.0]);
+        let mut contacts = Vec::new();
+        assert_eq!(collisions.add_contacts(&particles, &mut contacts, 10), 3);
+        assert_eq!(collisions.add_contacts(&particles, &mut contacts, 2), 2);
+        contacts.clear();
+        let added = collisions.add_pair_contacts(&particles, vec![(2, 0)], &mut contacts, 10);
+        assert_eq!(added, 1);
+        assert_eq!(contacts[0].particles, (2, Some(0)));
+        assert_eq!(contacts[0].contact_normal, Vec3(1.0, 0.0, 0.0));
+    }
+
+    const PLAYER: u32 = 1;
+    const DEBRIS: u32 = 2;
+    const SCENERY: u32 = 4;
+
+    #[test]
+    fn collision_filters() {
+        let everything = CollisionFilter::default();
+        let player = CollisionFilter::new(PLAYER, u32::MAX);
+        let debris = CollisionFilter::new(DEBRIS, PLAYER | SCENERY);
+        assert!(everything.collides(everything));
+        assert!(player.collides(debris));
+        assert!(debris.collides(everything));
+        assert!(!debris.collides(debris));
+        // Both have to accept the other: the ghost's mask takes in debris, but not the reverse.
+        let ghost = CollisionFilter::new(SCENERY << 1, DEBRIS);
+        assert!(!ghost.collides(debris) && !debris.collides(ghost));
+        assert!(!CollisionFilter::new(0, u32::MAX).collides(everything));
+    }
+
+    #[test]
+    fn filtered_spheres() {
+        // Three overlapping spheres: a player and two pieces of debris.
+        let (particles, collisions) = spheres(&[0.0, 0.5, 1.0], &[0.0, 0.0, 0.0]);
+        let filters = [
+            CollisionFilter::new(PLAYER, u32::MAX),
+            CollisionFilter::new(DEBRIS, PLAYER | SCENERY),
+            CollisionFilter::new(DEBRIS, PLAYER | SCENERY),
+        ];
+        for collisions in &[collisions.clone(), collisions.with_spatial_hash(2.0)] {
+            let mut contacts = Vec::new();
+            collisions.add_filtered_contacts(&particles, &filters, &mut contacts, 10);
+            let mut pairs: Vec<_> = contacts.iter().map(|contact| contact.particles).collect();
+            pairs.sort();
+            assert_eq!(pairs, vec![(0, Some(1)), (0, Some(2))]);
+            // Particles without a filter collide with everything.
+            contacts.clear();
+            collisions.add_filtered_contacts(&particles, &filters[..1], &mut contacts, 10);
+            assert_eq!(contacts.len(), 3);
+        }
+    }
+
+    #[test]
+    fn filtered_ground() {
+        let particles = vec![Particle::default(); 3];
+        let filters = [
+            CollisionFilter::new(PLAYER, u32::MAX),
+            CollisionFilter::new(DEBRIS, PLAYER),
+        ];
+        let ground = GroundPlaneContacts::at_height(0.0, 0.5, 0.0)
+            .with_filter(CollisionFilter::new(SCENERY, u32::MAX));
+        let mut contacts = Vec::new();
+        ground.add_filtered_contacts(&particles, &filters, &mut contacts, 10);
+        let indices: Vec<_> = contacts.iter().map(|contact| contact.particles.0).collect();
+        assert_eq!(indices, vec![0, 2]);
+        contacts.clear();
+        assert_eq!(ground.add_contacts(&particles, &mut contacts, 10), 3);
+    }
+}
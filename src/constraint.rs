@@ -0,0 +1,90 @@
+use crate::particle::Particle;
+use crate::vec::Vec3;
+use num::Float;
+use num_traits::NumAssign;
+
+/// Holds two particles exactly `length` apart.
+#[allow(clippy::module_name_repetitions)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DistanceConstraint<T: Float> {
+    pub particles: (usize, usize),
+    pub length: T,
+}
+
+impl<T: Float + NumAssign> DistanceConstraint<T> {
+    pub fn new(a: usize, b: usize, length: T) -> Self {
+        Self {
+            particles: (a, b),
+            length,
+        }
+    }
+
+    /// How much longer the constraint currently is than it should be, and the unit direction
+    /// from the second particle to the first. The direction is zero for coincident particles.
+    pub fn error(&self, particles: &[Particle<T>]) -> (T, Vec3<T>) {
+        let offset = particles[self.particles.0].position - particles[self.particles.1].position;
+        (offset.mag() - self.length, offset.norm())
+    }
+}
+
+/// Corrects constraints one at a time, sweeping over all of them `iterations` times.
+///
+/// Each correction moves both particles (in proportion to inverse mass) to fix that one
+/// constraint exactly and removes their relative velocity along it, which may disturb
+/// constraints corrected earlier in the sweep. Networks of many interconnected constraints
+/// need many sweeps to settle.
+pub fn solve_iterative<T: Float + NumAssign>(
+    particles: &mut [Particle<T>],
+    constraints: &[DistanceConstraint<T>],
+    iterations: usize,
+) {
+    for _ in 0..iterations {
+        for constraint in constraints {
+            let (a, b) = constraint.particles;
+            let total_inverse_mass = particles[a].inverse_mass + particles[b].inverse_mass;
+            if total_inverse_mass <= T::zero() {
+                continue;
+            }
+            let (error, normal) = constraint.error(particles);
+            let correction = normal * (error / total_inverse_mass);
+            let closing = (particles[a].velocity - particles[b].velocity).dot(normal);
+            let impulse = normal * (closing / total_inverse_mass);
+            let (inverse_mass_a, inverse_mass_b) =
+                (particles[a].inverse_mass, particles[b].inverse_mass);
+            particles[a].position -= correction * inverse_mass_a;
+            particles[b].position += correction * inverse_mass_b;
+            particles[a].velocity -= impulse * inverse_mass_a;
+            particles[b].velocity += impulse * inverse_mass_b;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constraint::{solve_iterative, DistanceConstraint};
+    use crate::particle::Particle;
+    use crate::vec::Vec3;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn single() {
+        let mut particles = vec![
+            Particle {
+                velocity: Vec3(1.0, 1.0, 0.0),
+                inverse_mass: 0.0,
+                ..Particle::default()
+            },
+            Particle {
+                position: Vec3(3.0_f64, 0.0, 0.0),
+                velocity: Vec3(-2.0, 1.0, 0.0),
+                ..Particle::default()
+            },
+        ];
+        let constraint = DistanceConstraint::new(0, 1, 2.0);
+        assert_approx_eq!(constraint.error(&particles).0, 1.0);
+        solve_iterative(&mut particles, &[constraint], 1);
+        assert_approx_eq!(constraint.error(&particles).0, 0.0);
+        assert_eq!(particles[0].position, Vec3(0.0, 0.0, 0.0));
+        assert_eq!(particles[1].velocity, Vec3(1.0, 1.0, 0.0));
+    }
+}
@@ -0,0 +1,1087 @@
+use crate::collide::CollisionFilter;
+use crate::material::MaterialId;
+use crate::particle::Particle;
+use crate::vec::Vec3;
+use num::Float;
+use num_traits::NumAssign;
+
+/// Two particles in contact, or one particle in contact with immovable scenery.
+#[allow(clippy::module_name_repetitions)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParticleContact<T: Float> {
+    /// Indices of the particles involved. The second is `None` for contacts with scenery.
+    pub particles: (usize, Option<usize>),
+    pub restitution: T,
+    /// Direction, in world coordinates, that the first particle must move to separate.
+    pub contact_normal: Vec3<T>,
+    /// How far the particles overlap along `contact_normal`; negative when they're apart.
+    pub penetration: T,
+    /// Coulomb friction coefficients, as multiples of the impulse along the normal. Sliding
+    /// that this much static friction could stop is stopped outright; otherwise dynamic
+    /// friction slows it. Zero for both is frictionless.
+    pub static_friction: T,
+    pub dynamic_friction: T,
+}
+
+impl<T: Float + NumAssign> ParticleContact<T> {
+    /// The speed at which the particles are moving apart along the normal; negative when
+    /// they're approaching.
+    ///
+    /// Takes the particles the contact's indices refer to, like `resolve`. Useful for custom
+    /// resolution strategies, and for seeing how hard things hit.
+    pub fn separating_velocity(&self, particles: &[Particle<T>]) -> T {
+        self.relative_velocity(particles).dot(self.contact_normal)
+    }
+
+    /// The first particle's velocity relative to the second, or to the scenery.
+    fn relative_velocity(&self, particles: &[Particle<T>]) -> Vec3<T> {
+        let (a, b) = self.particles;
+        match b {
+            Some(b) => particles[a].velocity - particles[b].velocity,
+            None => particles[a].velocity,
+        }
+    }
+
+    /// The sum of the particles' inverse masses; zero when neither can be moved.
+    fn total_inverse_mass(&self, particles: &[Particle<T>]) -> T {
+        let (a, b) = self.particles;
+        particles[a].inverse_mass + b.map_or(T::zero(), |b| particles[b].inverse_mass)
+    }
+
+    /// Applies the collision impulse for this contact to the particles' velocities, so that they
+    /// separate at `restitution` times the speed they were approaching.
+    ///
+    /// Particles that are already separating are left alone. As for a particle resting on the
+    /// ground: in each frame of `duration` its `acceleration` (gravity, say) builds up a little
+    /// downward velocity, which a bounce would turn into a little upward velocity, and the
+    /// particle would never settle. So the velocity built up by the particles' `acceleration`
+    /// over the last frame is left out of the bounce. Velocity from forces isn't recognized this
+    /// way, since the force accumulator has already been cleared by then.
+    ///
+    /// Friction then acts against the particles' sliding, with an impulse of at most the
+    /// friction coefficient times the impulse along the normal. It can stop the sliding, but
+    /// never reverse it.
+    ///
+    /// Returns the size of the impulse along the normal, which is zero if nothing was done.
+    pub fn resolve_velocity(&self, particles: &mut [Particle<T>], duration: T) -> T {
+        let (a, b) = self.particles;
+        let acceleration_b = b.map_or(Vec3(T::zero(), T::zero(), T::zero()), |b| {
+            particles[b].acceleration
+        });
+        let built_up =
+            (particles[a].acceleration - acceleration_b).dot(self.contact_normal) * duration;
+        let total_inverse_mass = self.total_inverse_mass(particles);
+        let normal_impulse = normal_impulse(
+            self.separating_velocity(particles),
+            total_inverse_mass,
+            self.restitution,
+            built_up,
+        );
+        if normal_impulse.is_zero() {
+            return T::zero();
+        }
+        self.apply_impulse(particles, self.contact_normal * normal_impulse);
+
+        let sliding = self
+            .relative_velocity(particles)
+            .project_on_plane(self.contact_normal);
+        let speed = sliding.mag();
+        if speed.is_zero() {
+            return normal_impulse;
+        }
+        let stopping_impulse = speed / total_inverse_mass;
+        let friction_impulse = if stopping_impulse <= self.static_friction * normal_impulse {
+            stopping_impulse
+        } else {
+            (self.dynamic_friction * normal_impulse).min(stopping_impulse)
+        };
+        self.apply_impulse(particles, sliding * (-friction_impulse / speed));
+        normal_impulse
+    }
+
+    /// Changes the first particle's momentum by `impulse`, and the second's by the opposite.
+    fn apply_impulse(&self, particles: &mut [Particle<T>], impulse: Vec3<T>) {
+        let (a, b) = self.particles;
+        particles[a].apply_impulse(impulse);
+        if let Some(b) = b {
+            particles[b].apply_impulse(impulse.invert());
+        }
+    }
+
+    /// Moves the particles apart until they no longer overlap, as with `separate`.
+    ///
+    /// Does nothing unless `penetration` is positive. Returns how far each particle moved.
+    pub fn resolve_interpenetration(
+        &mut self,
+        particles: &mut [Particle<T>],
+    ) -> (Vec3<T>, Vec3<T>) {
+        if self.penetration <= T::zero() {
+            let zero = Vec3(T::zero(), T::zero(), T::zero());
+            return (zero, zero);
+        }
+        self.separate(particles, self.penetration)
+    }
+
+    /// Moves the particles `distance` further apart along the normal, reducing `penetration`.
+    ///
+    /// Each particle moves in proportion to its inverse mass, so an immovable particle stays put.
+    /// Returns how far each particle moved.
+    pub fn separate(&mut self, particles: &mut [Particle<T>], distance: T) -> (Vec3<T>, Vec3<T>) {
+        let zero = Vec3(T::zero(), T::zero(), T::zero());
+        let (a, b) = self.particles;
+        let inverse_mass_b = b.map_or(T::zero(), |b| particles[b].inverse_mass);
+        let total_inverse_mass = particles[a].inverse_mass + inverse_mass_b;
+        if total_inverse_mass <= T::zero() {
+            return (zero, zero);
+        }
+        let per_inverse_mass = self.contact_normal * (distance / total_inverse_mass);
+        let movement_a = per_inverse_mass * particles[a].inverse_mass;
+        let movement_b = per_inverse_mass * -inverse_mass_b;
+        particles[a].position += movement_a;
+        if let Some(b) = b {
+            particles[b].position += movement_b;
+        }
+        self.penetration -= distance;
+        (movement_a, movement_b)
+    }
+}
+
+/// The velocities of two bodies after colliding, found by applying an impulse along `normal`.
+///
+/// Masses are given as inverse masses, so either body can be immovable (with an inverse mass of
+/// zero). `normal` is the unit direction the first body must move to separate from the second.
+/// Afterward the bodies separate at `restitution` times the speed they were approaching.
+/// Bodies that are already separating are left alone.
+///
+/// `built_up` is the separating velocity the bodies' accelerations added over the last frame,
+/// which is left out of the bounce as in `ParticleContact::resolve_velocity`, so that bodies
+/// resting on each other settle. Pass zero to bounce with the whole approach speed.
+pub fn resolve_collision_velocities<T: Float + NumAssign>(
+    velocity_a: Vec3<T>,
+    inverse_mass_a: T,
+    velocity_b: Vec3<T>,
+    inverse_mass_b: T,
+    normal: Vec3<T>,
+    restitution: T,
+    built_up: T,
+) -> (Vec3<T>, Vec3<T>) {
+    let impulse = normal
+        * normal_impulse(
+            (velocity_a - velocity_b).dot(normal),
+            inverse_mass_a + inverse_mass_b,
+            restitution,
+            built_up,
+        );
+    (
+        velocity_a + impulse * inverse_mass_a,
+        velocity_b - impulse * inverse_mass_b,
+    )
+}
+
+/// The size of the impulse along the normal for `resolve_collision_velocities`, zero if the
+/// bodies are separating or both immovable.
+fn normal_impulse<T: Float>(
+    separating_velocity: T,
+    total_inverse_mass: T,
+    restitution: T,
+    built_up: T,
+) -> T {
+    if separating_velocity >= T::zero() || total_inverse_mass <= T::zero() {
+        return T::zero();
+    }
+    let mut new_separating_velocity = -separating_velocity * restitution;
+    if built_up < T::zero() {
+        new_separating_velocity = (new_separating_velocity + built_up * restitution).max(T::zero());
+    }
+    (new_separating_velocity - separating_velocity) / total_inverse_mass
+}
+
+/// Finds contacts between particles, or between particles and scenery.
+#[allow(clippy::module_name_repetitions)]
+pub trait ParticleContactGenerator<T: Float> {
+    /// Pushes at most `limit` contacts onto `contacts`, returning how many were pushed.
+    fn add_contacts(
+        &self,
+        particles: &[Particle<T>],
+        contacts: &mut Vec<ParticleContact<T>>,
+        limit: usize,
+    ) -> usize;
+
+    /// Like `add_contacts`, leaving out collisions that `filters` rule out. The particle at each
+    /// index has the filter at the same index of `filters`, or the default filter past its end.
+    ///
+    /// Generators of collisions should check filters before doing any narrow-phase work. By
+    /// default `filters` are ignored, which suits constraints like cables: they hold particles
+    /// together whatever their layers.
+    fn add_filtered_contacts(
+        &self,
+        particles: &[Particle<T>],
+        _filters: &[CollisionFilter],
+        contacts: &mut Vec<ParticleContact<T>>,
+        limit: usize,
+    ) -> usize {
+        self.add_contacts(particles, contacts, limit)
+    }
+
+    /// For continuous collision detection: when the particle at `index`, moving in a straight
+    /// line from `starts[index]` to where it is now, first hits something this generator
+    /// collides it with, as a fraction of the way along, and the contact there.
+    ///
+    /// Every other particle is taken to have moved in a straight line from its own start over
+    /// the same step. Collisions the particle was already in at the start are left to
+    /// `add_contacts`. By default nothing is ever hit, which suits constraints.
+    fn sweep(
+        &self,
+        _particles: &[Particle<T>],
+        _starts: &[Vec3<T>],
+        _filters: &[CollisionFilter],
+        _index: usize,
+    ) -> Option<(T, ParticleContact<T>)> {
+        None
+    }
+
+    /// Whether a `ParticleWorld` should replace the restitution and friction of this
+    /// generator's contacts with those its `MaterialTable` gives for the two sides' materials,
+    /// when both sides have one. By default it shouldn't, which suits constraints.
+    fn uses_materials(&self) -> bool {
+        false
+    }
+
+    /// The material of whatever this generator's contacts with only one particle are against,
+    /// like the ground, if it has one.
+    fn scenery_material(&self) -> Option<MaterialId> {
+        None
+    }
+}
+
+/// Wraps a closure as a contact generator, for one-off contacts that don't deserve their own type.
+///
+/// The closure receives the particles and pushes whatever contacts it finds. Any past the
+/// generator's limit are dropped.
+/// ```
+/// # use cyclone::contact::{ContactFn, ParticleContact};
+/// # use cyclone::particle::Particle;
+/// # use cyclone::vec::Vec3;
+/// # use cyclone::world::ParticleWorld;
+/// let mut world = ParticleWorld::new(10);
+/// world.add_particle(Particle {
+///     position: Vec3(0.0, -0.5, 0.0),
+///     ..Particle::default()
+/// });
+/// // Keep every particle above y = 0.
+/// world.add_contact_generator(ContactFn::new(
+///     |particles: &[Particle<f64>], contacts: &mut Vec<ParticleContact<f64>>| {
+///         for (index, particle) in particles.iter().enumerate() {
+///             if particle.position.1 < 0.0 {
+///                 contacts.push(ParticleContact {
+///                     particles: (index, None),
+///                     restitution: 0.5,
+///                     contact_normal: Vec3(0.0, 1.0, 0.0),
+///                     penetration: -particle.position.1,
+///                     static_friction: 0.0,
+///                     dynamic_friction: 0.0,
+///                 });
+///             }
+///         }
+///     },
+/// ));
+/// assert_eq!(world.generate_contacts(), 1);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct ContactFn<F> {
+    f: F,
+}
+
+impl<F> ContactFn<F> {
+    pub fn new(f: F) -> Self {
+        Self { f }
+    }
+}
+
+impl<T, F> ParticleContactGenerator<T> for ContactFn<F>
+where
+    T: Float,
+    F: Fn(&[Particle<T>], &mut Vec<ParticleContact<T>>),
+{
+    fn add_contacts(
+        &self,
+        particles: &[Particle<T>],
+        contacts: &mut Vec<ParticleContact<T>>,
+        limit: usize,
+    ) -> usize {
+        let before = contacts.len();
+        (self.f)(particles, contacts);
+        contacts.truncate(before + limit);
+        contacts.len() - before
+    }
+}
+
+/// Resolves a frame's contacts: first their velocities, then their interpenetration.
+///
+/// Resolving one contact can disturb another, so velocities are resolved one contact at a time,
+/// always the one approaching fastest, until no contact is approaching or `iterations` contacts
+/// have been resolved. `iterations_used` tells which happened in the last call; twice the number
+/// of contacts is usually plenty.
+///
+/// Interpenetration is then corrected deepest first, updating the penetration of every contact
+/// that shares a particle with each one moved. Each contact is corrected once per call, and only
+/// partially: penetrations up to `slop` are allowed to remain, and `correction_factor` of the
+/// rest is removed. Leaving a little overlap keeps resting contacts from flickering between
+/// touching and not touching every frame, and correcting gradually (Baumgarte-style) keeps
+/// deep penetrations from popping apart in a single frame. A `slop` of zero and a
+/// `correction_factor` of one remove all penetration immediately.
+///
+/// Contacts that tie, approaching equally fast or overlapping equally deep, are taken in the
+/// order they come in `contacts`, so the same contacts in the same order always resolve to the
+/// same result.
+///
+/// A stack's resting contacts need the same impulses frame after frame, but resolving them
+/// from nothing takes many iterations, since each resolved contact sets the one above it
+/// approaching. `resolve_contacts_warm_started` can instead start from `warm_starting` times
+/// the impulses of the previous frame, leaving the iterations only the difference to find. A
+/// `warm_starting` of zero, the default, disables it; it should stay below one, or impulses
+/// that are no longer needed, once a load is taken off, never die away.
+///
+/// `resolve_velocity` already keeps a resting particle from bouncing on the velocity its
+/// acceleration built up over the frame. A particle settling after a fall still makes smaller
+/// and smaller hops, though, which can end up repeating forever rather than dying away, and
+/// velocity from forces isn't recognized at all. Contacts approaching slower than
+/// `resting_speed` are resolved without restitution, so these stop dead. It's zero, leaving
+/// every contact its restitution, by default.
+#[allow(clippy::module_name_repetitions)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParticleContactResolver<T: Float> {
+    pub iterations: usize,
+    pub slop: T,
+    pub correction_factor: T,
+    pub warm_starting: T,
+    pub resting_speed: T,
+    iterations_used: usize,
+}
+
+impl<T: Float + NumAssign> ParticleContactResolver<T> {
+    pub fn new(iterations: usize, slop: T, correction_factor: T) -> Self {
+        Self {
+            iterations,
+            slop,
+            correction_factor,
+            warm_starting: T::zero(),
+            resting_speed: T::zero(),
+            iterations_used: 0,
+        }
+    }
+
+    pub fn with_warm_starting(mut self, warm_starting: T) -> Self {
+        self.warm_starting = warm_starting;
+        self
+    }
+
+    pub fn with_resting_speed(mut self, resting_speed: T) -> Self {
+        self.resting_speed = resting_speed;
+        self
+    }
+
+    /// How many contact velocities the last `resolve_contacts` resolved. If this is
+    /// `iterations`, some contacts may still be approaching.
+    pub fn iterations_used(&self) -> usize {
+        self.iterations_used
+    }
+
+    /// Resolves `contacts`, which were found after integrating `particles` by `duration`.
+    pub fn resolve_contacts(
+        &mut self,
+        contacts: &mut [ParticleContact<T>],
+        particles: &mut [Particle<T>],
+        duration: T,
+    ) {
+        self.resolve_contacts_reporting(contacts, particles, duration);
+    }
+
+    /// Like `resolve_contacts`, returning the total impulse applied along each contact's
+    /// normal, which is zero for contacts whose velocities were left alone.
+    ///
+    /// A contact can be resolved more than once in a call, when resolving others sets it
+    /// approaching again; its impulses are added up.
+    pub fn resolve_contacts_reporting(
+        &mut self,
+        contacts: &mut [ParticleContact<T>],
+        particles: &mut [Particle<T>],
+        duration: T,
+    ) -> Vec<T> {
+        self.resolve_contacts_warm_started(contacts, particles, duration, &[])
+    }
+
+    /// Like `resolve_contacts_reporting`, but first applies `warm_starting` times `previous[i]`
+    /// along the normal of `contacts[i]`: the impulse this method returned for the same contact
+    /// last frame. Contacts past the end of `previous` are new, and start from nothing.
+    ///
+    /// The returned impulses include the ones applied up front.
+    pub fn resolve_contacts_warm_started(
+        &mut self,
+        contacts: &mut [ParticleContact<T>],
+        particles: &mut [Particle<T>],
+        duration: T,
+        previous: &[T],
+    ) -> Vec<T> {
+        let mut impulses = vec![T::zero(); contacts.len()];
+        if self.warm_starting > T::zero() {
+            for ((contact, impulse), &previous) in contacts.iter().zip(&mut impulses).zip(previous)
+            {
+                *impulse = previous * self.warm_starting;
+                contact.apply_impulse(particles, contact.contact_normal * *impulse);
+            }
+        }
+        self.iterations_used = 0;
+        while self.iterations_used < self.iterations {
+            let mut fastest = None;
+            let mut fastest_velocity = T::zero();
+            // Strictly faster only, so a tie goes to the earliest contact. Contacts between
+            // immovable particles can't be resolved, and would be picked again every time.
+            for (index, contact) in contacts.iter().enumerate() {
+                if contact.total_inverse_mass(particles) <= T::zero() {
+                    continue;
+                }
+                let separating_velocity = contact.separating_velocity(particles);
+                if separating_velocity < fastest_velocity {
+                    fastest = Some(index);
+                    fastest_velocity = separating_velocity;
+                }
+            }
+            let fastest = match fastest {
+                Some(fastest) => fastest,
+                None => break,
+            };
+            let mut contact = contacts[fastest];
+            if -fastest_velocity < self.resting_speed {
+                contact.restitution = T::zero();
+            }
+            impulses[fastest] += contact.resolve_velocity(particles, duration);
+            self.iterations_used += 1;
+        }
+
+        let mut corrected = vec![false; contacts.len()];
+        loop {
+            let mut deepest = None;
+            let mut deepest_penetration = self.slop;
+            for (index, contact) in contacts.iter().enumerate() {
+                if !corrected[index] && contact.penetration > deepest_penetration {
+                    deepest = Some(index);
+                    deepest_penetration = contact.penetration;
+                }
+            }
+            let deepest = match deepest {
+                Some(deepest) => deepest,
+                None => break,
+            };
+            corrected[deepest] = true;
+            let excess = deepest_penetration - self.slop;
+            let contact = &mut contacts[deepest];
+            let moved = contact.particles;
+            let movement = contact.separate(particles, excess * self.correction_factor);
+            for (index, other) in contacts.iter_mut().enumerate() {
+                if index != deepest {
+                    other.moved(moved.0, movement.0);
+                    if let Some(b) = moved.1 {
+                        other.moved(b, movement.1);
+                    }
+                }
+            }
+        }
+        impulses
+    }
+}
+
+impl<T: Float + NumAssign> ParticleContact<T> {
+    /// Updates `penetration` for the particle at `particle` having moved by `movement`.
+    fn moved(&mut self, particle: usize, movement: Vec3<T>) {
+        if self.particles.0 == particle {
+            self.penetration -= movement.dot(self.contact_normal);
+        }
+        if self.particles.1 == Some(particle) {
+            self.penetration += movement.dot(self.contact_normal);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::contact::{
+        resolve_collision_velocities, ContactFn, ParticleContact, ParticleContactGenerator,
+        ParticleContactResolver,
+    };
+    use crate::particle::Particle;
+    use crate::vec::Vec3;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn momentum_and_restitution() {
+        let (v1, m1) = (Vec3(-3.0_f64, 1.0, 0.5), 2.0);
+        let (v2, m2) = (Vec3(1.0, -2.0, 0.0), 0.5);
+        let normal = Vec3(1.0, 1.0, 0.0).norm();
+        let (after1, after2) =
+            resolve_collision_velocities(v1, 1.0 / m1, v2, 1.0 / m2, normal, 0.7, 0.0);
+        let momentum = v1 * m1 + v2 * m2;
+        let after = after1 * m1 + after2 * m2;
+        assert_approx_eq!(after.0, momentum.0);
+        assert_approx_eq!(after.1, momentum.1);
+        assert_approx_eq!(after.2, momentum.2);
+        let before = (v1 - v2).dot(normal);
+        assert_approx_eq!((after1 - after2).dot(normal), -0.7 * before);
+        // Tangential velocity is untouched.
+        let tangent = Vec3(1.0, -1.0, 0.0).norm();
+        assert_approx_eq!(after1.dot(tangent), v1.dot(tangent));
+        assert_approx_eq!(after1.2, v1.2);
+    }
+
+    #[test]
+    fn separating() {
+        let normal = Vec3(0.0, 1.0, 0.0);
+        let (v1, v2) = (Vec3(0.0, 1.0, 0.0), Vec3(0.0, -1.0, 0.0));
+        assert_eq!(
+            resolve_collision_velocities(v1, 1.0, v2, 1.0, normal, 0.5, 0.0),
+            (v1, v2)
+        );
+    }
+
+    #[test]
+    fn immovable() {
+        let normal = Vec3(0.0, 1.0, 0.0);
+        let (v1, v2) = (Vec3(1.0, -2.0, 0.0), Vec3(0.0, 0.0, 0.0));
+        let after = resolve_collision_velocities(v1, 1.0, v2, 0.0, normal, 0.5, 0.0);
+        assert_eq!(after, (Vec3(1.0, 1.0, 0.0), v2));
+        assert_eq!(
+            resolve_collision_velocities(v1, 0.0, v2, 0.0, normal, 0.5, 0.0),
+            (v1, v2)
+        );
+    }
+
+    #[test]
+    fn resolve_velocity() {
+        let mut particles = vec![
+            Particle {
+                velocity: Vec3(0.0, -2.0, 0.0),
+                ..Particle::default()
+            },
+            Particle {
+                velocity: Vec3(0.0, 2.0, 0.0),
+                ..Particle::default()
+            },
+        ];
+        let contact = ParticleContact {
+            particles: (0, Some(1)),
+            restitution: 1.0,
+            contact_normal: Vec3(0.0, 1.0, 0.0),
+            penetration: 0.0,
+            static_friction: 0.0,
+            dynamic_friction: 0.0,
+        };
+        contact.resolve_velocity(&mut particles, 0.1);
+        assert_eq!(particles[0].velocity, Vec3(0.0, 2.0, 0.0));
+        assert_eq!(particles[1].velocity, Vec3(0.0, -2.0, 0.0));
+        let ground = ParticleContact {
+            particles: (1, None),
+            ..contact
+        };
+        ground.resolve_velocity(&mut particles, 0.1);
+        assert_eq!(particles[1].velocity, Vec3(0.0, 2.0, 0.0));
+    }
+
+    fn head_on(inverse_mass_b: f64) -> (Vec<Particle<f64>>, ParticleContact<f64>) {
+        let particles = vec![
+            Particle {
+                velocity: Vec3(3.0, 0.0, 1.0),
+                ..Particle::default()
+            },
+            Particle {
+                position: Vec3(1.0, 0.0, 0.0),
+                inverse_mass: inverse_mass_b,
+                ..Particle::default()
+            },
+        ];
+        let contact = ParticleContact {
+            particles: (0, Some(1)),
+            restitution: 1.0,
+            contact_normal: Vec3(-1.0, 0.0, 0.0),
+            penetration: 0.2,
+            static_friction: 0.0,
+            dynamic_friction: 0.0,
+        };
+        (particles, contact)
+    }
+
+    #[test]
+    fn exchange_velocity() {
+        let (mut particles, contact) = head_on(1.0);
+        assert_eq!(contact.separating_velocity(&particles), -3.0);
+        contact.resolve_velocity(&mut particles, 0.1);
+        assert_eq!(particles[0].velocity, Vec3(0.0, 0.0, 1.0));
+        assert_eq!(particles[1].velocity, Vec3(3.0, 0.0, 0.0));
+        assert_eq!(contact.separating_velocity(&particles), 3.0);
+    }
+
+    #[test]
+    fn separating_velocity() {
+        let particles = [
+            Particle {
+                velocity: Vec3(0.0, -2.0, 5.0),
+                ..Particle::default()
+            },
+            Particle {
+                velocity: Vec3(0.0, 1.5, -1.0),
+                ..Particle::default()
+            },
+        ];
+        let contact = ParticleContact {
+            particles: (0, Some(1)),
+            restitution: 0.5,
+            contact_normal: Vec3(0.0, 1.0, 0.0),
+            penetration: 0.0,
+            static_friction: 0.0,
+            dynamic_friction: 0.0,
+        };
+        // Closing at 3.5 along the normal; motion across it doesn't count.
+        assert_eq!(contact.separating_velocity(&particles), -3.5);
+        let ground = ParticleContact {
+            particles: (1, None),
+            ..contact
+        };
+        assert_eq!(ground.separating_velocity(&particles), 1.5);
+    }
+
+    #[test]
+    fn infinite_mass() {
+        let (mut particles, mut contact) = head_on(0.0);
+        contact.resolve_velocity(&mut particles, 0.1);
+        assert_eq!(particles[0].velocity, Vec3(-3.0, 0.0, 1.0));
+        assert_eq!(particles[1].velocity, Vec3(0.0, 0.0, 0.0));
+        contact.resolve_interpenetration(&mut particles);
+        assert_approx_eq!(particles[0].position.0, -0.2);
+        assert_eq!(particles[1].position, Vec3(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn resolve_interpenetration() {
+        let (mut particles, mut contact) = head_on(3.0);
+        let (moved_a, moved_b) = contact.resolve_interpenetration(&mut particles);
+        assert_approx_eq!(moved_a.0, -0.05);
+        assert_approx_eq!(moved_b.0, 0.15);
+        assert_approx_eq!((moved_a - moved_b).mag(), 0.2);
+        assert_approx_eq!(particles[1].position.0 - particles[0].position.0, 1.2);
+        assert_eq!(contact.penetration, 0.0);
+        assert_eq!(
+            contact.resolve_interpenetration(&mut particles),
+            (Vec3(0.0, 0.0, 0.0), Vec3(0.0, 0.0, 0.0))
+        );
+    }
+
+    #[test]
+    fn already_separating() {
+        let (mut particles, mut contact) = head_on(1.0);
+        particles[0].velocity = Vec3(-1.0, 0.0, 0.0);
+        contact.penetration = -0.1;
+        let before = particles.clone();
+        contact.resolve_velocity(&mut particles, 0.1);
+        contact.resolve_interpenetration(&mut particles);
+        assert_eq!(particles, before);
+    }
+
+    #[test]
+    fn resting_contact() {
+        // One frame of gravity on a ball resting on the ground.
+        let mut particles = vec![Particle {
+            velocity: Vec3(0.0, -0.1_f64, 0.0),
+            acceleration: Vec3(0.0, -10.0, 0.0),
+            ..Particle::default()
+        }];
+        let contact = ParticleContact {
+            particles: (0, None),
+            restitution: 0.5,
+            contact_normal: Vec3(0.0, 1.0, 0.0),
+            penetration: 0.001,
+            static_friction: 0.0,
+            dynamic_friction: 0.0,
+        };
+        contact.resolve_velocity(&mut particles, 0.01);
+        assert_eq!(particles[0].velocity, Vec3(0.0, 0.0, 0.0));
+        // A real impact still bounces, less the velocity gravity added this frame.
+        particles[0].velocity = Vec3(0.0, -2.0, 0.0);
+        contact.resolve_velocity(&mut particles, 0.01);
+        assert_approx_eq!(particles[0].velocity.1, 0.95);
+    }
+
+    #[test]
+    fn resting_contact_standalone() {
+        let normal = Vec3(0.0, 1.0, 0.0);
+        let ground = Vec3(0.0, 0.0, 0.0);
+        let built_up = -10.0_f64 * 0.01;
+        let (settled, _) = resolve_collision_velocities(
+            Vec3(0.0, -0.1, 0.0),
+            1.0,
+            ground,
+            0.0,
+            normal,
+            0.5,
+            built_up,
+        );
+        assert_eq!(settled, ground);
+        let (bounced, _) = resolve_collision_velocities(
+            Vec3(0.0, -2.0, 0.0),
+            1.0,
+            ground,
+            0.0,
+            normal,
+            0.5,
+            built_up,
+        );
+        assert_approx_eq!(bounced.1, 0.95);
+    }
+
+    #[test]
+    fn friction() {
+        let slide = |static_friction, dynamic_friction, velocity| {
+            let mut particles = vec![
+                Particle {
+                    velocity,
+                    ..Particle::default()
+                },
+                Particle::default(),
+            ];
+            let contact = ParticleContact {
+                particles: (0, Some(1)),
+                restitution: 0.0,
+                contact_normal: Vec3(0.0, 1.0, 0.0),
+                penetration: 0.0,
+                static_friction,
+                dynamic_friction,
+            };
+            contact.resolve_velocity(&mut particles, 0.01);
+            // Friction is equal and opposite.
+            let momentum = particles[0].velocity + particles[1].velocity;
+            assert_eq!(momentum.with_y(0.0), velocity.with_y(0.0));
+            particles[0].velocity - particles[1].velocity
+        };
+        // The normal impulse is 1, so dynamic friction takes 0.25 off each particle's sliding.
+        let relative = slide(0.1, 0.25, Vec3(1.0, -2.0, 0.0));
+        assert_eq!(relative, Vec3(0.5, 0.0, 0.0));
+        // Strong static friction stops the sliding outright.
+        assert_eq!(slide(0.5, 0.25, Vec3(1.0, -2.0, 0.0)), Vec3(0.0, 0.0, 0.0));
+        // However strong dynamic friction is, it never reverses the sliding.
+        for &dynamic_friction in &[0.4, 1.0, 100.0] {
+            let relative = slide(0.1, dynamic_friction, Vec3(0.6, -2.0, -0.8));
+            assert!(relative.0 >= 0.0 && relative.2 <= 0.0);
+        }
+        // Separating particles have no normal impulse, so no friction.
+        assert_eq!(slide(1.0, 1.0, Vec3(1.0, 2.0, 0.0)), Vec3(1.0, 2.0, 0.0));
+    }
+
+    #[test]
+    fn separate() {
+        let mut particles = vec![
+            Particle {
+                inverse_mass: 3.0_f64,
+                ..Particle::default()
+            },
+            Particle::default(),
+        ];
+        let mut contact = ParticleContact {
+            particles: (0, Some(1)),
+            restitution: 0.0,
+            contact_normal: Vec3(1.0, 0.0, 0.0),
+            penetration: 0.5,
+            static_friction: 0.0,
+            dynamic_friction: 0.0,
+        };
+        contact.separate(&mut particles, 0.4);
+        assert_approx_eq!(particles[0].position.0, 0.3);
+        assert_approx_eq!(particles[1].position.0, -0.1);
+        assert_approx_eq!(contact.penetration, 0.1);
+    }
+
+    /// Three touching particles in a row along x, the first moving toward the others.
+    fn cradle() -> (Vec<Particle<f64>>, Vec<ParticleContact<f64>>) {
+        let particles = (0..3)
+            .map(|i| Particle {
+                position: Vec3(f64::from(i), 0.0, 0.0),
+                ..Particle::default()
+            })
+            .collect::<Vec<_>>();
+        let contacts = (0..2)
+            .map(|i| ParticleContact {
+                particles: (i + 1, Some(i)),
+                restitution: 1.0,
+                contact_normal: Vec3(1.0, 0.0, 0.0),
+                penetration: 0.0,
+                static_friction: 0.0,
+                dynamic_friction: 0.0,
+            })
+            .collect();
+        (particles, contacts)
+    }
+
+    #[test]
+    fn propagates_down_chain() {
+        let (mut particles, mut contacts) = cradle();
+        particles[0].velocity = Vec3(2.0, 0.0, 0.0);
+        let mut resolver = ParticleContactResolver::new(10, 0.0, 1.0);
+        let impulses = resolver.resolve_contacts_reporting(&mut contacts, &mut particles, 0.01);
+        assert_eq!(particles[0].velocity, Vec3(0.0, 0.0, 0.0));
+        assert_eq!(particles[1].velocity, Vec3(0.0, 0.0, 0.0));
+        assert_eq!(particles[2].velocity, Vec3(2.0, 0.0, 0.0));
+        assert_eq!(impulses, vec![2.0, 2.0]);
+        // Nothing is approaching after two, so the rest of the budget goes unused.
+        assert_eq!(resolver.iterations_used(), 2);
+        let impulses = resolver.resolve_contacts_reporting(&mut contacts, &mut particles, 0.01);
+        assert_eq!(resolver.iterations_used(), 0);
+        assert_eq!(impulses, vec![0.0, 0.0]);
+    }
+
+    #[test]
+    fn iteration_cap() {
+        let (mut particles, mut contacts) = cradle();
+        particles[0].velocity = Vec3(2.0, 0.0, 0.0);
+        let mut resolver = ParticleContactResolver::new(1, 0.0, 1.0);
+        resolver.resolve_contacts(&mut contacts, &mut particles, 0.01);
+        assert_eq!(resolver.iterations_used(), 1);
+        assert_eq!(particles[1].velocity, Vec3(2.0, 0.0, 0.0));
+        assert_eq!(particles[2].velocity, Vec3(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn immovable_approaching() {
+        let mut particles = vec![
+            Particle {
+                velocity: Vec3(0.0, -1.0, 0.0),
+                inverse_mass: 0.0,
+                ..Particle::default()
+            },
+            Particle {
+                velocity: Vec3(0.0, -0.5, 0.0),
+                ..Particle::default()
+            },
+        ];
+        let mut contacts: Vec<_> = (0..2)
+            .map(|i| ParticleContact {
+                particles: (i, None),
+                restitution: 0.0,
+                contact_normal: Vec3(0.0, 1.0, 0.0),
+                penetration: 0.0,
+                static_friction: 0.0,
+                dynamic_friction: 0.0,
+            })
+            .collect();
+        let mut resolver = ParticleContactResolver::new(8, 0.0, 1.0);
+        resolver.resolve_contacts(&mut contacts, &mut particles, 0.01);
+        // The kinematic particle keeps its velocity, and doesn't use up the budget.
+        assert_eq!(particles[0].velocity, Vec3(0.0, -1.0, 0.0));
+        assert_eq!(particles[1].velocity, Vec3(0.0, 0.0, 0.0));
+        assert_eq!(resolver.iterations_used(), 1);
+    }
+
+    #[test]
+    fn ties() {
+        let (mut particles, mut contacts) = cradle();
+        // The outer particles close in on the middle one equally fast, and equally deep.
+        particles[0].velocity = Vec3(1.0, 0.0, 0.0);
+        particles[2].velocity = Vec3(-1.0, 0.0, 0.0);
+        for contact in &mut contacts {
+            contact.penetration = 0.2;
+        }
+        let resolve = |iterations| {
+            let (mut particles, mut contacts) = (particles.clone(), contacts.clone());
+            let mut resolver = ParticleContactResolver::new(iterations, 0.0, 1.0);
+            let impulses = resolver.resolve_contacts_reporting(&mut contacts, &mut particles, 0.01);
+            (particles, contacts, impulses)
+        };
+        let (first, _, _) = resolve(1);
+        assert_eq!(first[0].velocity, Vec3(0.0, 0.0, 0.0));
+        assert_eq!(first[1].velocity, Vec3(1.0, 0.0, 0.0));
+        assert_eq!(first[2].velocity, Vec3(-1.0, 0.0, 0.0));
+        let expected = resolve(10);
+        for _ in 0..20 {
+            assert_eq!(resolve(10), expected);
+        }
+    }
+
+    #[test]
+    fn propagates_penetration() {
+        let (mut particles, mut contacts) = cradle();
+        contacts[0].penetration = 0.3;
+        contacts[1].penetration = 0.1;
+        particles[0].inverse_mass = 0.0;
+        let mut resolver = ParticleContactResolver::new(10, 0.0, 1.0);
+        resolver.resolve_contacts(&mut contacts, &mut particles, 0.01);
+        // The deeper contact goes first, pushing the middle particle 0.3 into the last one. That
+        // contact then splits its 0.4 between them, pushing the middle one back into the first.
+        assert_approx_eq!(particles[1].position.0, 1.1);
+        assert_approx_eq!(particles[2].position.0, 2.2);
+        assert_approx_eq!(contacts[1].penetration, 0.0);
+        // Each contact is corrected only once per call; the rest waits for the next.
+        assert_approx_eq!(contacts[0].penetration, 0.2);
+        resolver.resolve_contacts(&mut contacts, &mut particles, 0.01);
+        assert_approx_eq!(particles[1].position.0, 1.2);
+        assert_approx_eq!(contacts[1].penetration, 0.0);
+    }
+
+    #[test]
+    fn contact_fn() {
+        let mut particles = vec![
+            Particle {
+                position: Vec3(0.0, -0.1, 0.0),
+                velocity: Vec3(1.0, -4.0, 0.0),
+                ..Particle::default()
+            },
+            Particle {
+                position: Vec3(3.0, -0.2, 0.0),
+                velocity: Vec3(0.0, -2.0, 0.0),
+                ..Particle::default()
+            },
+        ];
+        let floor = ContactFn::new(
+            |particles: &[Particle<f64>], contacts: &mut Vec<ParticleContact<f64>>| {
+                for (index, particle) in particles.iter().enumerate() {
+                    contacts.push(ParticleContact {
+                        particles: (index, None),
+                        restitution: 0.5,
+                        contact_normal: Vec3(0.0, 1.0, 0.0),
+                        penetration: -particle.position.1,
+                        static_friction: 0.0,
+                        dynamic_friction: 0.0,
+                    });
+                }
+            },
+        );
+        let mut contacts = Vec::new();
+        assert_eq!(floor.add_contacts(&particles, &mut contacts, 1), 1);
+        assert_eq!(floor.add_contacts(&particles, &mut contacts, 5), 2);
+        assert_eq!(contacts.len(), 3);
+        contacts.clear();
+        floor.add_contacts(&particles, &mut contacts, 5);
+        let mut resolver = ParticleContactResolver::new(4, 0.0, 1.0);
+        resolver.resolve_contacts(&mut contacts, &mut particles, 0.01);
+        assert_eq!(particles[0].velocity, Vec3(1.0, 2.0, 0.0));
+        assert_eq!(particles[1].velocity, Vec3(0.0, 1.0, 0.0));
+        assert_approx_eq!(particles[1].position.1, 0.0);
+    }
+
+    const RADIUS: f64 = 0.5;
+
+    /// Contacts for a column of balls, each resting on the one below it and the lowest on the
+    /// ground at `y = 0`. A contact exists only while its balls overlap.
+    fn stack_contacts(particles: &[Particle<f64>]) -> Vec<ParticleContact<f64>> {
+        let mut contacts = Vec::new();
+        let mut push = |particles, penetration: f64| {
+            if penetration > 0.0 {
+                contacts.push(ParticleContact {
+                    particles,
+                    restitution: 0.0,
+                    contact_normal: Vec3(0.0, 1.0, 0.0),
+                    penetration,
+                    static_friction: 0.0,
+                    dynamic_friction: 0.0,
+                });
+            }
+        };
+        push((0, None), RADIUS - particles[0].position.1);
+        for i in 1..particles.len() {
+            let gap = particles[i].position.1 - particles[i - 1].position.1;
+            push((i, Some(i - 1)), 2.0 * RADIUS - gap);
+        }
+        contacts
+    }
+
+    /// Steps a resting column of balls, returning the heights of the balls after each step.
+    fn stack(
+        mut resolver: ParticleContactResolver<f64>,
+        heights: &[f64],
+        steps: usize,
+    ) -> Vec<Vec<f64>> {
+        let mut particles: Vec<_> = heights
+            .iter()
+            .map(|&y| Particle {
+                position: Vec3(0.0, y, 0.0),
+                acceleration: Vec3(0.0, -10.0, 0.0),
+                ..Particle::default()
+            })
+            .collect();
+        let mut frames = Vec::new();
+        for _ in 0..steps {
+            for particle in &mut particles {
+                particle.integrate(0.01);
+            }
+            let mut contacts = stack_contacts(&particles);
+            resolver.resolve_contacts(&mut contacts, &mut particles, 0.01);
+            frames.push(particles.iter().map(|p| p.position.1).collect());
+        }
+        frames
+    }
+
+    #[test]
+    fn warm_started() {
+        let falling = |y| Particle {
+            position: Vec3(0.0, y, 0.0),
+            velocity: Vec3(0.0, -0.5, 0.0),
+            ..Particle::default()
+        };
+        let mut particles: Vec<_> = [0.49, 1.48, 2.47].iter().map(|&y| falling(y)).collect();
+        let mut contacts = stack_contacts(&particles);
+        let mut resolver = ParticleContactResolver::new(10, 0.01, 0.5);
+        resolver.resolve_contacts(&mut contacts, &mut particles.clone(), 0.0);
+        assert_eq!(resolver.iterations_used(), 10);
+        // Given exactly the impulses that stop the stack, nothing is left to iterate on.
+        let mut resolver = resolver.with_warm_starting(1.0);
+        let previous = [1.5, 1.0, 0.5];
+        let impulses =
+            resolver.resolve_contacts_warm_started(&mut contacts, &mut particles, 0.0, &previous);
+        assert_eq!(resolver.iterations_used(), 0);
+        for particle in &particles {
+            assert_approx_eq!(particle.velocity.1, 0.0, 1e-12);
+        }
+        for (&impulse, &expected) in impulses.iter().zip(&previous) {
+            assert_approx_eq!(impulse, expected, 1e-12);
+        }
+    }
+
+    #[test]
+    fn slop_stops_jitter() {
+        let resting = [0.5, 1.5, 2.5];
+        // A chain of resting contacts converges slowly, so this needs a generous budget.
+        let frames = stack(ParticleContactResolver::new(300, 0.01, 0.5), &resting, 300);
+        let settled = &frames[100];
+        for frame in &frames[100..] {
+            for (y, settled_y) in frame.iter().zip(settled) {
+                assert_approx_eq!(y, settled_y, 1e-12);
+            }
+        }
+        // Each contact is left with a bit less than `slop` of overlap.
+        assert!(settled[0] > RADIUS - 0.01);
+        assert!(settled[1] - settled[0] > 2.0 * RADIUS - 0.01);
+        assert!(settled[2] - settled[1] > 2.0 * RADIUS - 0.01);
+    }
+
+    #[test]
+    fn deep_penetration() {
+        let frames = stack(ParticleContactResolver::new(100, 0.01, 0.8), &[0.1], 20);
+        // The correction is spread over several frames rather than applied all at once.
+        assert!(frames[0][0] < RADIUS - 0.05);
+        assert_approx_eq!(frames[19][0], RADIUS - 0.01, 1e-9);
+    }
+
+    #[test]
+    fn stack_settles() {
+        // Dropped from a little apart, with the budget `ParticleWorld` gives three contacts.
+        let frames = stack(
+            ParticleContactResolver::new(6, 0.01, 0.5),
+            &[0.6, 1.8, 3.0],
+            400,
+        );
+        let settled = &frames[100];
+        for frame in &frames[100..] {
+            for (y, settled_y) in frame.iter().zip(settled) {
+                assert_approx_eq!(y, settled_y, 1e-3);
+            }
+        }
+        for (i, &y) in settled.iter().enumerate() {
+            assert_approx_eq!(y, RADIUS * (2.0 * i as f64 + 1.0), 0.05);
+        }
+    }
+}
@@ -0,0 +1,836 @@
+use crate::particle::Particle;
+use crate::slot::{Key, SlotMap};
+use crate::vec::Vec3;
+use num::Float;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use std::any::Any;
+
+mod buoyancy;
+mod closure;
+mod drag;
+mod explosion;
+mod field;
+mod friction;
+mod gravity;
+mod lift;
+mod spring;
+mod vortex;
+
+pub use buoyancy::ParticleBuoyancy;
+pub use closure::{ClosureForce, ForceFn};
+pub use drag::{ParticleAtmosphericDrag, ParticleDrag, ParticleWind, Turbulence};
+pub use explosion::{Convection, Implosion, ParticleExplosion, Shockwave};
+pub use field::{ForceField, Region, Strength};
+pub use friction::{NormalForce, NormalForceFn, ParticlePlaneFriction};
+pub use gravity::{Gravitation, GravityWell, ParticleAttractor, ParticleGravity};
+pub use lift::ParticleLift;
+pub use spring::{
+    DampedSpring, ParticleAnchoredBungee, ParticleAnchoredDampedSpring, ParticleAnchoredSpring,
+    ParticleBungee, ParticleDampedSpring, ParticleFakeSpring, ParticleSpring,
+};
+pub use vortex::{ParticleVortex, RadialFalloff};
+
+/// Implemented by every `Send` type when the `rayon` feature is enabled, so that registries
+/// can apply forces from several threads, and by every type otherwise.
+#[cfg(feature = "rayon")]
+pub trait MaybeSend: Send {}
+
+#[cfg(feature = "rayon")]
+impl<X: Send + ?Sized> MaybeSend for X {}
+
+#[cfg(not(feature = "rayon"))]
+pub trait MaybeSend {}
+
+#[cfg(not(feature = "rayon"))]
+impl<X: ?Sized> MaybeSend for X {}
+
+/// Like `MaybeSend`, for `Sync`.
+#[cfg(feature = "rayon")]
+pub trait MaybeSync: Sync {}
+
+#[cfg(feature = "rayon")]
+impl<X: Sync + ?Sized> MaybeSync for X {}
+
+#[cfg(not(feature = "rayon"))]
+pub trait MaybeSync {}
+
+#[cfg(not(feature = "rayon"))]
+impl<X: ?Sized> MaybeSync for X {}
+
+/// Computes forces on one particle at a time.
+///
+/// Particles are referred to by their index in the slice being simulated. A generator finds
+/// the force on `particles[index]`, and may read any other particles to do so. Effects that act
+/// on both ends (like gravitation between two bodies) are registered once for each particle.
+///
+/// Since computing a force can't change any particle, a registry is free to compute the forces
+/// on different particles in parallel.
+pub trait ParticleForceGenerator<T: Float> {
+    /// The force on `particles[index]` for the next `duration`.
+    fn force(&mut self, particles: &[Particle<T>], index: usize, duration: T) -> Vec3<T>;
+
+    /// Adds `force` to the force accumulator of `particles[index]`, for applying a generator
+    /// without a registry.
+    ///
+    /// A registry calls `force` and adds the result itself, so that it can compute forces in
+    /// parallel; an override here doesn't change what a registry applies.
+    fn update_force(&mut self, particles: &mut [Particle<T>], index: usize, duration: T) {
+        let force = self.force(particles, index, duration);
+        let particle = &mut particles[index];
+        particle.force_accum = particle.force_accum + force;
+    }
+
+    /// Moves the generator's own clock, if it keeps one, forward by `duration`.
+    ///
+    /// A registry calls this once per `update_forces` for each generator, after every force for
+    /// that frame has been found, however many particles the generator acts on. Generators whose
+    /// force changes over time should advance their clock here rather than in `force`.
+    fn advance(&mut self, _duration: T) {}
+
+    /// Whether the generator has broken, and will apply no more force.
+    ///
+    /// A registry reports a `BreakEvent` for each of its registrations the first time this is
+    /// true after applying them.
+    fn is_broken(&self) -> bool {
+        false
+    }
+}
+
+/// A generator that can be downcast back to its own type, for `generator_mut`.
+trait AnyGenerator<T: Float>: ParticleForceGenerator<T> {
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Float, G: ParticleForceGenerator<T> + 'static> AnyGenerator<T> for G {
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(feature = "rayon")]
+type BoxedGenerator<T> = Box<dyn AnyGenerator<T> + Send>;
+
+#[cfg(not(feature = "rayon"))]
+type BoxedGenerator<T> = Box<dyn AnyGenerator<T>>;
+
+/// Refers to one pairing of a particle and a generator in a `ParticleForceRegistry`.
+///
+/// Ids of removed registrations are never reused, so removing one twice does nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RegistrationId(Key);
+
+/// Refers to a generator in a `ParticleForceRegistry`, which may act on several particles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GeneratorId(Key);
+
+/// A registration whose generator broke, like a spring stretched past its `break_force`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BreakEvent<T: Float> {
+    pub registration: RegistrationId,
+    /// The index of the particle the registration acted on.
+    pub particle: usize,
+    /// Where that particle was when the generator broke.
+    pub position: Vec3<T>,
+}
+
+enum Generator<T: Float> {
+    /// Added with `add`, and used only by its one registration.
+    Owned(BoxedGenerator<T>),
+    /// Added with `add_generator`, and possibly used by several registrations.
+    Shared(Key),
+}
+
+struct Registration<T: Float> {
+    id: Key,
+    generator: Generator<T>,
+    priority: i32,
+    /// Whether a `BreakEvent` has been reported for this registration.
+    broken: bool,
+}
+
+struct SharedGenerator<T: Float> {
+    generator: BoxedGenerator<T>,
+    registrations: usize,
+}
+
+/// Pairs particles with the force generators that act on them.
+///
+/// A generator can be shared by several particles; it's dropped once its last registration is
+/// removed.
+///
+/// Forces are applied in a fixed order, so that a simulation replays identically no matter
+/// how its registrations were made: particle by particle in index order, and each particle's
+/// registrations by ascending priority, then in the order registered.
+///
+/// Generators that break are kept, applying no force, until they're removed. Each break is
+/// reported once, in that same order, by `drain_break_events`.
+#[allow(clippy::module_name_repetitions)]
+pub struct ParticleForceRegistry<T: Float> {
+    /// The particle each registration acts on.
+    particles: SlotMap<usize>,
+    /// Each particle's registrations, in the order they're applied.
+    groups: Vec<Vec<Registration<T>>>,
+    generators: SlotMap<SharedGenerator<T>>,
+    breaks: Vec<BreakEvent<T>>,
+}
+
+impl<T: Float> Default for ParticleForceRegistry<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Float> ParticleForceRegistry<T> {
+    pub fn new() -> Self {
+        Self {
+            particles: SlotMap::new(),
+            groups: Vec::new(),
+            generators: SlotMap::new(),
+            breaks: Vec::new(),
+        }
+    }
+
+    /// Registers `generator` to act on the particle at `particle` each `update_forces`, with a
+    /// priority of zero.
+    pub fn add<G>(&mut self, particle: usize, generator: G) -> RegistrationId
+    where
+        G: ParticleForceGenerator<T> + MaybeSend + 'static,
+    {
+        self.add_with_priority(particle, generator, 0)
+    }
+
+    /// Like `add`, but applied before registrations of higher priority and after lower ones.
+    pub fn add_with_priority<G>(
+        &mut self,
+        particle: usize,
+        generator: G,
+        priority: i32,
+    ) -> RegistrationId
+    where
+        G: ParticleForceGenerator<T> + MaybeSend + 'static,
+    {
+        self.insert(particle, Generator::Owned(Box::new(generator)), priority)
+    }
+
+    /// Stores `generator` without applying it to any particle yet; see `register`.
+    pub fn add_generator<G>(&mut self, generator: G) -> GeneratorId
+    where
+        G: ParticleForceGenerator<T> + MaybeSend + 'static,
+    {
+        GeneratorId(self.generators.insert(SharedGenerator {
+            generator: Box::new(generator),
+            registrations: 0,
+        }))
+    }
+
+    /// Registers a stored generator to also act on the particle at `particle`, with a priority
+    /// of zero.
+    ///
+    /// Returns `None` if the generator has been removed.
+    pub fn register(&mut self, particle: usize, generator: GeneratorId) -> Option<RegistrationId> {
+        self.register_with_priority(particle, generator, 0)
+    }
+
+    /// Like `register`, but applied before registrations of higher priority and after lower ones.
+    pub fn register_with_priority(
+        &mut self,
+        particle: usize,
+        generator: GeneratorId,
+        priority: i32,
+    ) -> Option<RegistrationId> {
+        self.generators.get_mut(generator.0)?.registrations += 1;
+        Some(self.insert(particle, Generator::Shared(generator.0), priority))
+    }
+
+    fn insert(
+        &mut self,
+        particle: usize,
+        generator: Generator<T>,
+        priority: i32,
+    ) -> RegistrationId {
+        let id = self.particles.insert(particle);
+        if self.groups.len() <= particle {
+            self.groups.resize_with(particle + 1, Vec::new);
+        }
+        let group = &mut self.groups[particle];
+        // After every registration of equal priority, since those were registered earlier.
+        let position = group
+            .iter()
+            .position(|registration| registration.priority > priority)
+            .unwrap_or(group.len());
+        group.insert(
+            position,
+            Registration {
+                id,
+                generator,
+                priority,
+                broken: false,
+            },
+        );
+        RegistrationId(id)
+    }
+
+    /// The generator behind a registration made with `register`, if the registration still
+    /// exists.
+    pub fn generator_of(&self, registration: RegistrationId) -> Option<GeneratorId> {
+        let particle = *self.particles.get(registration.0)?;
+        let registration = self.groups[particle]
+            .iter()
+            .find(|r| r.id == registration.0)
+            .unwrap();
+        match registration.generator {
+            Generator::Owned(_) => None,
+            Generator::Shared(key) => Some(GeneratorId(key)),
+        }
+    }
+
+    /// The generator a registration applies, for changing its settings while it's registered.
+    ///
+    /// Returns `None` if the registration has been removed, or its generator isn't a `G`. A
+    /// generator shared by several registrations is the same one through each of them.
+    pub fn generator_mut<G>(&mut self, registration: RegistrationId) -> Option<&mut G>
+    where
+        G: ParticleForceGenerator<T> + 'static,
+    {
+        let particle = *self.particles.get(registration.0)?;
+        let registration = self.groups[particle]
+            .iter_mut()
+            .find(|r| r.id == registration.0)
+            .unwrap();
+        let generator = match &mut registration.generator {
+            Generator::Owned(generator) => generator,
+            Generator::Shared(key) => &mut self.generators.get_mut(*key).unwrap().generator,
+        };
+        (**generator).as_any_mut().downcast_mut()
+    }
+
+    /// Like `generator_mut`, for a generator stored with `add_generator`, whether or not it's
+    /// registered with any particle yet.
+    pub fn shared_generator_mut<G>(&mut self, generator: GeneratorId) -> Option<&mut G>
+    where
+        G: ParticleForceGenerator<T> + 'static,
+    {
+        let shared = self.generators.get_mut(generator.0)?;
+        (*shared.generator).as_any_mut().downcast_mut()
+    }
+
+    /// Removes one registration, returning false if it was already removed.
+    pub fn remove(&mut self, registration: RegistrationId) -> bool {
+        let particle = match self.particles.remove(registration.0) {
+            Some(particle) => particle,
+            None => return false,
+        };
+        let group = &mut self.groups[particle];
+        let position = group.iter().position(|r| r.id == registration.0).unwrap();
+        if let Generator::Shared(key) = group.remove(position).generator {
+            self.release(key);
+        }
+        true
+    }
+
+    /// Removes every registration acting on the particle at `particle`, returning how many.
+    pub fn remove_all_for_particle(&mut self, particle: usize) -> usize {
+        let group = match self.groups.get_mut(particle) {
+            Some(group) => std::mem::take(group),
+            None => return 0,
+        };
+        for registration in &group {
+            self.particles.remove(registration.id);
+            if let Generator::Shared(key) = registration.generator {
+                self.release(key);
+            }
+        }
+        group.len()
+    }
+
+    /// Removes a generator and every registration using it, returning how many registrations.
+    pub fn remove_all_of_generator(&mut self, generator: GeneratorId) -> usize {
+        if self.generators.remove(generator.0).is_none() {
+            return 0;
+        }
+        let before = self.particles.len();
+        let particles = &mut self.particles;
+        for group in &mut self.groups {
+            group.retain(|registration| match registration.generator {
+                Generator::Shared(key) if key == generator.0 => {
+                    particles.remove(registration.id);
+                    false
+                }
+                _ => true,
+            });
+        }
+        before - self.particles.len()
+    }
+
+    fn release(&mut self, generator: Key) {
+        let shared = self.generators.get_mut(generator).unwrap();
+        shared.registrations -= 1;
+        if shared.registrations == 0 {
+            self.generators.remove(generator);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.particles.clear();
+        self.groups.clear();
+        self.generators.clear();
+        self.breaks.clear();
+    }
+
+    /// The number of registrations.
+    pub fn len(&self) -> usize {
+        self.particles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.particles.len() == 0
+    }
+
+    /// Adds every registered force to the particles' force accumulators, on this thread, then
+    /// advances each generator's clock (see `ParticleForceGenerator::advance`).
+    ///
+    /// This gives exactly the same results as `update_forces`, even when that runs in parallel.
+    /// It doesn't integrate; call `Particle::integrate` afterward.
+    pub fn update_forces_serial(&mut self, particles: &mut [Particle<T>], duration: T) {
+        for index in 0..self.groups.len() {
+            self.update_particle(particles, index, duration);
+        }
+        self.advance(duration);
+    }
+
+    /// Advances each generator, owned or shared, once.
+    fn advance(&mut self, duration: T) {
+        for registration in self.groups.iter_mut().flatten() {
+            if let Generator::Owned(generator) = &mut registration.generator {
+                generator.advance(duration);
+            }
+        }
+        for (_, shared) in self.generators.iter_mut() {
+            shared.generator.advance(duration);
+        }
+    }
+
+    fn update_particle(&mut self, particles: &mut [Particle<T>], index: usize, duration: T) {
+        for registration in &mut self.groups[index] {
+            let generator = match &mut registration.generator {
+                Generator::Owned(generator) => generator,
+                Generator::Shared(key) => &mut self.generators.get_mut(*key).unwrap().generator,
+            };
+            let force = generator.force(particles, index, duration);
+            let particle = &mut particles[index];
+            particle.force_accum = particle.force_accum + force;
+            if !registration.broken && generator.is_broken() {
+                registration.broken = true;
+                self.breaks.push(BreakEvent {
+                    registration: RegistrationId(registration.id),
+                    particle: index,
+                    position: particles[index].position,
+                });
+            }
+        }
+    }
+
+    /// Reports the generators in the group for `particles[index]` that have newly broken.
+    #[cfg(feature = "rayon")]
+    fn report_breaks(&mut self, particles: &[Particle<T>], index: usize) {
+        for registration in &mut self.groups[index] {
+            if let Generator::Owned(generator) = &registration.generator {
+                if !registration.broken && generator.is_broken() {
+                    registration.broken = true;
+                    self.breaks.push(BreakEvent {
+                        registration: RegistrationId(registration.id),
+                        particle: index,
+                        position: particles[index].position,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Takes the breaks reported since the last call, oldest first.
+    pub fn drain_break_events(&mut self) -> std::vec::Drain<'_, BreakEvent<T>> {
+        self.breaks.drain(..)
+    }
+
+    /// Adds every registered force to the particles' force accumulators.
+    ///
+    /// This doesn't integrate; call `Particle::integrate` afterward.
+    #[cfg(not(feature = "rayon"))]
+    pub fn update_forces(&mut self, particles: &mut [Particle<T>], duration: T) {
+        self.update_forces_serial(particles, duration);
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<T: Float + Send + Sync> ParticleForceRegistry<T> {
+    /// Adds every registered force to the particles' force accumulators.
+    ///
+    /// Particles are spread over threads, each applying all of one particle's forces in order,
+    /// so the results match `update_forces_serial` exactly. Particles using a generator shared
+    /// with other particles are handled afterward on this thread, since a generator can't be
+    /// used by two threads at once.
+    ///
+    /// This doesn't integrate; call `Particle::integrate` afterward.
+    pub fn update_forces(&mut self, particles: &mut [Particle<T>], duration: T) {
+        // Each group's forces, in order, and whether any of its generators broke.
+        let forces: Vec<Option<(Vec3<T>, bool)>> = {
+            let particles = &*particles;
+            self.groups
+                .par_iter_mut()
+                .enumerate()
+                .map(|(index, group)| {
+                    let shared = |r: &Registration<T>| match r.generator {
+                        Generator::Owned(_) => false,
+                        Generator::Shared(_) => true,
+                    };
+                    // Empty groups, like those of particles that have all their registrations
+                    // removed, are left to the serial pass, which skips them.
+                    if group.is_empty() || group.iter().any(shared) {
+                        return None;
+                    }
+                    let mut force = particles[index].force_accum;
+                    let mut broke = false;
+                    for registration in group.iter_mut() {
+                        if let Generator::Owned(generator) = &mut registration.generator {
+                            force = force + generator.force(particles, index, duration);
+                            broke |= !registration.broken && generator.is_broken();
+                        }
+                    }
+                    Some((force, broke))
+                })
+                .collect()
+        };
+        for (index, force) in forces.into_iter().enumerate() {
+            match force {
+                Some((force, broke)) => {
+                    particles[index].force_accum = force;
+                    if broke {
+                        self.report_breaks(particles, index);
+                    }
+                }
+                None => self.update_particle(particles, index, duration),
+            }
+        }
+        self.advance(duration);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::force::{ForceFn, ParticleForceGenerator, ParticleForceRegistry, ParticleGravity};
+    use crate::particle::Particle;
+    use crate::vec::Vec3;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn free_fall() {
+        let gravity = Vec3(0.0, -9.8, 0.0);
+        let mut particles = vec![
+            Particle {
+                inverse_mass: 0.25,
+                ..Particle::default()
+            },
+            Particle {
+                inverse_mass: 2.0,
+                ..Particle::default()
+            },
+        ];
+        let mut registry = ParticleForceRegistry::new();
+        registry.add(0, ParticleGravity::new(gravity));
+        registry.add(1, ParticleGravity::new(gravity));
+        let steps = 1000;
+        let duration = 1.0 / f64::from(steps);
+        for _ in 0..steps {
+            registry.update_forces(&mut particles, duration);
+            for particle in &mut particles {
+                particle.integrate(duration);
+            }
+        }
+        // y = ½gt², with one step of lag from integrating position before velocity.
+        for particle in &particles {
+            assert_approx_eq!(particle.position.1, 0.5 * -9.8, 0.01);
+            assert_approx_eq!(particle.velocity.1, -9.8, 1e-9);
+            assert_eq!(particle.position.0, 0.0);
+            assert_eq!(particle.position.2, 0.0);
+        }
+    }
+
+    #[test]
+    fn clear() {
+        let mut registry = ParticleForceRegistry::<f64>::new();
+        assert!(registry.is_empty());
+        registry.add(0, ParticleGravity::new(Vec3(0.0, -9.8, 0.0)));
+        assert_eq!(registry.len(), 1);
+        registry.clear();
+        assert!(registry.is_empty());
+    }
+
+    fn gravity_on(registry: &mut ParticleForceRegistry<f64>, particles: &mut [Particle<f64>]) {
+        for particle in particles.iter_mut() {
+            particle.clear_accumulator();
+        }
+        registry.update_forces(particles, 0.1);
+    }
+
+    #[test]
+    fn remove() {
+        let mut particles = vec![Particle::default()];
+        let mut registry = ParticleForceRegistry::new();
+        let first = registry.add(0, ParticleGravity::new(Vec3(0.0, -1.0, 0.0)));
+        let second = registry.add(0, ParticleGravity::new(Vec3(0.0, 0.0, 2.0)));
+        assert!(registry.remove(first));
+        assert_eq!(registry.len(), 1);
+        gravity_on(&mut registry, &mut particles);
+        assert_eq!(particles[0].force_accum, Vec3(0.0, 0.0, 2.0));
+        // Stale ids are rejected, even once their slot is reused.
+        assert!(!registry.remove(first));
+        let third = registry.add(0, ParticleGravity::new(Vec3(3.0, 0.0, 0.0)));
+        assert!(!registry.remove(first));
+        assert_eq!(registry.generator_of(first), None);
+        assert_eq!(registry.len(), 2);
+        assert!(registry.remove(second));
+        assert!(registry.remove(third));
+        assert!(registry.is_empty());
+    }
+
+    #[test]
+    fn remove_all_for_particle() {
+        let mut particles = vec![Particle::default(), Particle::default()];
+        let mut registry = ParticleForceRegistry::new();
+        let gravity = registry.add_generator(ParticleGravity::new(Vec3(0.0, -1.0, 0.0)));
+        registry.register(0, gravity).unwrap();
+        registry.register(1, gravity).unwrap();
+        registry.add(0, ParticleGravity::new(Vec3(0.0, 0.0, 2.0)));
+        assert_eq!(registry.remove_all_for_particle(0), 2);
+        assert_eq!(registry.remove_all_for_particle(0), 0);
+        gravity_on(&mut registry, &mut particles);
+        assert_eq!(particles[0].force_accum, Vec3(0.0, 0.0, 0.0));
+        assert_eq!(particles[1].force_accum, Vec3(0.0, -1.0, 0.0));
+        // The shared generator is dropped with its last registration.
+        assert_eq!(registry.remove_all_for_particle(1), 1);
+        assert_eq!(registry.register(0, gravity), None);
+    }
+
+    #[test]
+    fn remove_all_of_generator() {
+        let mut particles = vec![Particle::default(), Particle::default()];
+        let mut registry = ParticleForceRegistry::new();
+        let gravity = registry.add_generator(ParticleGravity::new(Vec3(0.0, -1.0, 0.0)));
+        let first = registry.register(0, gravity).unwrap();
+        registry.register(1, gravity).unwrap();
+        let other = registry.add(1, ParticleGravity::new(Vec3(0.0, 0.0, 2.0)));
+        assert_eq!(registry.generator_of(first), Some(gravity));
+        assert_eq!(registry.remove_all_of_generator(gravity), 2);
+        assert_eq!(registry.remove_all_of_generator(gravity), 0);
+        assert!(!registry.remove(first));
+        gravity_on(&mut registry, &mut particles);
+        assert_eq!(particles[0].force_accum, Vec3(0.0, 0.0, 0.0));
+        assert_eq!(particles[1].force_accum, Vec3(0.0, 0.0, 2.0));
+        assert!(registry.remove(other));
+    }
+
+    /// One step of a particle pushed by forces that sum differently depending on their order.
+    fn step(registrations: &[(i32, f64)]) -> Particle<f64> {
+        let mut particles = vec![Particle::default()];
+        let mut registry = ParticleForceRegistry::new();
+        for &(priority, force) in registrations {
+            let push = move |p: &mut Particle<f64>, _| p.add_force(Vec3(force, 0.0, 0.0));
+            registry.add_with_priority(0, ForceFn::new(push), priority);
+        }
+        registry.update_forces(&mut particles, 0.1);
+        particles[0].integrate(0.1);
+        particles[0]
+    }
+
+    #[test]
+    fn priority() {
+        let a = step(&[(0, 1e16), (1, 1.0), (2, -1e16)]);
+        let b = step(&[(2, -1e16), (0, 1e16), (1, 1.0)]);
+        let c = step(&[(1, 1.0), (2, -1e16), (0, 1e16)]);
+        assert_eq!(a, b);
+        assert_eq!(a, c);
+        // Equal priorities keep registration order, which here changes the result.
+        let d = step(&[(0, 1e16), (0, -1e16), (0, 1.0)]);
+        assert_ne!(a, d);
+        assert_eq!(d.velocity, Vec3(0.1, 0.0, 0.0));
+    }
+
+    /// A spring-net with a mix of owned and shared generators, some reading other particles.
+    #[cfg(feature = "rayon")]
+    fn net() -> (Vec<Particle<f64>>, ParticleForceRegistry<f64>) {
+        use crate::force::{ParticleDrag, ParticleSpring, ParticleWind};
+        let particles: Vec<_> = (0..200)
+            .map(|i| Particle {
+                position: Vec3(f64::from(i).sin(), f64::from(i) * 0.1, f64::from(i).cos()),
+                velocity: Vec3(0.1, f64::from(i % 7), -0.3),
+                ..Particle::default()
+            })
+            .collect();
+        let mut registry = ParticleForceRegistry::new();
+        let shared_gravity = registry.add_generator(ParticleGravity::new(Vec3(0.0, -9.8, 0.0)));
+        for i in 0..particles.len() {
+            if i % 10 == 0 {
+                registry.register(i, shared_gravity).unwrap();
+            } else {
+                registry.add(i, ParticleGravity::new(Vec3(0.0, -9.8, 0.0)));
+            }
+            registry.add_with_priority(i, ParticleDrag::new(0.1, 0.01), 1);
+            let spring = ParticleSpring::new((i + 1) % particles.len(), 3.0, 0.2);
+            if i % 3 == 0 {
+                registry.add(i, spring.with_break_force(4.0));
+            } else {
+                registry.add(i, spring);
+            }
+            registry.add_with_priority(
+                i,
+                ParticleWind::new(Vec3(1.0, 0.0, 0.0), 0.3).with_sine_turbulence(0.5, 2.0, 3),
+                -1,
+            );
+        }
+        (particles, registry)
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_matches_serial() {
+        let (mut parallel, mut parallel_registry) = net();
+        let (mut serial, mut serial_registry) = net();
+        for _ in 0..50 {
+            parallel_registry.update_forces(&mut parallel, 0.01);
+            serial_registry.update_forces_serial(&mut serial, 0.01);
+            for (a, b) in parallel.iter().zip(&serial) {
+                assert_eq!(a.force_accum.0.to_bits(), b.force_accum.0.to_bits());
+                assert_eq!(a.force_accum.1.to_bits(), b.force_accum.1.to_bits());
+                assert_eq!(a.force_accum.2.to_bits(), b.force_accum.2.to_bits());
+            }
+            assert!(parallel_registry
+                .drain_break_events()
+                .eq(serial_registry.drain_break_events()));
+            for particle in parallel.iter_mut().chain(serial.iter_mut()) {
+                particle.integrate(0.01);
+            }
+        }
+        assert_eq!(parallel, serial);
+    }
+
+    #[test]
+    fn generator_mut() {
+        let mut particles = vec![Particle::default(); 2];
+        let mut registry = ParticleForceRegistry::new();
+        let owned = registry.add(0, ParticleGravity::new(Vec3(0.0, -10.0, 0.0)));
+        let generator = registry.add_generator(ParticleGravity::new(Vec3(0.0, -1.0, 0.0)));
+        let shared = registry.register(1, generator).unwrap();
+        registry
+            .generator_mut::<ParticleGravity<f64>>(owned)
+            .unwrap()
+            .gravity = Vec3(5.0, 0.0, 0.0);
+        registry
+            .generator_mut::<ParticleGravity<f64>>(shared)
+            .unwrap()
+            .gravity = Vec3(0.0, 0.0, 2.0);
+        registry.update_forces(&mut particles, 0.1);
+        assert_eq!(particles[0].force_accum, Vec3(5.0, 0.0, 0.0));
+        assert_eq!(particles[1].force_accum, Vec3(0.0, 0.0, 2.0));
+        assert_eq!(
+            registry
+                .shared_generator_mut::<ParticleGravity<f64>>(generator)
+                .unwrap()
+                .gravity,
+            Vec3(0.0, 0.0, 2.0)
+        );
+        // The wrong type, or a removed registration, gives nothing.
+        assert!(registry.generator_mut::<Doubled>(owned).is_none());
+        registry.remove(owned);
+        assert!(registry
+            .generator_mut::<ParticleGravity<f64>>(owned)
+            .is_none());
+    }
+
+    /// Doubles the force it finds when applied by hand, by overriding `update_force`.
+    struct Doubled;
+
+    impl ParticleForceGenerator<f64> for Doubled {
+        fn force(&mut self, _: &[Particle<f64>], _: usize, _: f64) -> Vec3<f64> {
+            Vec3(1.0, 0.0, 0.0)
+        }
+
+        fn update_force(&mut self, particles: &mut [Particle<f64>], index: usize, duration: f64) {
+            let force = self.force(particles, index, duration) * 2.0;
+            particles[index].add_force(force);
+        }
+    }
+
+    #[test]
+    fn overridden_update_force() {
+        let mut registry = ParticleForceRegistry::new();
+        registry.add(0, Doubled);
+        let removed = registry.add(5, Doubled);
+        registry.remove(removed);
+        // The removed registration leaves more groups than particles, which are skipped.
+        let mut particles = vec![Particle::default(); 2];
+        // Registries apply `force`, on either path.
+        registry.update_forces(&mut particles, 0.1);
+        assert_eq!(particles[0].force_accum, Vec3(1.0, 0.0, 0.0));
+        assert_eq!(particles[1].force_accum, Vec3(0.0, 0.0, 0.0));
+        registry.update_forces_serial(&mut particles, 0.1);
+        assert_eq!(particles[0].force_accum, Vec3(2.0, 0.0, 0.0));
+        Doubled.update_force(&mut particles, 1, 0.1);
+        assert_eq!(particles[1].force_accum, Vec3(2.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn breaks() {
+        use crate::force::ParticleAnchoredSpring;
+        let mut particles = vec![Particle::default(), Particle::default()];
+        let mut registry = ParticleForceRegistry::new();
+        let anchor = Vec3(0.0, 0.0, 0.0);
+        let weak = registry.add(
+            0,
+            ParticleAnchoredSpring::new(anchor, 10.0, 1.0).with_break_force(15.0),
+        );
+        registry.add(
+            1,
+            ParticleAnchoredSpring::new(anchor, 10.0, 1.0).with_break_force(100.0),
+        );
+        let mut events = Vec::new();
+        for step in 1..=50 {
+            let x = 1.0 + f64::from(step) * 0.1;
+            for particle in &mut particles {
+                particle.position = Vec3(x, 0.0, 0.0);
+                particle.clear_accumulator();
+            }
+            registry.update_forces(&mut particles, 0.1);
+            events.extend(registry.drain_break_events());
+            if x > 2.5 {
+                assert_eq!(particles[0].force_accum, Vec3(0.0, 0.0, 0.0));
+            } else {
+                assert_eq!(particles[0].force_accum, particles[1].force_accum);
+            }
+        }
+        // The strong spring reached 50 of its 100, so only the weak one broke, at 16 > 15.
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].registration, weak);
+        assert_eq!(events[0].particle, 0);
+        assert_approx_eq!(events[0].position.0, 2.6);
+        assert_eq!(particles[1].force_accum, Vec3(-50.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn order_survives_removal() {
+        let mut particles = vec![Particle::default()];
+        let mut registry = ParticleForceRegistry::new();
+        let push = |force: f64| {
+            ForceFn::new(move |p: &mut Particle<f64>, _| p.add_force(Vec3(force, 0.0, 0.0)))
+        };
+        let removed = registry.add(0, push(5.0));
+        registry.add(0, push(1.0));
+        registry.update_forces(&mut particles, 0.1);
+        registry.remove(removed);
+        // This reuses the removed registration's slot, but still runs last.
+        registry.add(0, push(-1e16));
+        particles[0].force_accum = Vec3(1e16, 0.0, 0.0);
+        registry.update_forces(&mut particles, 0.1);
+        assert_eq!(particles[0].force_accum, Vec3(0.0, 0.0, 0.0));
+    }
+}
@@ -0,0 +1,144 @@
+use crate::force::ParticleForceGenerator;
+use crate::particle::Particle;
+use crate::vec::Vec3;
+use num::Float;
+use num_traits::NumAssign;
+
+/// Buoyancy from a flat liquid surface.
+///
+/// The particle is treated as a body extending `max_depth` above and below its position. It is
+/// out of the liquid once its position is `max_depth` above `liquid_height`, fully submerged
+/// once it is `max_depth` below, and partially submerged (with linearly interpolated force)
+/// in between. A fully submerged particle is pushed along `up` by `liquid_density * volume`,
+/// so `liquid_density` is weight per unit volume, with gravity already multiplied in.
+///
+/// `up` is `+y` by default. `liquid_height` is measured along `up`, and `up` must be unit length.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParticleBuoyancy<T: Float> {
+    pub max_depth: T,
+    pub volume: T,
+    pub liquid_height: T,
+    pub liquid_density: T,
+    pub up: Vec3<T>,
+}
+
+impl<T: Float> ParticleBuoyancy<T> {
+    pub fn new(max_depth: T, volume: T, liquid_height: T, liquid_density: T) -> Self {
+        Self {
+            max_depth,
+            volume,
+            liquid_height,
+            liquid_density,
+            up: Vec3(T::zero(), T::one(), T::zero()),
+        }
+    }
+}
+
+impl<T: Float + NumAssign> ParticleForceGenerator<T> for ParticleBuoyancy<T> {
+    fn force(&mut self, particles: &[Particle<T>], index: usize, _duration: T) -> Vec3<T> {
+        let zero = Vec3(T::zero(), T::zero(), T::zero());
+        let particle = &particles[index];
+        let height = particle.position.dot(self.up);
+        if height >= self.liquid_height + self.max_depth {
+            return zero;
+        }
+        let full = self.liquid_density * self.volume;
+        if height <= self.liquid_height - self.max_depth {
+            return self.up * full;
+        }
+        let submerged =
+            (self.liquid_height + self.max_depth - height) / (self.max_depth + self.max_depth);
+        self.up * (full * submerged)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::force::{
+        ParticleBuoyancy, ParticleForceGenerator, ParticleForceRegistry, ParticleGravity,
+    };
+    use crate::particle::Particle;
+    use crate::vec::Vec3;
+    use assert_approx_eq::assert_approx_eq;
+
+    fn force_at(buoyancy: &mut ParticleBuoyancy<f64>, position: Vec3<f64>) -> Vec3<f64> {
+        let mut particles = vec![Particle {
+            position,
+            ..Particle::default()
+        }];
+        buoyancy.update_force(&mut particles, 0, 0.1);
+        particles[0].force_accum
+    }
+
+    #[test]
+    fn depths() {
+        let mut buoyancy = ParticleBuoyancy::new(0.5, 2.0, 1.0, 10.0);
+        assert_eq!(
+            force_at(&mut buoyancy, Vec3(0.0, 1.5, 0.0)),
+            Vec3(0.0, 0.0, 0.0)
+        );
+        assert_eq!(
+            force_at(&mut buoyancy, Vec3(0.0, 9.0, 0.0)),
+            Vec3(0.0, 0.0, 0.0)
+        );
+        assert_eq!(
+            force_at(&mut buoyancy, Vec3(0.0, 1.0, 0.0)),
+            Vec3(0.0, 10.0, 0.0)
+        );
+        assert_eq!(
+            force_at(&mut buoyancy, Vec3(0.0, 0.75, 0.0)),
+            Vec3(0.0, 15.0, 0.0)
+        );
+        assert_eq!(
+            force_at(&mut buoyancy, Vec3(0.0, 0.5, 0.0)),
+            Vec3(0.0, 20.0, 0.0)
+        );
+        assert_eq!(
+            force_at(&mut buoyancy, Vec3(0.0, -9.0, 0.0)),
+            Vec3(0.0, 20.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn up() {
+        let mut buoyancy = ParticleBuoyancy::new(0.5, 2.0, 1.0, 10.0);
+        buoyancy.up = Vec3(1.0, 0.0, 0.0);
+        assert_eq!(
+            force_at(&mut buoyancy, Vec3(1.0, 9.0, 0.0)),
+            Vec3(10.0, 0.0, 0.0)
+        );
+    }
+
+    /// Drops a particle from above the liquid and returns its height after it has come to rest.
+    fn settle(liquid_density: f64) -> f64 {
+        let mut particles = vec![Particle {
+            position: Vec3(0.0, 3.0, 0.0),
+            damping: 0.2,
+            ..Particle::default()
+        }];
+        let mut registry = ParticleForceRegistry::new();
+        registry.add(0, ParticleGravity::new(Vec3(0.0, -10.0, 0.0)));
+        registry.add(0, ParticleBuoyancy::new(0.5, 1.0, 1.0, liquid_density));
+        let mut lowest = f64::INFINITY;
+        for _ in 0..30_000 {
+            registry.update_forces(&mut particles, 0.001);
+            particles[0].integrate(0.001);
+            lowest = lowest.min(particles[0].position.1);
+        }
+        // It must have actually bobbed below its resting height on the way in.
+        assert!(lowest < particles[0].position.1 - 0.1);
+        particles[0].position.1
+    }
+
+    #[test]
+    fn floats() {
+        // Half submerged.
+        assert_approx_eq!(settle(20.0), 1.0, 1e-6);
+    }
+
+    #[test]
+    fn sinks() {
+        // Weight is balanced once 80% submerged.
+        assert_approx_eq!(settle(12.5), 0.7, 1e-6);
+    }
+}
@@ -0,0 +1,205 @@
+use crate::force::ParticleForceGenerator;
+use crate::particle::Particle;
+use crate::vec::Vec3;
+use num::Float;
+
+/// Wraps a closure as a force generator, for one-off forces that don't deserve their own type.
+///
+/// The closure receives a copy of the registered particle and the frame's duration. Only the
+/// force it adds is kept; any other change it makes to the copy is discarded.
+/// ```
+/// # use cyclone::force::{ForceFn, ParticleForceRegistry};
+/// # use cyclone::particle::Particle;
+/// # use cyclone::vec::Vec3;
+/// let mut particles = vec![Particle {
+///     position: Vec3(2.0, 0.0, 0.0),
+///     ..Particle::default()
+/// }];
+/// let mut registry = ParticleForceRegistry::new();
+/// // Suck toward the origin.
+/// registry.add(0, ForceFn::new(|p: &mut Particle<f64>, _| p.add_force(p.position * -3.0)));
+/// registry.update_forces(&mut particles, 0.1);
+/// assert_eq!(particles[0].force_accum, Vec3(-6.0, 0.0, 0.0));
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct ForceFn<F> {
+    f: F,
+}
+
+impl<F> ForceFn<F> {
+    pub fn new(f: F) -> Self {
+        Self { f }
+    }
+}
+
+impl<T, F> ParticleForceGenerator<T> for ForceFn<F>
+where
+    T: Float,
+    F: FnMut(&mut Particle<T>, T),
+{
+    fn force(&mut self, particles: &[Particle<T>], index: usize, duration: T) -> Vec3<T> {
+        let mut particle = particles[index];
+        particle.force_accum = Vec3(T::zero(), T::zero(), T::zero());
+        (self.f)(&mut particle, duration);
+        particle.force_accum
+    }
+}
+
+/// A force scripted as a function of the particle and the simulated time, for pulsing fields,
+/// timed explosions, and the like.
+///
+/// The generator keeps its own clock, which a registry advances by each frame's duration once
+/// every force for the frame is found, so the closure sees the time at the start of the frame
+/// on every particle it acts on. The clock starts at zero, or at `starting_at`: to keep in step
+/// with a world that has already run for a while, start it at `ParticleWorld::elapsed`.
+/// ```
+/// # use cyclone::force::ClosureForce;
+/// # use cyclone::particle::Particle;
+/// # use cyclone::vec::Vec3;
+/// # use cyclone::world::ParticleWorld;
+/// let mut world = ParticleWorld::new(0);
+/// let particle = world.add_particle(Particle::default());
+/// // Push along +X for the first second only.
+/// let push = |_: &Particle<f64>, time: f64| Vec3(if time < 1.0 { 1.0 } else { 0.0 }, 0.0, 0.0);
+/// world.add_force(particle, ClosureForce::new(push).starting_at(world.elapsed()));
+/// for _ in 0..8 {
+///     world.start_frame();
+///     world.run_physics(0.25);
+/// }
+/// assert_eq!(world.particle(particle).unwrap().velocity.0, 1.0);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct ClosureForce<T, F> {
+    f: F,
+    time: T,
+}
+
+impl<T: Float, F: Fn(&Particle<T>, T) -> Vec3<T>> ClosureForce<T, F> {
+    pub fn new(f: F) -> Self {
+        Self { f, time: T::zero() }
+    }
+
+    /// Starts the generator's clock at `time` rather than zero.
+    pub fn starting_at(mut self, time: T) -> Self {
+        self.time = time;
+        self
+    }
+
+    /// The time the closure will be given next.
+    pub fn time(&self) -> T {
+        self.time
+    }
+}
+
+impl<T, F> ParticleForceGenerator<T> for ClosureForce<T, F>
+where
+    T: Float,
+    F: Fn(&Particle<T>, T) -> Vec3<T>,
+{
+    fn force(&mut self, particles: &[Particle<T>], index: usize, _duration: T) -> Vec3<T> {
+        (self.f)(&particles[index], self.time)
+    }
+
+    fn advance(&mut self, duration: T) {
+        self.time = self.time + duration;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::force::ParticleGravity;
+    use crate::force::{ClosureForce, ForceFn, ParticleForceGenerator, ParticleForceRegistry};
+    use crate::particle::Particle;
+    use crate::vec::Vec3;
+    use crate::world::ParticleWorld;
+
+    #[test]
+    fn with_gravity() {
+        let mut particles = vec![Particle::default(); 2];
+        let mut registry = ParticleForceRegistry::new();
+        registry.add(0, ParticleGravity::new(Vec3(0.0, -10.0, 0.0)));
+        registry.add(
+            0,
+            ForceFn::new(|p: &mut Particle<f64>, _| p.add_force(Vec3(0.0, 4.0, 1.0))),
+        );
+        registry.add(1, ParticleGravity::new(Vec3(0.0, -10.0, 0.0)));
+        registry.update_forces(&mut particles, 0.5);
+        assert_eq!(particles[0].force_accum, Vec3(0.0, -6.0, 1.0));
+        assert_eq!(particles[1].force_accum, Vec3(0.0, -10.0, 0.0));
+    }
+
+    #[test]
+    fn stateful() {
+        let mut particles = vec![Particle::default()];
+        let mut registry = ParticleForceRegistry::new();
+        let mut elapsed = 0.0;
+        registry.add(
+            0,
+            ForceFn::new(move |p: &mut Particle<f64>, duration| {
+                elapsed += duration;
+                p.add_force(Vec3(elapsed, 0.0, 0.0));
+            }),
+        );
+        registry.update_forces(&mut particles, 0.5);
+        registry.update_forces(&mut particles, 0.5);
+        assert_eq!(particles[0].force_accum, Vec3(1.5, 0.0, 0.0));
+    }
+
+    #[test]
+    fn closure_constant() {
+        let particles = vec![Particle::default()];
+        let mut force = ClosureForce::new(|_: &Particle<f64>, _| Vec3(0.0, 2.0, -1.0));
+        for _ in 0..3 {
+            assert_eq!(force.force(&particles, 0, 0.25), Vec3(0.0, 2.0, -1.0));
+            force.advance(0.25);
+        }
+        assert_eq!(force.time(), 0.75);
+    }
+
+    #[test]
+    fn closure_shared() {
+        let mut world = ParticleWorld::new(0);
+        let handles = [
+            world.add_particle(Particle::default()),
+            world.add_particle(Particle::default()),
+        ];
+        // Push along +X for the first second only, on both particles at once.
+        type Push = fn(&Particle<f64>, f64) -> Vec3<f64>;
+        let push: Push = |_, time| Vec3(if time < 1.0 { 1.0 } else { 0.0 }, 0.0, 0.0);
+        let generator = world.registry_mut().add_generator(ClosureForce::new(push));
+        for handle in &handles {
+            world.registry_mut().register(handle.index(), generator);
+        }
+        for _ in 0..8 {
+            world.start_frame();
+            world.run_physics(0.25);
+        }
+        for &handle in &handles {
+            assert_eq!(world.particle(handle).unwrap().velocity.0, 1.0);
+        }
+        let elapsed = world.elapsed();
+        let force = world
+            .registry_mut()
+            .shared_generator_mut::<ClosureForce<f64, Push>>(generator)
+            .unwrap();
+        assert_eq!(force.time(), elapsed);
+    }
+
+    #[test]
+    fn closure_ramps() {
+        let mut particles = vec![Particle {
+            inverse_mass: 0.5,
+            ..Particle::default()
+        }];
+        let mut registry = ParticleForceRegistry::new();
+        let ramp = |p: &Particle<f64>, time| Vec3(time / p.inverse_mass, 0.0, 0.0);
+        registry.add(0, ClosureForce::new(ramp).starting_at(1.0));
+        let mut seen = Vec::new();
+        for _ in 0..4 {
+            registry.update_forces(&mut particles, 0.5);
+            seen.push(particles[0].force_accum.0);
+            particles[0].clear_accumulator();
+        }
+        assert_eq!(seen, vec![2.0, 3.0, 4.0, 5.0]);
+    }
+}
@@ -0,0 +1,379 @@
+use crate::force::{MaybeSend, ParticleForceGenerator};
+use crate::math;
+use crate::particle::Particle;
+use crate::vec::Vec3;
+use num::Float;
+use num_traits::NumAssign;
+
+/// Drag opposing the particle's velocity, with magnitude `k1 * speed + k2 * speed²`.
+///
+/// The magnitude is clamped to `T::max_value()`, so speeds large enough to overflow
+/// `speed²` produce a huge but finite force rather than infinities and NaN.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParticleDrag<T: Float> {
+    pub k1: T,
+    pub k2: T,
+}
+
+impl<T: Float> ParticleDrag<T> {
+    pub fn new(k1: T, k2: T) -> Self {
+        Self { k1, k2 }
+    }
+}
+
+impl<T: Float + NumAssign> ParticleForceGenerator<T> for ParticleDrag<T> {
+    fn force(&mut self, particles: &[Particle<T>], index: usize, _duration: T) -> Vec3<T> {
+        let particle = &particles[index];
+        match speed_and_direction(particle.velocity) {
+            Some((speed, direction)) => {
+                let drag = (self.k1 * speed + self.k2 * speed * speed).min(T::max_value());
+                direction * -drag
+            }
+            None => Vec3(T::zero(), T::zero(), T::zero()),
+        }
+    }
+}
+
+/// The speed and direction of a nonzero velocity, with speed clamped to `T::max_value()`.
+fn speed_and_direction<T: Float + NumAssign>(velocity: Vec3<T>) -> Option<(T, Vec3<T>)> {
+    // Scaling by the largest component first keeps `mag` from overflowing.
+    let largest = velocity.0.abs().max(velocity.1.abs()).max(velocity.2.abs());
+    if largest.is_zero() {
+        return None;
+    }
+    let scaled = velocity / largest;
+    Some(((largest * scaled.mag()).min(T::max_value()), scaled.norm()))
+}
+
+/// Quadratic drag in an atmosphere that thins exponentially with altitude.
+///
+/// The air density at height `y` is `sea_level_density * exp(-y / scale_height)`, and the
+/// drag is `½ * density * speed² * drag_area` opposite the velocity, where `drag_area` is the
+/// drag coefficient times the cross-sectional area. Below `y = 0` the density stays at
+/// `sea_level_density`. As with `ParticleDrag`, the force is clamped to `T::max_value()`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParticleAtmosphericDrag<T: Float> {
+    pub sea_level_density: T,
+    pub scale_height: T,
+    pub drag_area: T,
+}
+
+impl<T: Float> ParticleAtmosphericDrag<T> {
+    pub fn new(sea_level_density: T, scale_height: T, drag_area: T) -> Self {
+        Self {
+            sea_level_density,
+            scale_height,
+            drag_area,
+        }
+    }
+
+    pub fn density_at(&self, altitude: T) -> T {
+        self.sea_level_density * math::exp(-altitude.max(T::zero()) / self.scale_height)
+    }
+}
+
+impl<T: Float + NumAssign> ParticleForceGenerator<T> for ParticleAtmosphericDrag<T> {
+    fn force(&mut self, particles: &[Particle<T>], index: usize, _duration: T) -> Vec3<T> {
+        let particle = &particles[index];
+        match speed_and_direction(particle.velocity) {
+            Some((speed, direction)) => {
+                let half = T::from(0.5).unwrap();
+                let density = self.density_at(particle.position.1);
+                let drag = (half * density * speed * speed * self.drag_area).min(T::max_value());
+                direction * -drag
+            }
+            None => Vec3(T::zero(), T::zero(), T::zero()),
+        }
+    }
+}
+
+/// A velocity perturbation as a function of position and time.
+#[cfg(not(feature = "rayon"))]
+pub type Turbulence<T> = Box<dyn Fn(Vec3<T>, T) -> Vec3<T>>;
+
+/// A velocity perturbation as a function of position and time.
+#[cfg(feature = "rayon")]
+pub type Turbulence<T> = Box<dyn Fn(Vec3<T>, T) -> Vec3<T> + Send>;
+
+/// Pushes particles toward the wind's velocity with the force `coefficient * (wind - velocity)`.
+///
+/// The wind may be perturbed by turbulence: a function of position and time giving a velocity
+/// to add to the base wind. Each wind keeps its own clock, starting at zero, which a registry
+/// advances by each frame's duration once per frame, however many particles the wind blows on.
+pub struct ParticleWind<T: Float> {
+    pub wind: Vec3<T>,
+    pub coefficient: T,
+    pub time: T,
+    turbulence: Option<Turbulence<T>>,
+}
+
+impl<T: Float + NumAssign + MaybeSend + 'static> ParticleWind<T> {
+    pub fn new(wind: Vec3<T>, coefficient: T) -> Self {
+        Self {
+            wind,
+            coefficient,
+            time: T::zero(),
+            turbulence: None,
+        }
+    }
+
+    /// Adds `turbulence(position, time)` to the wind velocity.
+    pub fn with_turbulence<F>(mut self, turbulence: F) -> Self
+    where
+        F: Fn(Vec3<T>, T) -> Vec3<T> + MaybeSend + 'static,
+    {
+        self.turbulence = Some(Box::new(turbulence));
+        self
+    }
+
+    /// Turbulence built from `octaves` layers of sine waves, varying over both space and time.
+    ///
+    /// Each layer has twice the frequency and half the amplitude of the one before, so the
+    /// perturbation never exceeds `amplitude * 2` in any component.
+    pub fn with_sine_turbulence(self, amplitude: T, frequency: T, octaves: u32) -> Self {
+        let c = |x: f64| T::from(x).unwrap();
+        let phases = (
+            Vec3(c(1.0), c(0.31), c(0.57)),
+            Vec3(c(0.43), c(1.0), c(0.29)),
+            Vec3(c(0.37), c(0.61), c(1.0)),
+        );
+        self.with_turbulence(move |position, time| {
+            let mut turbulence = Vec3(T::zero(), T::zero(), T::zero());
+            let mut scale = T::one();
+            for _ in 0..octaves {
+                let wave = |phase: Vec3<T>| {
+                    math::sin_cos((position.dot(phase) + time) * frequency * scale).0 * amplitude
+                        / scale
+                };
+                turbulence += Vec3(wave(phases.0), wave(phases.1), wave(phases.2));
+                scale += scale;
+            }
+            turbulence
+        })
+    }
+
+    /// The wind velocity at `position`, at the current time.
+    pub fn wind_at(&self, position: Vec3<T>) -> Vec3<T> {
+        match &self.turbulence {
+            Some(turbulence) => self.wind + turbulence(position, self.time),
+            None => self.wind,
+        }
+    }
+}
+
+impl<T: Float + NumAssign + MaybeSend + 'static> ParticleForceGenerator<T> for ParticleWind<T> {
+    fn force(&mut self, particles: &[Particle<T>], index: usize, _duration: T) -> Vec3<T> {
+        let particle = &particles[index];
+        let relative = self.wind_at(particle.position) - particle.velocity;
+        relative * self.coefficient
+    }
+
+    fn advance(&mut self, duration: T) {
+        self.time += duration;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::force::{
+        MaybeSend, ParticleAtmosphericDrag, ParticleDrag, ParticleForceGenerator,
+        ParticleForceRegistry, ParticleGravity, ParticleWind,
+    };
+    use crate::particle::Particle;
+    use crate::vec::Vec3;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn drag() {
+        let mut particles = vec![Particle {
+            velocity: Vec3(0.0, 0.0, -2.0),
+            ..Particle::default()
+        }];
+        ParticleDrag::new(1.0, 0.5).update_force(&mut particles, 0, 0.1);
+        assert_eq!(particles[0].force_accum, Vec3(0.0, 0.0, 4.0));
+    }
+
+    #[test]
+    fn stationary() {
+        let mut particles = vec![Particle::<f64>::default()];
+        ParticleDrag::new(1.0, 0.5).update_force(&mut particles, 0, 0.1);
+        assert_eq!(particles[0].force_accum, Vec3(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn overflow() {
+        let mut particles = vec![Particle {
+            velocity: Vec3(1e200_f64, 0.0, 0.0),
+            ..Particle::default()
+        }];
+        ParticleDrag::new(1.0, 1.0).update_force(&mut particles, 0, 0.1);
+        assert!(particles[0].force_accum.0.is_finite());
+        assert!(particles[0].force_accum.0 < 0.0);
+        assert_eq!(particles[0].force_accum.1, 0.0);
+    }
+
+    #[test]
+    fn terminal_velocity() {
+        let mut particles = vec![Particle {
+            inverse_mass: 0.5,
+            ..Particle::default()
+        }];
+        let mut registry = ParticleForceRegistry::new();
+        registry.add(0, ParticleGravity::new(Vec3(0.0, -9.8, 0.0)));
+        registry.add(0, ParticleDrag::new(0.0, 0.1));
+        let duration = 0.001;
+        for _ in 0..20_000 {
+            registry.update_forces(&mut particles, duration);
+            particles[0].integrate(duration);
+        }
+        // m * g = k2 * v²
+        let terminal = (2.0 * 9.8 / 0.1_f64).sqrt();
+        assert_approx_eq!(particles[0].velocity.1, -terminal, 1e-3);
+    }
+
+    #[test]
+    fn atmospheric_half_density() {
+        let mut drag = ParticleAtmosphericDrag::new(1.2, 8000.0, 0.5);
+        let force_at = |drag: &mut ParticleAtmosphericDrag<f64>, y: f64| {
+            let mut particles = vec![Particle {
+                position: Vec3(0.0, y, 0.0),
+                velocity: Vec3(0.0, 0.0, 10.0),
+                ..Particle::default()
+            }];
+            drag.update_force(&mut particles, 0, 0.1);
+            particles[0].force_accum
+        };
+        let sea_level = force_at(&mut drag, 0.0);
+        assert_approx_eq!(sea_level.2, -0.5 * 1.2 * 100.0 * 0.5);
+        assert_eq!(force_at(&mut drag, -100.0), sea_level);
+        let half = force_at(&mut drag, 8000.0 * 2.0_f64.ln());
+        assert_approx_eq!(half.2, sea_level.2 / 2.0);
+    }
+
+    /// The horizontal distance a shot at 45° travels before falling back to `y = 0`.
+    fn range<G: ParticleForceGenerator<f64> + MaybeSend + 'static>(drag: G) -> f64 {
+        let mut particles = vec![Particle {
+            velocity: Vec3(150.0, 150.0, 0.0),
+            ..Particle::default()
+        }];
+        let mut registry = ParticleForceRegistry::new();
+        registry.add(0, ParticleGravity::new(Vec3(0.0, -9.8, 0.0)));
+        registry.add(0, drag);
+        let duration = 0.001;
+        loop {
+            registry.update_forces(&mut particles, duration);
+            particles[0].integrate(duration);
+            if particles[0].position.1 < 0.0 {
+                return particles[0].position.0;
+            }
+        }
+    }
+
+    #[test]
+    fn atmospheric_range() {
+        let constant = range(ParticleDrag::new(0.0, 0.5 * 1.2 * 0.002));
+        let atmospheric = range(ParticleAtmosphericDrag::new(1.2, 200.0, 0.002));
+        assert!(atmospheric > constant * 1.2);
+        assert!(atmospheric < range(ParticleDrag::new(0.0, 0.0)));
+    }
+
+    fn blow(wind: &mut ParticleWind<f64>, velocity: Vec3<f64>, steps: usize) -> Vec<Vec3<f64>> {
+        let mut particles = vec![Particle {
+            velocity,
+            ..Particle::default()
+        }];
+        let mut velocities = Vec::new();
+        for _ in 0..steps {
+            wind.update_force(&mut particles, 0, 0.01);
+            wind.advance(0.01);
+            particles[0].integrate(0.01);
+            velocities.push(particles[0].velocity);
+        }
+        velocities
+    }
+
+    #[test]
+    fn wind() {
+        let mut wind = ParticleWind::new(Vec3(4.0, 0.0, -2.0), 1.0);
+        let velocities = blow(&mut wind, Vec3(0.0, 0.0, 0.0), 2000);
+        for pair in velocities.windows(2) {
+            assert!(pair[1].0 > pair[0].0);
+            assert!(pair[1].0 < 4.0);
+        }
+        let last = velocities[velocities.len() - 1];
+        assert!(last.approx_eq_relative(Vec3(4.0, 0.0, -2.0), 1e-6));
+        assert_approx_eq!(wind.time, 20.0, 1e-9);
+    }
+
+    #[test]
+    fn still_air() {
+        let mut wind = ParticleWind::new(Vec3(0.0, 0.0, 0.0), 1.0);
+        let velocities = blow(&mut wind, Vec3(0.0, 3.0, 0.0), 1000);
+        for pair in velocities.windows(2) {
+            assert!(pair[1].1 < pair[0].1);
+            assert!(pair[1].1 > 0.0);
+        }
+        assert!(velocities[velocities.len() - 1].mag() < 1e-3);
+    }
+
+    #[test]
+    fn turbulence() {
+        let mut wind = ParticleWind::new(Vec3(1.0, 0.0, 0.0), 2.0)
+            .with_turbulence(|position, time| Vec3(0.0, position.0 * time, 0.0));
+        wind.time = 3.0;
+        assert_eq!(wind.wind_at(Vec3(2.0, 0.0, 0.0)), Vec3(1.0, 6.0, 0.0));
+        let mut particles = vec![Particle {
+            position: Vec3(2.0, 0.0, 0.0),
+            ..Particle::default()
+        }];
+        wind.update_force(&mut particles, 0, 0.5);
+        assert_eq!(particles[0].force_accum, Vec3(2.0, 12.0, 0.0));
+        wind.advance(0.5);
+        assert_eq!(wind.wind_at(Vec3(2.0, 0.0, 0.0)), Vec3(1.0, 7.0, 0.0));
+    }
+
+    #[test]
+    fn shared_wind() {
+        let gust = |_: Vec3<f64>, time: f64| Vec3(if time < 1.0 { 0.0 } else { 5.0 }, 0.0, 0.0);
+        let mut particles = vec![Particle::default(); 2];
+        let mut registry = ParticleForceRegistry::new();
+        let generator = registry
+            .add_generator(ParticleWind::new(Vec3(0.0, 0.0, 0.0), 1.0).with_turbulence(gust));
+        registry.register(0, generator);
+        registry.register(1, generator);
+        let mut forces = Vec::new();
+        for _ in 0..8 {
+            for particle in &mut particles {
+                particle.clear_accumulator();
+            }
+            registry.update_forces(&mut particles, 0.25);
+            assert_eq!(particles[0].force_accum, particles[1].force_accum);
+            forces.push(particles[0].force_accum.0);
+        }
+        // The gust arrives after a second of frames, not half that for blowing on two particles.
+        assert_eq!(forces, vec![0.0, 0.0, 0.0, 0.0, 5.0, 5.0, 5.0, 5.0]);
+        let wind = registry
+            .shared_generator_mut::<ParticleWind<f64>>(generator)
+            .unwrap();
+        assert_eq!(wind.time, 2.0);
+    }
+
+    #[test]
+    fn sine_turbulence() {
+        let mut wind =
+            ParticleWind::new(Vec3(1.0, 0.0, 0.0), 1.0).with_sine_turbulence(0.5, 2.0, 3);
+        let position = Vec3(0.3, -1.2, 4.0);
+        let first = wind.wind_at(position);
+        let mut changed = false;
+        for step in 0..1000 {
+            wind.time = f64::from(step) * 0.01;
+            let turbulence = wind.wind_at(position) - Vec3(1.0, 0.0, 0.0);
+            assert!(turbulence.0.abs() <= 1.0);
+            assert!(turbulence.1.abs() <= 1.0);
+            assert!(turbulence.2.abs() <= 1.0);
+            changed |= wind.wind_at(position) != first;
+        }
+        assert!(changed);
+        assert_ne!(wind.wind_at(position), wind.wind_at(position * 2.0));
+    }
+}
@@ -0,0 +1,291 @@
+use crate::force::ParticleForceGenerator;
+use crate::particle::Particle;
+use crate::vec::Vec3;
+use num::Float;
+use num_traits::NumAssign;
+
+/// The first phase of an explosion: a constant pull toward the center.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Implosion<T: Float> {
+    pub duration: T,
+    /// Particles closer than this aren't pulled, so they don't collapse onto the center.
+    pub min_radius: T,
+    pub max_radius: T,
+    pub force: T,
+}
+
+/// The second phase: a shell expanding from the center, pushing particles outward.
+///
+/// The shell's outer edge is at `speed * t`, `t` seconds into the phase, and it is `thickness`
+/// thick. Its force fades linearly from `peak_force` to zero over the phase.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Shockwave<T: Float> {
+    pub duration: T,
+    pub speed: T,
+    pub thickness: T,
+    pub peak_force: T,
+}
+
+/// The last phase: a chimney of rising air above the center.
+///
+/// The chimney is a cylinder along `up` (a unit vector), starting at the center. Particles in
+/// it are pushed along `up`, hardest on the axis and fading to zero at `radius`. The force also
+/// fades linearly to zero over the phase.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Convection<T: Float> {
+    pub duration: T,
+    pub radius: T,
+    pub height: T,
+    pub peak_force: T,
+    pub up: Vec3<T>,
+}
+
+/// An explosion at `center`, applying an implosion, a shockwave and then convection in turn.
+///
+/// The explosion keeps its own clock. A registry advances it once per frame, however many
+/// particles the explosion acts on; used on its own, call `update` once per frame instead. Once
+/// `is_finished`, no more force is applied and the explosion can be dropped.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParticleExplosion<T: Float> {
+    pub center: Vec3<T>,
+    pub implosion: Implosion<T>,
+    pub shockwave: Shockwave<T>,
+    pub convection: Convection<T>,
+    pub time: T,
+}
+
+impl<T: Float + NumAssign> ParticleExplosion<T> {
+    pub fn new(
+        center: Vec3<T>,
+        implosion: Implosion<T>,
+        shockwave: Shockwave<T>,
+        convection: Convection<T>,
+    ) -> Self {
+        Self {
+            center,
+            implosion,
+            shockwave,
+            convection,
+            time: T::zero(),
+        }
+    }
+
+    pub fn update(&mut self, duration: T) {
+        self.time += duration;
+    }
+
+    pub fn duration(&self) -> T {
+        self.implosion.duration + self.shockwave.duration + self.convection.duration
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.time >= self.duration()
+    }
+
+    /// The force on a particle at `position` at the explosion's current time.
+    pub fn force_at(&self, position: Vec3<T>) -> Vec3<T> {
+        let zero = Vec3(T::zero(), T::zero(), T::zero());
+        let offset = position - self.center;
+        let distance = offset.mag();
+        let mut t = self.time;
+        if t < self.implosion.duration {
+            let Implosion {
+                min_radius,
+                max_radius,
+                force,
+                ..
+            } = self.implosion;
+            if distance.is_zero() || distance < min_radius || distance > max_radius {
+                return zero;
+            }
+            return offset / distance * -force;
+        }
+        t -= self.implosion.duration;
+        if t < self.shockwave.duration {
+            let front = self.shockwave.speed * t;
+            if distance.is_zero() || distance > front || distance < front - self.shockwave.thickness
+            {
+                return zero;
+            }
+            let fade = T::one() - t / self.shockwave.duration;
+            return offset / distance * (self.shockwave.peak_force * fade);
+        }
+        t -= self.shockwave.duration;
+        if t < self.convection.duration {
+            let Convection {
+                duration,
+                radius,
+                height,
+                peak_force,
+                up,
+            } = self.convection;
+            let above = offset.dot(up);
+            let across = (offset - up * above).mag();
+            if above < T::zero() || above > height || across >= radius {
+                return zero;
+            }
+            let fade = (T::one() - t / duration) * (T::one() - across / radius);
+            return up * (peak_force * fade);
+        }
+        zero
+    }
+}
+
+impl<T: Float + NumAssign> ParticleForceGenerator<T> for ParticleExplosion<T> {
+    fn force(&mut self, particles: &[Particle<T>], index: usize, _duration: T) -> Vec3<T> {
+        let particle = &particles[index];
+        self.force_at(particle.position)
+    }
+
+    fn advance(&mut self, duration: T) {
+        self.update(duration);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::force::{Convection, Implosion, ParticleExplosion, Shockwave};
+    use crate::particle::Particle;
+    use crate::vec::Vec3;
+    use crate::world::ParticleWorld;
+    use assert_approx_eq::assert_approx_eq;
+
+    fn explosion() -> ParticleExplosion<f64> {
+        ParticleExplosion::new(
+            Vec3(1.0, 0.0, 0.0),
+            Implosion {
+                duration: 0.5,
+                min_radius: 1.0,
+                max_radius: 5.0,
+                force: 10.0,
+            },
+            Shockwave {
+                duration: 1.0,
+                speed: 10.0,
+                thickness: 2.0,
+                peak_force: 100.0,
+            },
+            Convection {
+                duration: 2.0,
+                radius: 2.0,
+                height: 10.0,
+                peak_force: 20.0,
+                up: Vec3(0.0, 1.0, 0.0),
+            },
+        )
+    }
+
+    #[test]
+    fn implosion() {
+        let mut explosion = explosion();
+        explosion.update(0.25);
+        assert_eq!(
+            explosion.force_at(Vec3(4.0, 0.0, 0.0)),
+            Vec3(-10.0, 0.0, 0.0)
+        );
+        assert_eq!(
+            explosion.force_at(Vec3(1.0, 0.0, -2.0)),
+            Vec3(0.0, 0.0, 10.0)
+        );
+        assert_eq!(explosion.force_at(Vec3(1.5, 0.0, 0.0)), Vec3(0.0, 0.0, 0.0));
+        assert_eq!(explosion.force_at(Vec3(7.0, 0.0, 0.0)), Vec3(0.0, 0.0, 0.0));
+        assert_eq!(explosion.force_at(Vec3(1.0, 0.0, 0.0)), Vec3(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn shockwave() {
+        let mut explosion = explosion();
+        // A quarter of the way through the shockwave, the shell spans 0.5 to 2.5 from the center.
+        explosion.update(0.75);
+        let force = explosion.force_at(Vec3(1.0, 2.0, 0.0));
+        assert_approx_eq!(force.1, 75.0);
+        assert_eq!((force.0, force.2), (0.0, 0.0));
+        assert_eq!(explosion.force_at(Vec3(1.0, 0.0, 3.0)), Vec3(0.0, 0.0, 0.0));
+        assert_eq!(
+            explosion.force_at(Vec3(1.25, 0.0, 0.0)),
+            Vec3(0.0, 0.0, 0.0)
+        );
+        // Later, the shell has passed the first particle and reached the second.
+        explosion.update(0.5);
+        assert_eq!(explosion.force_at(Vec3(1.0, 2.0, 0.0)), Vec3(0.0, 0.0, 0.0));
+        let force = explosion.force_at(Vec3(1.0, 0.0, 6.0));
+        assert_approx_eq!(force.2, 25.0);
+        assert!(!explosion.is_finished());
+    }
+
+    #[test]
+    fn convection() {
+        let mut explosion = explosion();
+        explosion.update(2.5);
+        let force = explosion.force_at(Vec3(2.0, 3.0, 0.0));
+        assert_approx_eq!(force.1, 20.0 * 0.5 * 0.5);
+        assert_eq!((force.0, force.2), (0.0, 0.0));
+        assert_eq!(
+            explosion.force_at(Vec3(1.0, -1.0, 0.0)),
+            Vec3(0.0, 0.0, 0.0)
+        );
+        assert_eq!(explosion.force_at(Vec3(4.0, 1.0, 0.0)), Vec3(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn registered() {
+        let mut world = ParticleWorld::new(0);
+        let beside = world.add_particle(Particle {
+            position: Vec3(4.0, 0.0, 0.0),
+            ..Particle::default()
+        });
+        // Heavy enough to stay in the chimney.
+        let above = world.add_particle(Particle {
+            position: Vec3(1.0, 3.0, 0.0),
+            inverse_mass: 0.01,
+            ..Particle::default()
+        });
+        let explosion = explosion();
+        let id = world.add_force(beside, explosion).unwrap();
+        let generator = world.registry_mut().add_generator(explosion);
+        world.registry_mut().register(above.index(), generator);
+        let mut last_rise = 0.0;
+        for frame in 1..=56 {
+            world.start_frame();
+            world.run_physics(0.0625);
+            // The clock moves once a frame, however many particles the explosion acts on.
+            let time = world
+                .force_generator_mut::<ParticleExplosion<f64>>(id)
+                .unwrap()
+                .time;
+            assert_eq!(time, world.elapsed());
+            let velocity = |handle| world.particle(handle).unwrap().velocity;
+            if frame == 8 {
+                assert!(velocity(beside).0 < 0.0);
+            }
+            if frame == 24 {
+                // The shockwave has thrown the imploding particle back out.
+                assert!(velocity(beside).0 > 0.0);
+            }
+            if frame > 24 {
+                // Convection keeps lifting the particle above the center.
+                assert!(velocity(above).1 > last_rise);
+            }
+            last_rise = velocity(above).1;
+        }
+        let finished = world
+            .force_generator_mut::<ParticleExplosion<f64>>(id)
+            .unwrap();
+        assert!(finished.is_finished());
+    }
+
+    #[test]
+    fn finished() {
+        let mut explosion = explosion();
+        explosion.update(3.5);
+        assert!(explosion.is_finished());
+        for &position in &[
+            Vec3(1.0, 0.0, 0.0),
+            Vec3(1.0, 1.0, 0.0),
+            Vec3(4.0, 0.0, 0.0),
+            Vec3(40.0, 0.0, 0.0),
+        ] {
+            assert_eq!(explosion.force_at(position), Vec3(0.0, 0.0, 0.0));
+        }
+    }
+}
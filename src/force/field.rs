@@ -0,0 +1,234 @@
+use crate::bounds::{Aabb, BoundingSphere};
+use crate::force::ParticleForceGenerator;
+use crate::particle::Particle;
+use crate::vec::Vec3;
+use num::{clamp, Float};
+use num_traits::NumAssign;
+
+/// The volume a `ForceField` acts within, or a `TriggerVolume` watches.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Region<T: Float> {
+    Aabb(Aabb<T>),
+    Sphere(BoundingSphere<T>),
+}
+
+impl<T: Float + NumAssign> Region<T> {
+    /// Distance from `point` to the nearest boundary; positive inside, negative outside.
+    ///
+    /// Outside an `Aabb` this is only an approximation (the distance to the nearest face plane),
+    /// but the sign is always right.
+    pub fn depth(&self, point: Vec3<T>) -> T {
+        match self {
+            Region::Aabb(aabb) => {
+                let low = point - aabb.min;
+                let high = aabb.max - point;
+                low.0
+                    .min(low.1)
+                    .min(low.2)
+                    .min(high.0)
+                    .min(high.1)
+                    .min(high.2)
+            }
+            Region::Sphere(sphere) => sphere.radius - (point - sphere.center).mag(),
+        }
+    }
+
+    /// Points on the boundary are contained.
+    pub fn contains(&self, point: Vec3<T>) -> bool {
+        match self {
+            Region::Aabb(aabb) => aabb.contains(point),
+            Region::Sphere(sphere) => {
+                (point - sphere.center).mag_squared() <= sphere.radius * sphere.radius
+            }
+        }
+    }
+}
+
+/// What a `ForceField` applies: the same force to every particle, or the same acceleration
+/// (a force proportional to mass, which leaves infinite-mass particles alone).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Strength<T: Float> {
+    Force(Vec3<T>),
+    Acceleration(Vec3<T>),
+}
+
+/// A uniform push (like a fan or an updraft) that only acts on particles within a region.
+///
+/// To avoid popping as particles cross the boundary, the strength ramps up linearly over the
+/// `falloff` distance inside the region. With zero `falloff` the field is full strength all the
+/// way to the boundary.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ForceField<T: Float> {
+    pub region: Region<T>,
+    pub strength: Strength<T>,
+    pub falloff: T,
+}
+
+impl<T: Float + NumAssign> ForceField<T> {
+    pub fn new(region: Region<T>, strength: Strength<T>, falloff: T) -> Self {
+        Self {
+            region,
+            strength,
+            falloff,
+        }
+    }
+
+    pub fn set_region(&mut self, region: Region<T>) {
+        self.region = region;
+    }
+
+    pub fn set_force(&mut self, strength: Strength<T>) {
+        self.strength = strength;
+    }
+
+    /// How much of the field's strength applies at `point`, from zero to one.
+    pub fn scale_at(&self, point: Vec3<T>) -> T {
+        let depth = self.region.depth(point);
+        if depth < T::zero() {
+            T::zero()
+        } else if self.falloff <= T::zero() {
+            T::one()
+        } else {
+            clamp(depth / self.falloff, T::zero(), T::one())
+        }
+    }
+}
+
+impl<T: Float + NumAssign> ParticleForceGenerator<T> for ForceField<T> {
+    fn force(&mut self, particles: &[Particle<T>], index: usize, _duration: T) -> Vec3<T> {
+        let zero = Vec3(T::zero(), T::zero(), T::zero());
+        let particle = &particles[index];
+        let scale = self.scale_at(particle.position);
+        if scale.is_zero() {
+            return zero;
+        }
+        let force = match self.strength {
+            Strength::Force(force) => force,
+            Strength::Acceleration(acceleration) => {
+                if !particle.has_finite_mass() {
+                    return zero;
+                }
+                acceleration * particle.mass()
+            }
+        };
+        force * scale
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bounds::{Aabb, BoundingSphere};
+    use crate::force::{
+        ForceField, ParticleForceGenerator, ParticleForceRegistry, Region, Strength,
+    };
+    use crate::particle::Particle;
+    use crate::vec::Vec3;
+
+    fn force_at(field: &mut ForceField<f64>, particle: Particle<f64>) -> Vec3<f64> {
+        let mut particles = vec![particle];
+        field.update_force(&mut particles, 0, 0.1);
+        particles[0].force_accum
+    }
+
+    fn at(position: Vec3<f64>) -> Particle<f64> {
+        Particle {
+            position,
+            inverse_mass: 0.5,
+            ..Particle::default()
+        }
+    }
+
+    #[test]
+    fn aabb() {
+        let region = Region::Aabb(Aabb::new(Vec3(0.0, 0.0, 0.0), Vec3(2.0, 10.0, 2.0)));
+        let mut field = ForceField::new(region, Strength::Force(Vec3(0.0, 5.0, 0.0)), 0.0);
+        assert_eq!(
+            force_at(&mut field, at(Vec3(1.0, 1.0, 1.0))),
+            Vec3(0.0, 5.0, 0.0)
+        );
+        assert_eq!(
+            force_at(&mut field, at(Vec3(1.0, 11.0, 1.0))),
+            Vec3(0.0, 0.0, 0.0)
+        );
+        assert_eq!(
+            force_at(&mut field, at(Vec3(-0.1, 1.0, 1.0))),
+            Vec3(0.0, 0.0, 0.0)
+        );
+        field.set_force(Strength::Acceleration(Vec3(0.0, 5.0, 0.0)));
+        assert_eq!(
+            force_at(&mut field, at(Vec3(1.0, 1.0, 1.0))),
+            Vec3(0.0, 10.0, 0.0)
+        );
+        let mut immovable = at(Vec3(1.0, 1.0, 1.0));
+        immovable.inverse_mass = 0.0;
+        assert_eq!(force_at(&mut field, immovable), Vec3(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn registered() {
+        let mut particles = vec![at(Vec3(0.0, 0.0, 0.0))];
+        let mut registry = ParticleForceRegistry::new();
+        let region = Region::Sphere(BoundingSphere::new(Vec3(5.0, 0.0, 0.0), 1.0));
+        let field = ForceField::new(region, Strength::Force(Vec3(0.0, 1.0, 0.0)), 0.0);
+        let id = registry.add(0, field);
+        let mut force = |registry: &mut ParticleForceRegistry<f64>| {
+            particles[0].clear_accumulator();
+            registry.update_forces(&mut particles, 0.1);
+            particles[0].force_accum
+        };
+        assert_eq!(force(&mut registry), Vec3(0.0, 0.0, 0.0));
+        // Moving the field onto the particle, then changing what it applies, both take effect.
+        let field = registry.generator_mut::<ForceField<f64>>(id).unwrap();
+        field.set_region(Region::Sphere(BoundingSphere::new(
+            Vec3(0.0, 0.0, 0.0),
+            1.0,
+        )));
+        assert_eq!(force(&mut registry), Vec3(0.0, 1.0, 0.0));
+        let field = registry.generator_mut::<ForceField<f64>>(id).unwrap();
+        field.set_force(Strength::Acceleration(Vec3(0.0, 0.0, 3.0)));
+        assert_eq!(force(&mut registry), Vec3(0.0, 0.0, 6.0));
+    }
+
+    #[test]
+    fn sphere() {
+        let region = Region::Sphere(BoundingSphere::new(Vec3(0.0, 0.0, 0.0), 1.0));
+        let mut field = ForceField::new(region, Strength::Force(Vec3(1.0, 0.0, 0.0)), 0.0);
+        assert_eq!(
+            force_at(&mut field, at(Vec3(0.0, 0.9, 0.0))),
+            Vec3(1.0, 0.0, 0.0)
+        );
+        assert_eq!(
+            force_at(&mut field, at(Vec3(0.8, 0.8, 0.0))),
+            Vec3(0.0, 0.0, 0.0)
+        );
+        field.set_region(Region::Sphere(BoundingSphere::new(
+            Vec3(1.0, 1.0, 0.0),
+            1.0,
+        )));
+        assert_eq!(
+            force_at(&mut field, at(Vec3(0.8, 0.8, 0.0))),
+            Vec3(1.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn falloff() {
+        let region = Region::Sphere(BoundingSphere::new(Vec3(0.0, 0.0, 0.0), 2.0));
+        let mut field = ForceField::new(region, Strength::Force(Vec3(0.0, 0.0, 4.0)), 1.0);
+        let mut previous = f64::INFINITY;
+        for step in 0..=30 {
+            let x = f64::from(step) * 0.1;
+            let force = force_at(&mut field, at(Vec3(x, 0.0, 0.0))).2;
+            assert!(force <= previous);
+            if x <= 1.0 {
+                assert_eq!(force, 4.0);
+            } else if x >= 2.0 {
+                assert_eq!(force, 0.0);
+            } else {
+                assert!(force > 0.0 && force < 4.0);
+            }
+            previous = force;
+        }
+        assert_eq!(force_at(&mut field, at(Vec3(1.5, 0.0, 0.0))).2, 2.0);
+    }
+}
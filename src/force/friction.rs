@@ -0,0 +1,134 @@
+use crate::collide::Plane;
+use crate::force::ParticleForceGenerator;
+use crate::particle::Particle;
+use crate::vec::Vec3;
+use num::Float;
+use num_traits::NumAssign;
+
+/// How hard a particle presses against a surface, given the particle.
+#[cfg(not(feature = "rayon"))]
+pub type NormalForceFn<T> = Box<dyn Fn(&Particle<T>) -> T>;
+
+/// How hard a particle presses against a surface, given the particle.
+#[cfg(feature = "rayon")]
+pub type NormalForceFn<T> = Box<dyn Fn(&Particle<T>) -> T + Send>;
+
+/// Where `ParticlePlaneFriction` gets the force pressing a particle against the plane.
+pub enum NormalForce<T: Float> {
+    /// The particle's weight, `gravity * mass * gravity_scale`, for gravity of this magnitude.
+    Weight(T),
+    Custom(NormalForceFn<T>),
+}
+
+/// Kinetic friction from sliding along a plane: `coefficient * normal force`, opposing the
+/// particle's velocity along the plane.
+///
+/// Only particles within `tolerance` of the plane (or behind it) feel friction. The force is
+/// limited to what would stop the particle's sliding within one step, so friction can bring a
+/// particle to rest but never push it back the other way.
+pub struct ParticlePlaneFriction<T: Float> {
+    pub plane: Plane<T>,
+    pub coefficient: T,
+    pub tolerance: T,
+    pub normal_force: NormalForce<T>,
+}
+
+impl<T: Float + NumAssign> ParticlePlaneFriction<T> {
+    pub fn new(
+        plane: Plane<T>,
+        coefficient: T,
+        tolerance: T,
+        normal_force: NormalForce<T>,
+    ) -> Self {
+        Self {
+            plane,
+            coefficient,
+            tolerance,
+            normal_force,
+        }
+    }
+}
+
+impl<T: Float + NumAssign> ParticleForceGenerator<T> for ParticlePlaneFriction<T> {
+    fn force(&mut self, particles: &[Particle<T>], index: usize, duration: T) -> Vec3<T> {
+        let zero = Vec3(T::zero(), T::zero(), T::zero());
+        let particle = &particles[index];
+        if !particle.has_finite_mass() || self.plane.distance(particle.position) > self.tolerance {
+            return zero;
+        }
+        let normal = self.plane.normal;
+        let sliding = particle.velocity - normal * particle.velocity.dot(normal);
+        let speed = sliding.mag();
+        if speed.is_zero() {
+            return zero;
+        }
+        let normal_force = match &self.normal_force {
+            NormalForce::Weight(gravity) => *gravity * particle.mass() * particle.gravity_scale,
+            NormalForce::Custom(f) => f(particle),
+        };
+        let mut friction = self.coefficient * normal_force.max(T::zero());
+        if duration > T::zero() {
+            friction = friction.min(speed * particle.mass() / duration);
+        }
+        sliding / speed * -friction
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::collide::Plane;
+    use crate::force::{NormalForce, ParticleForceGenerator, ParticlePlaneFriction};
+    use crate::particle::Particle;
+    use crate::vec::Vec3;
+    use assert_approx_eq::assert_approx_eq;
+
+    fn ground() -> ParticlePlaneFriction<f64> {
+        let plane = Plane::new(Vec3(0.0, 1.0, 0.0), 0.0);
+        ParticlePlaneFriction::new(plane, 0.5, 0.01, NormalForce::Weight(10.0))
+    }
+
+    #[test]
+    fn slides_to_a_stop() {
+        let mut friction = ground();
+        let mut particles = vec![Particle {
+            velocity: Vec3(3.0, 0.0, 4.0),
+            inverse_mass: 0.5,
+            ..Particle::default()
+        }];
+        let mut speeds = Vec::new();
+        for _ in 0..150 {
+            friction.update_force(&mut particles, 0, 0.01);
+            particles[0].integrate(0.01);
+            speeds.push(particles[0].velocity.mag());
+        }
+        // Slowing by μg = 5 m/s² from 5 m/s, it stops at one second and stays stopped.
+        assert_approx_eq!(speeds[49], 2.5, 1e-9);
+        assert_eq!(speeds[99], 0.0);
+        assert!(speeds[100..].iter().all(|&s| s == 0.0));
+        assert_eq!(particles[0].velocity, Vec3(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn airborne() {
+        let mut particles = vec![Particle {
+            position: Vec3(0.0, 1.0, 0.0),
+            velocity: Vec3(3.0, 0.0, 4.0),
+            ..Particle::default()
+        }];
+        ground().update_force(&mut particles, 0, 0.01);
+        assert_eq!(particles[0].force_accum, Vec3(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn custom_normal_force() {
+        let plane = Plane::new(Vec3(0.0, 1.0, 0.0), 0.0);
+        let normal_force = NormalForce::Custom(Box::new(|_: &Particle<f64>| 4.0));
+        let mut friction = ParticlePlaneFriction::new(plane, 0.5, 0.01, normal_force);
+        let mut particles = vec![Particle {
+            velocity: Vec3(10.0, -1.0, 0.0),
+            ..Particle::default()
+        }];
+        friction.update_force(&mut particles, 0, 0.01);
+        assert_eq!(particles[0].force_accum, Vec3(-2.0, 0.0, 0.0));
+    }
+}
@@ -0,0 +1,381 @@
+use crate::force::ParticleForceGenerator;
+use crate::math;
+use crate::particle::Particle;
+use crate::vec::Vec3;
+use num::Float;
+use num_traits::NumAssign;
+
+/// A uniform gravitational acceleration, applied as the force `gravity * mass * gravity_scale`.
+///
+/// Particles with infinite mass are unaffected.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParticleGravity<T: Float> {
+    pub gravity: Vec3<T>,
+}
+
+impl<T: Float> ParticleGravity<T> {
+    pub fn new(gravity: Vec3<T>) -> Self {
+        Self { gravity }
+    }
+}
+
+impl<T: Float + NumAssign> ParticleForceGenerator<T> for ParticleGravity<T> {
+    fn force(&mut self, particles: &[Particle<T>], index: usize, _duration: T) -> Vec3<T> {
+        let zero = Vec3(T::zero(), T::zero(), T::zero());
+        let particle = &particles[index];
+        if !particle.has_finite_mass() {
+            return zero;
+        }
+        self.gravity * (particle.mass() * particle.gravity_scale)
+    }
+}
+
+/// Newtonian attraction toward another particle: `g * m1 * m2 / r²`.
+///
+/// `softening` is added to the distance (as `r² + softening²`) so that nearby particles don't
+/// feel enormous forces, and coincident particles feel no force at all.
+///
+/// Both particles must have finite mass; otherwise no force is applied.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Gravitation<T: Float> {
+    pub other: usize,
+    pub g: T,
+    pub softening: T,
+}
+
+impl<T: Float> Gravitation<T> {
+    pub fn new(other: usize, g: T, softening: T) -> Self {
+        Self {
+            other,
+            g,
+            softening,
+        }
+    }
+}
+
+impl<T: Float + NumAssign> ParticleForceGenerator<T> for Gravitation<T> {
+    fn force(&mut self, particles: &[Particle<T>], index: usize, _duration: T) -> Vec3<T> {
+        let zero = Vec3(T::zero(), T::zero(), T::zero());
+        let other = particles[self.other];
+        let particle = &particles[index];
+        if !particle.has_finite_mass() || !other.has_finite_mass() {
+            return zero;
+        }
+        let offset = other.position - particle.position;
+        let softened = offset.mag_squared() + self.softening.powi(2);
+        if softened.is_zero() {
+            return zero;
+        }
+        // Plummer softening: the offset is divided by the softened distance cubed,
+        // which reduces to g * m1 * m2 / r² along the unit offset when softening is zero.
+        let scale = self.g * particle.mass() * other.mass() / (softened * math::sqrt(softened));
+        offset * scale
+    }
+}
+
+/// Newtonian attraction toward a fixed point: `gm * m / (r² + softening²)`.
+///
+/// `gm` is the gravitational parameter (the gravitational constant times the attracting mass);
+/// a negative `gm` repels. As with `Gravitation`, `softening` keeps close particles from
+/// feeling enormous forces. Particles with infinite mass are unaffected.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParticleAttractor<T: Float> {
+    pub center: Vec3<T>,
+    pub gm: T,
+    pub softening: T,
+}
+
+impl<T: Float> ParticleAttractor<T> {
+    pub fn new(center: Vec3<T>, gm: T, softening: T) -> Self {
+        Self {
+            center,
+            gm,
+            softening,
+        }
+    }
+}
+
+impl<T: Float + NumAssign> ParticleForceGenerator<T> for ParticleAttractor<T> {
+    fn force(&mut self, particles: &[Particle<T>], index: usize, _duration: T) -> Vec3<T> {
+        let zero = Vec3(T::zero(), T::zero(), T::zero());
+        let particle = &particles[index];
+        if !particle.has_finite_mass() {
+            return zero;
+        }
+        let offset = self.center - particle.position;
+        let distance = offset.mag();
+        if distance.is_zero() {
+            return zero;
+        }
+        let magnitude = self.gm * particle.mass() / (distance.powi(2) + self.softening.powi(2));
+        offset / distance * magnitude
+    }
+}
+
+/// A tunable pull toward `center`, for sucking particles into a zone without the singularity of
+/// inverse-square attraction.
+///
+/// Within `inner_radius` of the center, particles accelerate at the full `strength`. Between the
+/// inner and outer radii that falls to zero as `t^exponent`, where `t` goes from 1 at the inner
+/// radius to 0 at the outer one, and beyond `outer_radius` there's no force at all. An exponent
+/// of 1 fades linearly; larger exponents fall off faster just outside the plateau. A negative
+/// `strength` makes a repulsor.
+///
+/// Like gravity, the force is proportional to mass, and particles with infinite mass (or exactly
+/// at the center) are unaffected.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GravityWell<T: Float> {
+    pub center: Vec3<T>,
+    pub strength: T,
+    pub inner_radius: T,
+    pub outer_radius: T,
+    pub exponent: T,
+}
+
+impl<T: Float> GravityWell<T> {
+    pub fn new(
+        center: Vec3<T>,
+        strength: T,
+        inner_radius: T,
+        outer_radius: T,
+        exponent: T,
+    ) -> Self {
+        Self {
+            center,
+            strength,
+            inner_radius,
+            outer_radius,
+            exponent,
+        }
+    }
+
+    pub fn set_center(&mut self, center: Vec3<T>) {
+        self.center = center;
+    }
+}
+
+impl<T: Float + NumAssign> GravityWell<T> {
+    /// The acceleration toward the center felt at `distance` from it.
+    pub fn strength_at(&self, distance: T) -> T {
+        if distance <= self.inner_radius {
+            self.strength
+        } else if distance >= self.outer_radius {
+            T::zero()
+        } else {
+            let t = (self.outer_radius - distance) / (self.outer_radius - self.inner_radius);
+            self.strength * math::powf(t, self.exponent)
+        }
+    }
+}
+
+impl<T: Float + NumAssign> ParticleForceGenerator<T> for GravityWell<T> {
+    fn force(&mut self, particles: &[Particle<T>], index: usize, _duration: T) -> Vec3<T> {
+        let zero = Vec3(T::zero(), T::zero(), T::zero());
+        let particle = &particles[index];
+        if !particle.has_finite_mass() {
+            return zero;
+        }
+        let offset = self.center - particle.position;
+        let distance = offset.mag();
+        if distance.is_zero() {
+            return zero;
+        }
+        offset / distance * (self.strength_at(distance) * particle.mass())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::force::{
+        ForceFn, Gravitation, GravityWell, ParticleAttractor, ParticleForceGenerator,
+        ParticleForceRegistry, ParticleGravity,
+    };
+    use crate::particle::Particle;
+    use crate::vec::Vec3;
+    use assert_approx_eq::assert_approx_eq;
+
+    fn pair(separation: f64) -> Vec<Particle<f64>> {
+        vec![
+            Particle {
+                inverse_mass: 0.5,
+                ..Particle::default()
+            },
+            Particle {
+                position: Vec3(separation, 0.0, 0.0),
+                inverse_mass: 0.5,
+                ..Particle::default()
+            },
+        ]
+    }
+
+    #[test]
+    fn gravity() {
+        let mut particles = pair(4.0);
+        particles[1].inverse_mass = 0.0;
+        let mut generator = ParticleGravity::new(Vec3(0.0, -9.8, 0.0));
+        generator.update_force(&mut particles, 0, 0.1);
+        generator.update_force(&mut particles, 1, 0.1);
+        assert_eq!(particles[0].force_accum, Vec3(0.0, -19.6, 0.0));
+        assert_eq!(particles[1].force_accum, Vec3(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn gravity_scale() {
+        let mut particles = pair(4.0);
+        particles[0].gravity_scale = 0.5;
+        particles[1].gravity_scale = 0.0;
+        let mut registry = ParticleForceRegistry::new();
+        for index in 0..2 {
+            registry.add(index, ParticleGravity::new(Vec3(0.0, -9.8, 0.0)));
+            registry.add(
+                index,
+                ForceFn::new(|p: &mut Particle<f64>, _| p.add_force(Vec3(1.0, 0.0, 0.0))),
+            );
+        }
+        registry.update_forces(&mut particles, 0.1);
+        assert_eq!(particles[0].force_accum, Vec3(1.0, -9.8, 0.0));
+        assert_eq!(particles[1].force_accum, Vec3(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn equal_and_opposite() {
+        let mut particles = pair(4.0);
+        Gravitation::new(1, 3.0, 0.0).update_force(&mut particles, 0, 0.1);
+        Gravitation::new(0, 3.0, 0.0).update_force(&mut particles, 1, 0.1);
+        // 3 * 2 * 2 / 4²
+        assert_approx_eq!(particles[0].force_accum.0, 0.75);
+        assert_eq!(particles[0].force_accum, particles[1].force_accum.invert());
+        assert_eq!(particles[0].force_accum.1, 0.0);
+        assert_eq!(particles[0].force_accum.2, 0.0);
+    }
+
+    #[test]
+    fn softening() {
+        let mut particles = pair(0.0);
+        Gravitation::new(1, 3.0, 0.1).update_force(&mut particles, 0, 0.1);
+        assert_eq!(particles[0].force_accum, Vec3(0.0, 0.0, 0.0));
+        let mut particles = pair(1e-9);
+        Gravitation::new(1, 3.0, 0.1).update_force(&mut particles, 0, 0.1);
+        assert!(particles[0].force_accum.mag() < 1e-3);
+        let mut particles = pair(0.0);
+        Gravitation::new(1, 3.0, 0.0).update_force(&mut particles, 0, 0.1);
+        assert_eq!(particles[0].force_accum, Vec3(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn infinite_mass() {
+        let mut particles = pair(4.0);
+        particles[1].inverse_mass = 0.0;
+        Gravitation::new(1, 3.0, 0.0).update_force(&mut particles, 0, 0.1);
+        Gravitation::new(0, 3.0, 0.0).update_force(&mut particles, 1, 0.1);
+        assert_eq!(particles[0].force_accum, Vec3(0.0, 0.0, 0.0));
+        assert_eq!(particles[1].force_accum, Vec3(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn attractor() {
+        let mut particles = pair(4.0);
+        ParticleAttractor::new(Vec3(0.0, 0.0, 0.0), 8.0, 0.0).update_force(&mut particles, 1, 0.1);
+        assert_eq!(particles[1].force_accum, Vec3(-1.0, 0.0, 0.0));
+        ParticleAttractor::new(Vec3(0.0, 0.0, 0.0), -8.0, 0.0).update_force(&mut particles, 1, 0.1);
+        assert_eq!(particles[1].force_accum, Vec3(0.0, 0.0, 0.0));
+        ParticleAttractor::new(Vec3(0.0, 0.0, 0.0), 8.0, 0.0).update_force(&mut particles, 0, 0.1);
+        assert_eq!(particles[0].force_accum, Vec3(0.0, 0.0, 0.0));
+        particles[1].inverse_mass = 0.0;
+        ParticleAttractor::new(Vec3(0.0, 0.0, 0.0), 8.0, 0.0).update_force(&mut particles, 1, 0.1);
+        assert_eq!(particles[1].force_accum, Vec3(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn attractor_softening() {
+        let mut particles = pair(1e-12);
+        ParticleAttractor::new(Vec3(0.0, 0.0, 0.0), 8.0, 0.1).update_force(&mut particles, 1, 0.1);
+        let force = particles[1].force_accum;
+        assert!(force.0.is_finite() && force.1.is_finite() && force.2.is_finite());
+        assert!(force.mag() <= 8.0 * 2.0 / 0.01);
+    }
+
+    #[test]
+    fn circular_orbit() {
+        let gm = 5.0_f64;
+        let radius = 2.0;
+        let mut particles = vec![Particle {
+            position: Vec3(radius, 0.0, 0.0),
+            velocity: Vec3(0.0, 0.0, (gm / radius).sqrt()),
+            inverse_mass: 0.5,
+            ..Particle::default()
+        }];
+        let mut attractor = ParticleAttractor::new(Vec3(0.0, 0.0, 0.0), gm, 0.0);
+        let period = 2.0 * std::f64::consts::PI * radius / (gm / radius).sqrt();
+        let duration = 1e-4;
+        let steps = (period / duration) as usize;
+        for _ in 0..steps {
+            attractor.update_force(&mut particles, 0, duration);
+            particles[0].integrate(duration);
+            assert_approx_eq!(particles[0].position.mag(), radius, radius * 0.001);
+        }
+        assert_approx_eq!(particles[0].position.0, radius, radius * 0.01);
+        assert_approx_eq!(particles[0].position.2, 0.0, radius * 0.01);
+    }
+
+    #[test]
+    fn gravity_well() {
+        let center = Vec3(1.0, -2.0, 0.5);
+        let mut well = GravityWell::new(center, 3.0, 1.0, 4.0, 2.0);
+        let direction = Vec3(2.0_f64, 1.0, -2.0).norm();
+        let mut last = f64::INFINITY;
+        for step in 0..=50 {
+            let distance = f64::from(step) * 0.1;
+            let mut particles = vec![Particle {
+                position: center + direction * distance,
+                inverse_mass: 0.5,
+                ..Particle::default()
+            }];
+            let force = well.force(&particles, 0, 0.1);
+            if distance == 0.0 {
+                assert_eq!(force, Vec3(0.0, 0.0, 0.0));
+                continue;
+            }
+            let magnitude = force.mag();
+            if distance <= 1.0 {
+                assert_approx_eq!(magnitude, 6.0);
+            } else if distance >= 4.0 {
+                assert_eq!(magnitude, 0.0);
+            } else {
+                assert!(0.0 < magnitude && magnitude < last);
+            }
+            if magnitude > 0.0 {
+                assert_approx_eq!(force.norm().dot(direction), -1.0);
+            }
+            last = magnitude;
+            well.strength = -3.0;
+            well.update_force(&mut particles, 0, 0.1);
+            assert_eq!(particles[0].force_accum, force.invert());
+            well.strength = 3.0;
+        }
+        assert_approx_eq!(well.strength_at(2.5), 0.75);
+        well.set_center(Vec3(0.0, 0.0, 0.0));
+        assert_eq!(well.center, Vec3(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn gravity_well_moved() {
+        let mut particles = vec![Particle {
+            inverse_mass: 0.5,
+            ..Particle::default()
+        }];
+        let mut registry = ParticleForceRegistry::new();
+        let well = GravityWell::new(Vec3(10.0, 0.0, 0.0), 3.0, 1.0, 4.0, 1.0);
+        let id = registry.add(0, well);
+        registry.update_forces(&mut particles, 0.1);
+        assert_eq!(particles[0].force_accum, Vec3(0.0, 0.0, 0.0));
+        // Moved to just above the particle, the registered well pulls it up.
+        registry
+            .generator_mut::<GravityWell<f64>>(id)
+            .unwrap()
+            .set_center(Vec3(0.0, 0.5, 0.0));
+        particles[0].clear_accumulator();
+        registry.update_forces(&mut particles, 0.1);
+        assert_eq!(particles[0].force_accum, Vec3(0.0, 6.0, 0.0));
+    }
+}
@@ -0,0 +1,82 @@
+use crate::force::ParticleForceGenerator;
+use crate::math;
+use crate::particle::Particle;
+use crate::vec::Vec3;
+use num::Float;
+use num_traits::NumAssign;
+
+/// Lift perpendicular to the particle's velocity, with magnitude `coefficient * speed²`.
+///
+/// The lift points along the part of `up` that is perpendicular to the velocity, so a particle
+/// flying level is pushed toward `up`, and one diving is pushed up and forward. There's no lift
+/// when the particle is at rest or moving parallel to `up`, since no direction is perpendicular.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParticleLift<T: Float> {
+    pub coefficient: T,
+    pub up: Vec3<T>,
+}
+
+impl<T: Float> ParticleLift<T> {
+    pub fn new(coefficient: T, up: Vec3<T>) -> Self {
+        Self { coefficient, up }
+    }
+}
+
+impl<T: Float + NumAssign> ParticleForceGenerator<T> for ParticleLift<T> {
+    fn force(&mut self, particles: &[Particle<T>], index: usize, _duration: T) -> Vec3<T> {
+        let zero = Vec3(T::zero(), T::zero(), T::zero());
+        let particle = &particles[index];
+        let speed_squared = particle.velocity.mag_squared();
+        if speed_squared <= T::epsilon() {
+            return zero;
+        }
+        let direction = particle.velocity / math::sqrt(speed_squared);
+        let perpendicular = self.up - direction * self.up.dot(direction);
+        if perpendicular.mag_squared() <= T::epsilon() * self.up.mag_squared() {
+            return zero;
+        }
+        perpendicular.norm() * (self.coefficient * speed_squared)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::force::{ParticleForceGenerator, ParticleLift};
+    use crate::particle::Particle;
+    use crate::vec::Vec3;
+    use assert_approx_eq::assert_approx_eq;
+
+    fn lift(velocity: Vec3<f64>) -> Vec3<f64> {
+        let mut particles = vec![Particle {
+            velocity,
+            ..Particle::default()
+        }];
+        ParticleLift::new(0.5, Vec3(0.0, 1.0, 0.0)).update_force(&mut particles, 0, 0.1);
+        particles[0].force_accum
+    }
+
+    #[test]
+    fn level() {
+        let force = lift(Vec3(0.0, 0.0, -20.0));
+        assert_approx_eq!(force.0, 0.0);
+        assert_approx_eq!(force.1, 200.0);
+        assert_approx_eq!(force.2, 0.0);
+    }
+
+    #[test]
+    fn diving() {
+        // The velocity direction is (0.6, -0.8, 0), leaving (0.48, 0.36, 0) of `up`.
+        let force = lift(Vec3(3.0, -4.0, 0.0));
+        assert_approx_eq!(force.0, 0.5 * 25.0 * 0.8);
+        assert_approx_eq!(force.1, 0.5 * 25.0 * 0.6);
+        assert_approx_eq!(force.2, 0.0);
+        assert_approx_eq!(force.dot(Vec3(3.0, -4.0, 0.0)), 0.0);
+    }
+
+    #[test]
+    fn no_lift() {
+        assert_eq!(lift(Vec3(0.0, 0.0, 0.0)), Vec3(0.0, 0.0, 0.0));
+        assert_eq!(lift(Vec3(0.0, -5.0, 0.0)), Vec3(0.0, 0.0, 0.0));
+        assert_eq!(lift(Vec3(0.0, 5.0, 0.0)), Vec3(0.0, 0.0, 0.0));
+    }
+}
@@ -0,0 +1,939 @@
+use crate::force::ParticleForceGenerator;
+use crate::math;
+use crate::particle::Particle;
+use crate::vec::Vec3;
+use num::Float;
+use num_traits::NumAssign;
+
+/// A spring to another particle, following Hooke's law.
+///
+/// Only the registered particle is pushed. For a two-way spring, register a mirrored pair:
+/// ```
+/// # use cyclone::force::{ParticleForceRegistry, ParticleSpring};
+/// let mut registry = ParticleForceRegistry::<f64>::new();
+/// registry.add(0, ParticleSpring::new(1, 10.0, 2.0));
+/// registry.add(1, ParticleSpring::new(0, 10.0, 2.0));
+/// ```
+///
+/// Coincident particles feel no force, since there is no direction to push them in.
+///
+/// Any of the springs and bungees here can be given a `break_force` with `with_break_force`.
+/// The first time the force it would apply is stronger than that, it snaps: it applies no force
+/// then or ever after, and `ParticleForceRegistry` reports a `BreakEvent`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParticleSpring<T: Float> {
+    pub other: usize,
+    pub spring_constant: T,
+    pub rest_length: T,
+    pub break_force: Option<T>,
+    broken: bool,
+}
+
+impl<T: Float> ParticleSpring<T> {
+    pub fn new(other: usize, spring_constant: T, rest_length: T) -> Self {
+        Self {
+            other,
+            spring_constant,
+            rest_length,
+            break_force: None,
+            broken: false,
+        }
+    }
+
+    /// Snaps once the force would be stronger than `break_force`; see `ParticleSpring`.
+    pub fn with_break_force(mut self, break_force: T) -> Self {
+        self.break_force = Some(break_force);
+        self
+    }
+}
+
+impl<T: Float + NumAssign> ParticleForceGenerator<T> for ParticleSpring<T> {
+    fn force(&mut self, particles: &[Particle<T>], index: usize, _duration: T) -> Vec3<T> {
+        let zero = Vec3(T::zero(), T::zero(), T::zero());
+        if self.broken {
+            return zero;
+        }
+        let other = particles[self.other].position;
+        let particle = &particles[index];
+        let offset = particle.position - other;
+        let length = offset.mag();
+        if length.is_zero() {
+            return zero;
+        }
+        let magnitude = -self.spring_constant * (length - self.rest_length);
+        let force = offset / length * magnitude;
+        snap(force, self.break_force, &mut self.broken)
+    }
+
+    fn is_broken(&self) -> bool {
+        self.broken
+    }
+}
+
+/// A spring to a fixed point in space, following Hooke's law.
+///
+/// A particle sitting exactly on the anchor feels no force.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParticleAnchoredSpring<T: Float> {
+    pub anchor: Vec3<T>,
+    pub spring_constant: T,
+    pub rest_length: T,
+    pub break_force: Option<T>,
+    broken: bool,
+}
+
+impl<T: Float> ParticleAnchoredSpring<T> {
+    pub fn new(anchor: Vec3<T>, spring_constant: T, rest_length: T) -> Self {
+        Self {
+            anchor,
+            spring_constant,
+            rest_length,
+            break_force: None,
+            broken: false,
+        }
+    }
+
+    /// Snaps once the force would be stronger than `break_force`; see `ParticleSpring`.
+    pub fn with_break_force(mut self, break_force: T) -> Self {
+        self.break_force = Some(break_force);
+        self
+    }
+
+    pub fn set_anchor(&mut self, anchor: Vec3<T>) {
+        self.anchor = anchor;
+    }
+}
+
+impl<T: Float + NumAssign> ParticleForceGenerator<T> for ParticleAnchoredSpring<T> {
+    fn force(&mut self, particles: &[Particle<T>], index: usize, _duration: T) -> Vec3<T> {
+        let zero = Vec3(T::zero(), T::zero(), T::zero());
+        if self.broken {
+            return zero;
+        }
+        let particle = &particles[index];
+        let offset = particle.position - self.anchor;
+        let length = offset.mag();
+        if length.is_zero() {
+            return zero;
+        }
+        let magnitude = -self.spring_constant * (length - self.rest_length);
+        let force = offset / length * magnitude;
+        snap(force, self.break_force, &mut self.broken)
+    }
+
+    fn is_broken(&self) -> bool {
+        self.broken
+    }
+}
+
+/// A spring to another particle that also resists stretching and compressing quickly.
+///
+/// On top of the Hooke's law force, the particle is pushed with `-damping * v` along the
+/// spring, where `v` is its velocity relative to the other particle along the spring's axis.
+/// Motion across the spring isn't damped. This lets a spring settle, even critically damped
+/// (at `damping = 2 * sqrt(spring_constant * mass)`), without `Particle::damping` also slowing
+/// unrelated motion.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParticleDampedSpring<T: Float> {
+    pub other: usize,
+    pub spring_constant: T,
+    pub rest_length: T,
+    pub damping: T,
+    pub break_force: Option<T>,
+    broken: bool,
+}
+
+impl<T: Float> ParticleDampedSpring<T> {
+    pub fn new(other: usize, spring_constant: T, rest_length: T, damping: T) -> Self {
+        Self {
+            other,
+            spring_constant,
+            rest_length,
+            damping,
+            break_force: None,
+            broken: false,
+        }
+    }
+
+    /// Snaps once the force would be stronger than `break_force`; see `ParticleSpring`.
+    pub fn with_break_force(mut self, break_force: T) -> Self {
+        self.break_force = Some(break_force);
+        self
+    }
+}
+
+impl<T: Float + NumAssign> ParticleForceGenerator<T> for ParticleDampedSpring<T> {
+    fn force(&mut self, particles: &[Particle<T>], index: usize, _duration: T) -> Vec3<T> {
+        let zero = Vec3(T::zero(), T::zero(), T::zero());
+        if self.broken {
+            return zero;
+        }
+        let other = &particles[self.other];
+        let (other_position, other_velocity) = (other.position, other.velocity);
+        let particle = &particles[index];
+        let offset = particle.position - other_position;
+        let length = offset.mag();
+        if length.is_zero() {
+            return zero;
+        }
+        let axis = offset / length;
+        let closing = (particle.velocity - other_velocity).dot(axis);
+        let magnitude =
+            -self.spring_constant * (length - self.rest_length) - self.damping * closing;
+        let force = axis * magnitude;
+        snap(force, self.break_force, &mut self.broken)
+    }
+
+    fn is_broken(&self) -> bool {
+        self.broken
+    }
+}
+
+/// A spring and dashpot between two particles; another name for `ParticleDampedSpring`.
+pub type DampedSpring<T> = ParticleDampedSpring<T>;
+
+/// A spring to a fixed point in space, damped along its axis like `ParticleDampedSpring`.
+///
+/// A particle sitting exactly on the anchor feels no force.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParticleAnchoredDampedSpring<T: Float> {
+    pub anchor: Vec3<T>,
+    pub spring_constant: T,
+    pub rest_length: T,
+    pub damping: T,
+    pub break_force: Option<T>,
+    broken: bool,
+}
+
+impl<T: Float> ParticleAnchoredDampedSpring<T> {
+    pub fn new(anchor: Vec3<T>, spring_constant: T, rest_length: T, damping: T) -> Self {
+        Self {
+            anchor,
+            spring_constant,
+            rest_length,
+            damping,
+            break_force: None,
+            broken: false,
+        }
+    }
+
+    /// Snaps once the force would be stronger than `break_force`; see `ParticleSpring`.
+    pub fn with_break_force(mut self, break_force: T) -> Self {
+        self.break_force = Some(break_force);
+        self
+    }
+
+    pub fn set_anchor(&mut self, anchor: Vec3<T>) {
+        self.anchor = anchor;
+    }
+}
+
+impl<T: Float + NumAssign> ParticleForceGenerator<T> for ParticleAnchoredDampedSpring<T> {
+    fn force(&mut self, particles: &[Particle<T>], index: usize, _duration: T) -> Vec3<T> {
+        let zero = Vec3(T::zero(), T::zero(), T::zero());
+        if self.broken {
+            return zero;
+        }
+        let particle = &particles[index];
+        let offset = particle.position - self.anchor;
+        let length = offset.mag();
+        if length.is_zero() {
+            return zero;
+        }
+        let axis = offset / length;
+        let magnitude = -self.spring_constant * (length - self.rest_length)
+            - self.damping * particle.velocity.dot(axis);
+        let force = axis * magnitude;
+        snap(force, self.break_force, &mut self.broken)
+    }
+
+    fn is_broken(&self) -> bool {
+        self.broken
+    }
+}
+
+/// A spring to another particle that only pulls, and only when stretched past its rest length.
+///
+/// The force falls continuously to zero as the bungee reaches its rest length. Register a
+/// mirrored pair for a two-way bungee, as with `ParticleSpring`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParticleBungee<T: Float> {
+    pub other: usize,
+    pub spring_constant: T,
+    pub rest_length: T,
+    pub break_force: Option<T>,
+    broken: bool,
+}
+
+impl<T: Float> ParticleBungee<T> {
+    pub fn new(other: usize, spring_constant: T, rest_length: T) -> Self {
+        Self {
+            other,
+            spring_constant,
+            rest_length,
+            break_force: None,
+            broken: false,
+        }
+    }
+
+    /// Snaps once the force would be stronger than `break_force`; see `ParticleSpring`.
+    pub fn with_break_force(mut self, break_force: T) -> Self {
+        self.break_force = Some(break_force);
+        self
+    }
+}
+
+impl<T: Float + NumAssign> ParticleForceGenerator<T> for ParticleBungee<T> {
+    fn force(&mut self, particles: &[Particle<T>], index: usize, _duration: T) -> Vec3<T> {
+        let zero = Vec3(T::zero(), T::zero(), T::zero());
+        if self.broken {
+            return zero;
+        }
+        let other = particles[self.other].position;
+        let particle = &particles[index];
+        let offset = particle.position - other;
+        let length = offset.mag();
+        if length <= self.rest_length {
+            return zero;
+        }
+        let magnitude = -self.spring_constant * (length - self.rest_length);
+        let force = offset / length * magnitude;
+        snap(force, self.break_force, &mut self.broken)
+    }
+
+    fn is_broken(&self) -> bool {
+        self.broken
+    }
+}
+
+/// A bungee to a fixed point in space, pulling only when stretched past its rest length.
+///
+/// This works as a leash: the particle moves freely within `rest_length` of the anchor.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParticleAnchoredBungee<T: Float> {
+    pub anchor: Vec3<T>,
+    pub spring_constant: T,
+    pub rest_length: T,
+    pub break_force: Option<T>,
+    broken: bool,
+}
+
+impl<T: Float> ParticleAnchoredBungee<T> {
+    pub fn new(anchor: Vec3<T>, spring_constant: T, rest_length: T) -> Self {
+        Self {
+            anchor,
+            spring_constant,
+            rest_length,
+            break_force: None,
+            broken: false,
+        }
+    }
+
+    /// Snaps once the force would be stronger than `break_force`; see `ParticleSpring`.
+    pub fn with_break_force(mut self, break_force: T) -> Self {
+        self.break_force = Some(break_force);
+        self
+    }
+
+    pub fn set_anchor(&mut self, anchor: Vec3<T>) {
+        self.anchor = anchor;
+    }
+}
+
+impl<T: Float + NumAssign> ParticleForceGenerator<T> for ParticleAnchoredBungee<T> {
+    fn force(&mut self, particles: &[Particle<T>], index: usize, _duration: T) -> Vec3<T> {
+        let zero = Vec3(T::zero(), T::zero(), T::zero());
+        if self.broken {
+            return zero;
+        }
+        let particle = &particles[index];
+        let offset = particle.position - self.anchor;
+        let length = offset.mag();
+        if length <= self.rest_length {
+            return zero;
+        }
+        let magnitude = -self.spring_constant * (length - self.rest_length);
+        let force = offset / length * magnitude;
+        snap(force, self.break_force, &mut self.broken)
+    }
+
+    fn is_broken(&self) -> bool {
+        self.broken
+    }
+}
+
+/// A stiff spring to a fixed point in space that stays stable at large time steps.
+///
+/// Rather than applying Hooke's law directly (which explodes under explicit integration when
+/// the spring is stiff), this predicts where a damped harmonic oscillator would be at the end
+/// of the frame and applies whatever force gets the particle there. The spring constant and
+/// damping are per unit of mass.
+///
+/// Critically damped (`damping² = 4 * spring_constant`) and overdamped springs creep back to
+/// the anchor without oscillating. Particles with infinite mass feel no force.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParticleFakeSpring<T: Float> {
+    pub anchor: Vec3<T>,
+    pub spring_constant: T,
+    pub damping: T,
+}
+
+impl<T: Float> ParticleFakeSpring<T> {
+    pub fn new(anchor: Vec3<T>, spring_constant: T, damping: T) -> Self {
+        Self {
+            anchor,
+            spring_constant,
+            damping,
+        }
+    }
+
+    pub fn set_anchor(&mut self, anchor: Vec3<T>) {
+        self.anchor = anchor;
+    }
+}
+
+impl<T: Float + NumAssign> ParticleForceGenerator<T> for ParticleFakeSpring<T> {
+    fn force(&mut self, particles: &[Particle<T>], index: usize, duration: T) -> Vec3<T> {
+        let zero = Vec3(T::zero(), T::zero(), T::zero());
+        let particle = &particles[index];
+        if !particle.has_finite_mass() || duration.is_zero() {
+            return zero;
+        }
+        let two = T::one() + T::one();
+        // `integrate` moves the particle by its current velocity before any force can act,
+        // so we predict from where it will be after that move.
+        let position = particle.position + particle.velocity * duration - self.anchor;
+        let velocity = particle.velocity;
+        let half_damping = self.damping / two;
+        let discriminant = two * two * self.spring_constant - self.damping.powi(2);
+        let target = if discriminant > T::zero() {
+            let gamma = math::sqrt(discriminant) / two;
+            let c = position * (half_damping / gamma) + velocity / gamma;
+            let (sin, cos) = math::sin_cos(gamma * duration);
+            (position * cos + c * sin) * math::exp(-half_damping * duration)
+        } else if discriminant.is_zero() {
+            (position + (velocity + position * half_damping) * duration)
+                * math::exp(-half_damping * duration)
+        } else {
+            // Two decaying exponentials, at rates `fast` and `slow`.
+            let spread = math::sqrt(-discriminant) / two;
+            let (slow, fast) = (half_damping - spread, half_damping + spread);
+            let slow_part = (velocity + position * fast) / (spread + spread);
+            let fast_part = (position * -slow - velocity) / (spread + spread);
+            slow_part * math::exp(-slow * duration) + fast_part * math::exp(-fast * duration)
+        };
+        let acceleration = (target - position) / duration.powi(2) - particle.velocity / duration;
+        acceleration * particle.mass()
+    }
+}
+
+/// Breaks the spring instead of applying `force` if that's stronger than `break_force`.
+fn snap<T: Float + NumAssign>(
+    force: Vec3<T>,
+    break_force: Option<T>,
+    broken: &mut bool,
+) -> Vec3<T> {
+    match break_force {
+        Some(break_force) if force.mag() > break_force => {
+            *broken = true;
+            Vec3(T::zero(), T::zero(), T::zero())
+        }
+        _ => force,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::force::{
+        DampedSpring, ParticleAnchoredBungee, ParticleAnchoredDampedSpring, ParticleAnchoredSpring,
+        ParticleBungee, ParticleDampedSpring, ParticleFakeSpring, ParticleForceGenerator,
+        ParticleForceRegistry, ParticleSpring,
+    };
+    use crate::particle::Particle;
+    use crate::vec::Vec3;
+    use assert_approx_eq::assert_approx_eq;
+
+    fn pair(separation: f64) -> Vec<Particle<f64>> {
+        vec![
+            Particle::default(),
+            Particle {
+                position: Vec3(separation, 0.0, 0.0),
+                ..Particle::default()
+            },
+        ]
+    }
+
+    fn forces<G: ParticleForceGenerator<f64>>(
+        particles: &mut [Particle<f64>],
+        mut a: G,
+        mut b: G,
+    ) -> (Vec3<f64>, Vec3<f64>) {
+        a.update_force(particles, 0, 0.1);
+        b.update_force(particles, 1, 0.1);
+        (particles[0].force_accum, particles[1].force_accum)
+    }
+
+    #[test]
+    fn rest_length() {
+        let mut particles = pair(2.0);
+        let (a, b) = forces(
+            &mut particles,
+            ParticleSpring::new(1, 10.0, 2.0),
+            ParticleSpring::new(0, 10.0, 2.0),
+        );
+        assert_eq!(a, Vec3(0.0, 0.0, 0.0));
+        assert_eq!(b, Vec3(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn stretched() {
+        let mut particles = pair(3.0);
+        let (a, b) = forces(
+            &mut particles,
+            ParticleSpring::new(1, 10.0, 2.0),
+            ParticleSpring::new(0, 10.0, 2.0),
+        );
+        assert_eq!(a, Vec3(10.0, 0.0, 0.0));
+        assert_eq!(b, Vec3(-10.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn compressed() {
+        let mut particles = pair(1.5);
+        let (a, b) = forces(
+            &mut particles,
+            ParticleSpring::new(1, 10.0, 2.0),
+            ParticleSpring::new(0, 10.0, 2.0),
+        );
+        assert_eq!(a, Vec3(-5.0, 0.0, 0.0));
+        assert_eq!(b, Vec3(5.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn coincident() {
+        let mut particles = pair(0.0);
+        let (a, b) = forces(
+            &mut particles,
+            ParticleSpring::new(1, 10.0, 2.0),
+            ParticleSpring::new(0, 10.0, 2.0),
+        );
+        assert_eq!(a, Vec3(0.0, 0.0, 0.0));
+        assert_eq!(b, Vec3(0.0, 0.0, 0.0));
+    }
+
+    /// Simulates one particle under `generator`, returning its x position after each step.
+    fn simulate<G: ParticleForceGenerator<f64>>(
+        particle: Particle<f64>,
+        generator: &mut G,
+        steps: usize,
+    ) -> Vec<f64> {
+        let mut particles = vec![particle];
+        let mut xs = Vec::with_capacity(steps);
+        for _ in 0..steps {
+            generator.update_force(&mut particles, 0, 0.001);
+            particles[0].integrate(0.001);
+            xs.push(particles[0].position.0);
+        }
+        xs
+    }
+
+    #[test]
+    fn anchored_oscillates() {
+        let mut spring = ParticleAnchoredSpring::new(Vec3(1.0, 0.0, 0.0), 10.0, 2.0);
+        let particle = Particle {
+            position: Vec3(4.0, 0.0, 0.0),
+            ..Particle::default()
+        };
+        // About two periods, 2π/√10 each.
+        let xs = simulate(particle, &mut spring, 4000);
+        let min = xs.iter().cloned().fold(f64::INFINITY, f64::min);
+        let max = xs.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        assert_approx_eq!(min, 2.0, 0.05);
+        assert_approx_eq!(max, 4.0, 0.05);
+        let crossings = xs.windows(2).filter(|w| (w[0] - 3.0) * (w[1] - 3.0) < 0.0);
+        assert_eq!(crossings.count(), 4);
+    }
+
+    #[test]
+    fn anchored_settles() {
+        let mut spring = ParticleAnchoredSpring::new(Vec3(1.0, 0.0, 0.0), 10.0, 2.0);
+        let particle = Particle {
+            position: Vec3(4.0, 0.0, 0.0),
+            damping: 0.2,
+            ..Particle::default()
+        };
+        let xs = simulate(particle, &mut spring, 30_000);
+        assert_approx_eq!(xs[xs.len() - 1], 3.0, 1e-4);
+    }
+
+    #[test]
+    fn anchored_set_anchor() {
+        let mut spring = ParticleAnchoredSpring::new(Vec3(0.0, 0.0, 0.0), 10.0, 2.0);
+        spring.set_anchor(Vec3(0.0, 5.0, 0.0));
+        let mut particles = vec![Particle::default()];
+        spring.update_force(&mut particles, 0, 0.1);
+        assert_eq!(particles[0].force_accum, Vec3(0.0, 30.0, 0.0));
+    }
+
+    #[test]
+    fn anchored_set_anchor_registered() {
+        let mut particles = vec![Particle::default()];
+        let mut registry = ParticleForceRegistry::new();
+        let spring = ParticleAnchoredSpring::new(Vec3(0.0, 0.0, 0.0), 10.0, 2.0);
+        let id = registry.add_with_priority(0, spring, 3);
+        registry
+            .generator_mut::<ParticleAnchoredSpring<f64>>(id)
+            .unwrap()
+            .set_anchor(Vec3(0.0, 5.0, 0.0));
+        registry.update_forces(&mut particles, 0.1);
+        assert_eq!(particles[0].force_accum, Vec3(0.0, 30.0, 0.0));
+    }
+
+    #[test]
+    fn damped_critical() {
+        let damping = 2.0 * 10.0_f64.sqrt();
+        let mut spring = ParticleAnchoredDampedSpring::new(Vec3(1.0, 0.0, 0.0), 10.0, 2.0, damping);
+        let particle = Particle {
+            position: Vec3(4.0, 0.0, 0.0),
+            ..Particle::default()
+        };
+        let xs = simulate(particle, &mut spring, 10_000);
+        assert!(xs.iter().all(|&x| x > 3.0));
+        assert!(xs.windows(2).all(|w| w[1] <= w[0]));
+        assert_approx_eq!(xs[xs.len() - 1], 3.0, 1e-4);
+    }
+
+    #[test]
+    fn damped_undamped() {
+        let mut particles = pair(3.0);
+        particles[0].velocity = Vec3(1.0, 2.0, 0.0);
+        particles[1].velocity = Vec3(-2.0, 0.5, 1.0);
+        let damped = forces(
+            &mut particles,
+            ParticleDampedSpring::new(1, 10.0, 2.0, 0.0),
+            ParticleDampedSpring::new(0, 10.0, 2.0, 0.0),
+        );
+        for particle in &mut particles {
+            particle.clear_accumulator();
+        }
+        let plain = forces(
+            &mut particles,
+            ParticleSpring::new(1, 10.0, 2.0),
+            ParticleSpring::new(0, 10.0, 2.0),
+        );
+        assert_eq!(damped, plain);
+        let mut particles = vec![Particle {
+            position: Vec3(0.0, 4.0, 0.0),
+            velocity: Vec3(0.5, 3.0, 0.0),
+            ..Particle::default()
+        }];
+        ParticleAnchoredDampedSpring::new(Vec3(0.0, 0.0, 0.0), 10.0, 2.0, 0.0).update_force(
+            &mut particles,
+            0,
+            0.1,
+        );
+        let damped = particles[0].force_accum;
+        particles[0].clear_accumulator();
+        ParticleAnchoredSpring::new(Vec3(0.0, 0.0, 0.0), 10.0, 2.0).update_force(
+            &mut particles,
+            0,
+            0.1,
+        );
+        assert_eq!(damped, particles[0].force_accum);
+    }
+
+    #[test]
+    fn damped_decays() {
+        // Stretched by 1 and let go, with no `Particle::damping` to help.
+        let mut particles = pair(3.0);
+        let mut springs = [
+            DampedSpring::new(1, 10.0, 2.0, 0.5),
+            DampedSpring::new(0, 10.0, 2.0, 0.5),
+        ];
+        let mut stretches = Vec::new();
+        for _ in 0..10_000 {
+            for (index, spring) in springs.iter_mut().enumerate() {
+                spring.update_force(&mut particles, index, 0.001);
+            }
+            for particle in &mut particles {
+                particle.integrate(0.001);
+            }
+            stretches.push((particles[1].position - particles[0].position).mag() - 2.0);
+        }
+        // Each swing out, stretched or compressed, is shorter than the last.
+        let peaks: Vec<f64> = stretches
+            .windows(3)
+            .filter(|w| w[1].abs() > w[0].abs() && w[1].abs() >= w[2].abs())
+            .map(|w| w[1].abs())
+            .collect();
+        assert!(peaks.len() > 10);
+        assert!(peaks.windows(2).all(|w| w[1] < w[0]));
+        assert!(peaks[peaks.len() - 1] < 0.1);
+    }
+
+    #[test]
+    fn damped_along_axis() {
+        // Only the 3 units/s of closing speed along the spring are damped, not the sideways 4.
+        let mut particles = pair(2.0);
+        particles[1].velocity = Vec3(-3.0, 4.0, 0.0);
+        let (a, b) = forces(
+            &mut particles,
+            ParticleDampedSpring::new(1, 10.0, 2.0, 0.5),
+            ParticleDampedSpring::new(0, 10.0, 2.0, 0.5),
+        );
+        assert_eq!(a, Vec3(-1.5, 0.0, 0.0));
+        assert_eq!(b, Vec3(1.5, 0.0, 0.0));
+    }
+
+    #[test]
+    fn anchored_at_anchor() {
+        let mut spring = ParticleAnchoredSpring::new(Vec3(0.0, 0.0, 0.0), 10.0, 2.0);
+        let mut particles = vec![Particle::default()];
+        spring.update_force(&mut particles, 0, 0.1);
+        assert_eq!(particles[0].force_accum, Vec3(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn bungee_compressed() {
+        let mut particles = pair(1.5);
+        let (a, b) = forces(
+            &mut particles,
+            ParticleBungee::new(1, 10.0, 2.0),
+            ParticleBungee::new(0, 10.0, 2.0),
+        );
+        assert_eq!(a, Vec3(0.0, 0.0, 0.0));
+        assert_eq!(b, Vec3(0.0, 0.0, 0.0));
+        let mut particles = pair(0.0);
+        let (a, _) = forces(
+            &mut particles,
+            ParticleBungee::new(1, 10.0, 2.0),
+            ParticleBungee::new(0, 10.0, 2.0),
+        );
+        assert_eq!(a, Vec3(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn bungee_stretched() {
+        let mut particles = pair(3.0);
+        let (a, b) = forces(
+            &mut particles,
+            ParticleBungee::new(1, 10.0, 2.0),
+            ParticleBungee::new(0, 10.0, 2.0),
+        );
+        assert_eq!(a, Vec3(10.0, 0.0, 0.0));
+        assert_eq!(b, Vec3(-10.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn bungee_snaps() {
+        let mut bungee = ParticleBungee::new(1, 10.0, 2.0).with_break_force(10.0);
+        let mut particles = pair(3.0);
+        assert_eq!(bungee.force(&particles, 0, 0.1), Vec3(10.0, 0.0, 0.0));
+        assert!(!bungee.is_broken());
+        particles[1].position.0 = 3.5;
+        assert_eq!(bungee.force(&particles, 0, 0.1), Vec3(0.0, 0.0, 0.0));
+        assert!(bungee.is_broken());
+        particles[1].position.0 = 2.5;
+        assert_eq!(bungee.force(&particles, 0, 0.1), Vec3(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn bungee_continuous() {
+        for &separation in &[2.0 - 1e-9, 2.0, 2.0 + 1e-9] {
+            let mut particles = pair(separation);
+            let (a, _) = forces(
+                &mut particles,
+                ParticleBungee::new(1, 10.0, 2.0),
+                ParticleBungee::new(0, 10.0, 2.0),
+            );
+            assert!(a.mag() < 1e-7);
+        }
+    }
+
+    #[test]
+    fn bungee_oscillates() {
+        let mut particles = pair(3.0);
+        particles[0].inverse_mass = 0.0;
+        let mut bungee = ParticleBungee::new(0, 10.0, 2.0);
+        let mut max_length: f64 = 0.0;
+        let mut crossings = 0;
+        let mut was_slack = false;
+        for _ in 0..20_000 {
+            bungee.update_force(&mut particles, 1, 0.001);
+            particles[1].integrate(0.001);
+            let length = particles[1].position.mag();
+            max_length = max_length.max(length);
+            let slack = length <= 2.0;
+            if slack != was_slack {
+                crossings += 1;
+            }
+            was_slack = slack;
+        }
+        assert!(crossings > 4);
+        // Explicit integration gains a little energy, but nothing jumps at the threshold.
+        assert!(max_length < 3.1);
+        assert!(particles[1].position.mag().is_finite());
+    }
+
+    #[test]
+    fn anchored_bungee_slack() {
+        let mut bungee = ParticleAnchoredBungee::new(Vec3(1.0, 1.0, 1.0), 10.0, 2.0);
+        for &position in &[
+            Vec3(1.0, 1.0, 1.0),
+            Vec3(2.0, 2.0, 2.0),
+            Vec3(1.0, -1.0, 1.0),
+        ] {
+            let mut particles = vec![Particle {
+                position,
+                ..Particle::default()
+            }];
+            bungee.update_force(&mut particles, 0, 0.1);
+            assert_eq!(particles[0].force_accum, Vec3(0.0, 0.0, 0.0));
+        }
+    }
+
+    #[test]
+    fn anchored_bungee_stretched() {
+        let mut bungee = ParticleAnchoredBungee::new(Vec3(0.0, 0.0, 0.0), 10.0, 2.0);
+        for &excess in &[0.5_f64, 1.0, 4.0] {
+            let mut particles = vec![Particle {
+                position: Vec3(0.0, 0.0, -(2.0 + excess)),
+                ..Particle::default()
+            }];
+            bungee.update_force(&mut particles, 0, 0.1);
+            assert_approx_eq!(particles[0].force_accum.2, 10.0 * excess);
+            assert_eq!(particles[0].force_accum.0, 0.0);
+            assert_eq!(particles[0].force_accum.1, 0.0);
+        }
+    }
+
+    #[test]
+    fn anchored_bungee_teleport() {
+        let mut bungee = ParticleAnchoredBungee::new(Vec3(0.0, 0.0, 0.0), 10.0, 2.0);
+        let mut particles = vec![Particle {
+            position: Vec3(1.0_f64, 0.0, 0.0),
+            ..Particle::default()
+        }];
+        bungee.update_force(&mut particles, 0, 0.1);
+        assert_eq!(particles[0].force_accum, Vec3(0.0, 0.0, 0.0));
+        bungee.set_anchor(Vec3(1.0, 1000.0, 0.0));
+        bungee.update_force(&mut particles, 0, 0.1);
+        assert_eq!(particles[0].force_accum, Vec3(0.0, 9980.0, 0.0));
+        particles[0].integrate(0.1);
+        bungee.update_force(&mut particles, 0, 0.1);
+        assert!(particles[0].force_accum.1 > 0.0);
+        assert!(particles[0].force_accum.1.is_finite());
+    }
+
+    #[test]
+    fn anchored_bungee_set_anchor_shared() {
+        let mut particles = vec![
+            Particle {
+                position: Vec3(1.0, 0.0, 0.0),
+                ..Particle::default()
+            },
+            Particle {
+                position: Vec3(-1.0, 0.0, 0.0),
+                ..Particle::default()
+            },
+        ];
+        let mut registry = ParticleForceRegistry::new();
+        let bungee = ParticleAnchoredBungee::new(Vec3(0.0, 0.0, 0.0), 10.0, 2.0);
+        let generator = registry.add_generator(bungee);
+        let first = registry.register(0, generator).unwrap();
+        registry.register(1, generator).unwrap();
+        registry.update_forces(&mut particles, 0.1);
+        assert_eq!(particles[0].force_accum, Vec3(0.0, 0.0, 0.0));
+        // Moving the anchor through one registration moves it for both.
+        registry
+            .generator_mut::<ParticleAnchoredBungee<f64>>(first)
+            .unwrap()
+            .set_anchor(Vec3(0.0, 0.0, 5.0));
+        registry.update_forces(&mut particles, 0.1);
+        for particle in &particles {
+            assert!(particle.force_accum.2 > 0.0);
+        }
+        assert_eq!(particles[0].force_accum.0, -particles[1].force_accum.0);
+    }
+
+    #[test]
+    fn fake_spring_stiff() {
+        let start = Particle {
+            position: Vec3(1.0, 2.0, 0.0),
+            inverse_mass: 0.5,
+            ..Particle::default()
+        };
+        let duration = 1.0 / 60.0;
+        let mut real = ParticleAnchoredSpring::new(Vec3(0.0, 0.0, 0.0), 20_000.0, 0.0);
+        let mut fake = ParticleFakeSpring::new(Vec3(0.0, 0.0, 0.0), 10_000.0, 4.0);
+        let mut real_particles = vec![start];
+        let mut fake_particles = vec![start];
+        let mut largest: f64 = 0.0;
+        for _ in 0..600 {
+            real.update_force(&mut real_particles, 0, duration);
+            real_particles[0].integrate(duration);
+            fake.update_force(&mut fake_particles, 0, duration);
+            fake_particles[0].integrate(duration);
+            largest = largest.max(fake_particles[0].position.mag());
+        }
+        let diverged = real_particles[0].position.mag();
+        assert!(diverged.is_nan() || diverged > 1e6);
+        assert!(largest < 10.0);
+        assert!(fake_particles[0].position.mag() < 1e-3);
+    }
+
+    #[test]
+    fn fake_spring_guards() {
+        let mut particles = vec![Particle {
+            position: Vec3(1.0, 2.0, 0.0),
+            ..Particle::default()
+        }];
+        ParticleFakeSpring::new(Vec3(0.0, 0.0, 0.0), 1.0, 0.0).update_force(&mut particles, 0, 0.0);
+        assert_eq!(particles[0].force_accum, Vec3(0.0, 0.0, 0.0));
+        particles[0].inverse_mass = 0.0;
+        ParticleFakeSpring::new(Vec3(0.0, 0.0, 0.0), 1.0, 0.0).update_force(&mut particles, 0, 0.1);
+        assert_eq!(particles[0].force_accum, Vec3(0.0, 0.0, 0.0));
+    }
+
+    /// The force a fake spring to the origin puts on a unit mass resting at `x`.
+    fn fake_force(spring_constant: f64, damping: f64, x: f64, duration: f64) -> f64 {
+        let particles = vec![Particle {
+            position: Vec3(x, 0.0, 0.0),
+            ..Particle::default()
+        }];
+        let mut spring = ParticleFakeSpring::new(Vec3(0.0, 0.0, 0.0), spring_constant, damping);
+        spring.force(&particles, 0, duration).0
+    }
+
+    #[test]
+    fn fake_spring_critical() {
+        // Aims for `(1 + t) * e^-t`, the critically damped path from rest at 1.
+        let t = 0.1_f64;
+        let target = (1.0 + t) * (-t).exp();
+        assert_approx_eq!(fake_force(1.0, 2.0, 1.0, t), (target - 1.0) / (t * t));
+    }
+
+    #[test]
+    fn fake_spring_overdamped() {
+        // Aims for `2 * e^-t - e^-2t`, the overdamped path from rest at 1.
+        let t = 0.1_f64;
+        let target = 2.0 * (-t).exp() - (-2.0 * t).exp();
+        assert_approx_eq!(fake_force(2.0, 3.0, 1.0, t), (target - 1.0) / (t * t));
+        // A stiff, heavily damped spring still pulls the particle in, without overshooting.
+        let mut particles = vec![Particle {
+            position: Vec3(1.0, 0.0, 0.0),
+            ..Particle::default()
+        }];
+        let mut spring = ParticleFakeSpring::new(Vec3(0.0, 0.0, 0.0), 10_000.0, 1_000.0);
+        let mut last = 1.0;
+        for _ in 0..600 {
+            spring.update_force(&mut particles, 0, 1.0 / 60.0);
+            particles[0].integrate(1.0 / 60.0);
+            particles[0].clear_accumulator();
+            let x = particles[0].position.0;
+            assert!(x > 0.0 && x <= last);
+            last = x;
+        }
+        assert!(last < 1e-3);
+    }
+}
@@ -0,0 +1,123 @@
+use crate::force::{MaybeSend, ParticleForceGenerator};
+use crate::particle::Particle;
+use crate::vec::Vec3;
+use num::Float;
+use num_traits::NumAssign;
+
+/// Scales a vortex's force by a particle's distance from its axis.
+#[cfg(not(feature = "rayon"))]
+pub type RadialFalloff<T> = Box<dyn Fn(T) -> T>;
+
+/// Scales a vortex's force by a particle's distance from its axis.
+#[cfg(feature = "rayon")]
+pub type RadialFalloff<T> = Box<dyn Fn(T) -> T + Send>;
+
+/// A whirlpool around the line through `point` along `axis`, which must be a unit vector.
+///
+/// Particles are pushed around the axis (counterclockwise, looking down `axis`) by `rotation`,
+/// toward it by `inward`, and along it by `lift`. All three are scaled by the falloff, a function
+/// of the particle's distance from the axis, which is 1 everywhere unless set with
+/// `with_falloff`. Particles on the axis are only lifted, since they have no direction around
+/// or toward it.
+pub struct ParticleVortex<T: Float> {
+    pub point: Vec3<T>,
+    pub axis: Vec3<T>,
+    pub rotation: T,
+    pub inward: T,
+    pub lift: T,
+    falloff: Option<RadialFalloff<T>>,
+}
+
+impl<T: Float + NumAssign> ParticleVortex<T> {
+    pub fn new(point: Vec3<T>, axis: Vec3<T>, rotation: T, inward: T, lift: T) -> Self {
+        Self {
+            point,
+            axis,
+            rotation,
+            inward,
+            lift,
+            falloff: None,
+        }
+    }
+
+    pub fn with_falloff<F>(mut self, falloff: F) -> Self
+    where
+        F: Fn(T) -> T + MaybeSend + 'static,
+    {
+        self.falloff = Some(Box::new(falloff));
+        self
+    }
+
+    /// The force on a particle at `position`.
+    pub fn force_at(&self, position: Vec3<T>) -> Vec3<T> {
+        let offset = position - self.point;
+        let radial = offset - self.axis * offset.dot(self.axis);
+        let distance = radial.mag();
+        let scale = self.falloff.as_ref().map_or(T::one(), |f| f(distance));
+        let lift = self.axis * self.lift;
+        if distance.is_zero() {
+            return lift * scale;
+        }
+        let outward = radial / distance;
+        let around = self.axis.cross(outward);
+        (around * self.rotation - outward * self.inward + lift) * scale
+    }
+}
+
+impl<T: Float + NumAssign> ParticleForceGenerator<T> for ParticleVortex<T> {
+    fn force(&mut self, particles: &[Particle<T>], index: usize, _duration: T) -> Vec3<T> {
+        let particle = &particles[index];
+        self.force_at(particle.position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::force::{ParticleForceGenerator, ParticleVortex};
+    use crate::particle::Particle;
+    use crate::vec::Vec3;
+    use assert_approx_eq::assert_approx_eq;
+
+    fn vortex() -> ParticleVortex<f64> {
+        ParticleVortex::new(Vec3(1.0, 0.0, 1.0), Vec3(0.0, 1.0, 0.0), 2.0, 0.5, 0.25)
+    }
+
+    #[test]
+    fn spins_up() {
+        let mut vortex = vortex();
+        let mut particles = vec![Particle {
+            position: Vec3(3.0, 0.0, 1.0),
+            ..Particle::default()
+        }];
+        let center = vortex.point;
+        let angular_momentum = |p: &Particle<f64>| (p.position - center).cross(p.velocity).1;
+        let mut last = angular_momentum(&particles[0]);
+        for _ in 0..100 {
+            vortex.update_force(&mut particles, 0, 0.01);
+            particles[0].integrate(0.01);
+            let now = angular_momentum(&particles[0]);
+            assert!(now > last);
+            last = now;
+        }
+    }
+
+    #[test]
+    fn perpendicular() {
+        let vortex = vortex().with_falloff(|r| 1.0 / (1.0 + r));
+        let point = Vec3(-2.0, 5.0, 3.0);
+        let force = vortex.force_at(point);
+        let radial = Vec3(-3.0, 0.0, 2.0);
+        // Without the inward pull and lift, only the push around the axis remains.
+        let around = force + radial.norm() * (0.5 / (1.0 + radial.mag()))
+            - Vec3(0.0, 0.25, 0.0) / (1.0 + radial.mag());
+        assert_approx_eq!(around.dot(radial), 0.0);
+        assert_approx_eq!(around.mag(), 2.0 / (1.0 + radial.mag()));
+        assert_approx_eq!(force.1, 0.25 / (1.0 + radial.mag()));
+    }
+
+    #[test]
+    fn on_axis() {
+        let vortex = vortex().with_falloff(|r| 1.0 / (1.0 + r));
+        assert_eq!(vortex.force_at(Vec3(1.0, 7.0, 1.0)), Vec3(0.0, 0.25, 0.0));
+    }
+}
@@ -1,3 +1,17 @@
+//! A particle physics engine, after Ian Millington's Cyclone.
+//!
+//! # Determinism
+//!
+//! A simulation run twice on one machine gives identical results, but the same simulation on
+//! another platform may not: functions like `powf` and `sin` come from the platform's math
+//! library, and differ in their last bits between them. For lockstep networking or replays
+//! shared across machines, enable the `deterministic` feature. Every square root, power,
+//! exponential, and trigonometric function the crate calls is then computed with `libm`'s
+//! portable software implementations instead.
+//!
+//! Everything else is plain IEEE 754 arithmetic, which is already the same everywhere, as long
+//! as the compiler isn't allowed to fuse or reorder operations (Rust never does by default).
+//! Closures you pass in, like a `ClosureForce` or custom wind turbulence, are up to you.
 #![warn(
     warnings,
     future_incompatible,
@@ -7,5 +21,22 @@
     rustdoc,
     unused
 )]
+pub mod bounds;
+pub mod collide;
+pub mod constraint;
+pub mod contact;
+pub mod force;
+#[cfg(feature = "linalg")]
+pub mod linalg;
+pub mod link;
+pub mod material;
+mod math;
 pub mod particle;
+pub mod pool;
+pub mod quaternion;
+mod slot;
+pub mod spatial;
+pub mod trail;
+pub mod trigger;
 pub mod vec;
+pub mod world;
@@ -0,0 +1,239 @@
+//! Solves whole networks of constraints at once, rather than one constraint at a time.
+//!
+//! Requires the `linalg` feature.
+use crate::constraint::DistanceConstraint;
+use crate::math;
+use crate::particle::Particle;
+use crate::vec::Vec3;
+use num::Float;
+use num_traits::NumAssign;
+
+/// Corrects all constraints jointly, with `iterations` rounds of (Gauss-)Newton's method.
+///
+/// Each round linearizes the constraints around the current positions, `C + J Δx = 0`, where
+/// `J` holds each constraint's direction, and solves for the smallest mass-weighted correction
+/// `Δx` by conjugate gradient on the sparse normal equations. Networks with more constraints
+/// than degrees of freedom (like triangulated grids) give overdetermined systems, which this
+/// solves in the least-squares sense. After the final round, relative velocities along the
+/// constraints are removed with one more joint solve.
+///
+/// Stiff networks of many interconnected constraints, which `constraint::solve_iterative` needs
+/// many sweeps to settle, typically converge in a handful of rounds.
+pub fn solve_global<T: Float + NumAssign>(
+    particles: &mut [Particle<T>],
+    constraints: &[DistanceConstraint<T>],
+    iterations: usize,
+) {
+    let mut system = System::new(particles, constraints.len());
+    let mut rhs = vec![T::zero(); constraints.len()];
+    for _ in 0..iterations {
+        for ((constraint, normal), b) in constraints.iter().zip(&mut system.normals).zip(&mut rhs) {
+            let (error, direction) = constraint.error(particles);
+            *normal = direction;
+            *b = -error;
+        }
+        let corrections = system.solve(constraints, &rhs);
+        for (particle, correction) in particles.iter_mut().zip(corrections) {
+            particle.position += correction;
+        }
+    }
+    for ((constraint, normal), b) in constraints.iter().zip(&mut system.normals).zip(&mut rhs) {
+        let (a, b_index) = constraint.particles;
+        *normal = constraint.error(particles).1;
+        *b = -(particles[a].velocity - particles[b_index].velocity).dot(*normal);
+    }
+    let impulses = system.solve(constraints, &rhs);
+    for (particle, impulse) in particles.iter_mut().zip(impulses) {
+        particle.velocity += impulse;
+    }
+}
+
+/// Solves `min |J D z - rhs|` without building `J`, where `D` scales each particle by the
+/// square root of its inverse mass, so `D z` is the mass-weighted correction.
+struct System<T: Float> {
+    normals: Vec<Vec3<T>>,
+    scales: Vec<T>,
+}
+
+impl<T: Float + NumAssign> System<T> {
+    fn new(particles: &[Particle<T>], constraints: usize) -> Self {
+        Self {
+            normals: vec![Vec3(T::zero(), T::zero(), T::zero()); constraints],
+            scales: particles
+                .iter()
+                .map(|p| math::sqrt(p.inverse_mass))
+                .collect(),
+        }
+    }
+
+    /// `out = J D z`
+    fn forward(&self, constraints: &[DistanceConstraint<T>], z: &[Vec3<T>], out: &mut [T]) {
+        for ((constraint, normal), o) in constraints.iter().zip(&self.normals).zip(out) {
+            let (a, b) = constraint.particles;
+            *o = normal.dot(z[a] * self.scales[a] - z[b] * self.scales[b]);
+        }
+    }
+
+    /// `out = Dᵀ Jᵀ r`
+    fn backward(&self, constraints: &[DistanceConstraint<T>], r: &[T], out: &mut [Vec3<T>]) {
+        for v in out.iter_mut() {
+            *v = Vec3(T::zero(), T::zero(), T::zero());
+        }
+        for ((constraint, normal), r) in constraints.iter().zip(&self.normals).zip(r) {
+            let (a, b) = constraint.particles;
+            out[a] += *normal * (*r * self.scales[a]);
+            out[b] -= *normal * (*r * self.scales[b]);
+        }
+    }
+
+    /// Conjugate gradient on the normal equations (CGLS), returning the per-particle
+    /// correction `D z`. The normal equations are always consistent, so this converges even
+    /// when the constraints can't all be satisfied at once.
+    fn solve(&self, constraints: &[DistanceConstraint<T>], rhs: &[T]) -> Vec<Vec3<T>> {
+        let zero = Vec3(T::zero(), T::zero(), T::zero());
+        let particles = self.scales.len();
+        let mut z = vec![zero; particles];
+        let mut r = rhs.to_vec();
+        let mut s = vec![zero; particles];
+        self.backward(constraints, &r, &mut s);
+        let mut p = s.clone();
+        let mut q = vec![T::zero(); rhs.len()];
+        let mut gamma = s.iter().fold(T::zero(), |sum, v| sum + v.mag_squared());
+        let tolerance = gamma * T::epsilon() * T::epsilon();
+        for _ in 0..particles * 3 {
+            if gamma <= tolerance || gamma.is_zero() {
+                break;
+            }
+            self.forward(constraints, &p, &mut q);
+            let qq = q.iter().fold(T::zero(), |sum, x| sum + *x * *x);
+            if qq.is_zero() {
+                break;
+            }
+            let alpha = gamma / qq;
+            for (z, p) in z.iter_mut().zip(&p) {
+                *z += *p * alpha;
+            }
+            for (r, q) in r.iter_mut().zip(&q) {
+                *r -= alpha * *q;
+            }
+            self.backward(constraints, &r, &mut s);
+            let gamma_next = s.iter().fold(T::zero(), |sum, v| sum + v.mag_squared());
+            let beta = gamma_next / gamma;
+            for (p, s) in p.iter_mut().zip(&s) {
+                *p = *s + *p * beta;
+            }
+            gamma = gamma_next;
+        }
+        z.iter()
+            .zip(&self.scales)
+            .map(|(z, scale)| *z * *scale)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constraint::{solve_iterative, DistanceConstraint};
+    use crate::linalg::solve_global;
+    use crate::particle::Particle;
+    use crate::vec::Vec3;
+
+    type Grid = (
+        Vec<Particle<f64>>,
+        Vec<Particle<f64>>,
+        Vec<DistanceConstraint<f64>>,
+    );
+
+    /// A triangulated `n` by `n` grid in the xy plane, pinned at two corners, with every free
+    /// particle nudged away from its rest position. Returns the rest and nudged particles.
+    fn grid(n: usize) -> Grid {
+        let mut rest = Vec::new();
+        for y in 0..n {
+            for x in 0..n {
+                rest.push(Particle {
+                    position: Vec3(x as f64, y as f64, 0.0),
+                    inverse_mass: if y == 0 && (x == 0 || x == n - 1) {
+                        0.0
+                    } else {
+                        1.0
+                    },
+                    ..Particle::default()
+                });
+            }
+        }
+        let mut constraints = Vec::new();
+        let mut link = |a: usize, b: usize| {
+            let length = (rest[a].position - rest[b].position).mag();
+            constraints.push(DistanceConstraint::new(a, b, length));
+        };
+        for y in 0..n {
+            for x in 0..n {
+                let i = y * n + x;
+                if x + 1 < n {
+                    link(i, i + 1);
+                }
+                if y + 1 < n {
+                    link(i, i + n);
+                }
+                if x + 1 < n && y + 1 < n {
+                    link(i, i + n + 1);
+                }
+            }
+        }
+        let mut nudged = rest.clone();
+        for (i, particle) in nudged.iter_mut().enumerate() {
+            if particle.inverse_mass > 0.0 {
+                let phase = i as f64;
+                particle.position += Vec3(phase.sin(), phase.cos(), 0.0) * 0.05;
+                particle.velocity = Vec3(phase.cos(), 0.0, phase.sin());
+            }
+        }
+        (rest, nudged, constraints)
+    }
+
+    fn max_error(particles: &[Particle<f64>], constraints: &[DistanceConstraint<f64>]) -> f64 {
+        constraints
+            .iter()
+            .map(|c| c.error(particles).0.abs())
+            .fold(0.0, f64::max)
+    }
+
+    #[test]
+    fn same_rest_configuration() {
+        let (rest, nudged, constraints) = grid(10);
+        let mut iterative = nudged.clone();
+        let mut global = nudged;
+        solve_iterative(&mut iterative, &constraints, 2000);
+        solve_global(&mut global, &constraints, 10);
+        assert!(max_error(&iterative, &constraints) < 1e-6);
+        assert!(max_error(&global, &constraints) < 1e-12);
+        for ((a, b), r) in iterative.iter().zip(&global).zip(&rest) {
+            assert!((a.position - r.position).mag() < 1e-5);
+            assert!((b.position - r.position).mag() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn velocities_along_constraints_removed() {
+        let (_, mut particles, constraints) = grid(4);
+        solve_global(&mut particles, &constraints, 10);
+        for constraint in &constraints {
+            let (a, b) = constraint.particles;
+            let normal = constraint.error(&particles).1;
+            let closing = (particles[a].velocity - particles[b].velocity).dot(normal);
+            assert!(closing.abs() < 1e-9);
+        }
+        // Only motion out of the plane, which the flat grid can't resist to first order, remains.
+        assert!(particles.iter().any(|p| p.velocity.2.abs() > 1e-3));
+    }
+
+    #[test]
+    fn fewer_iterations() {
+        let (_, nudged, constraints) = grid(10);
+        let mut iterative = nudged.clone();
+        let mut global = nudged;
+        solve_iterative(&mut iterative, &constraints, 5);
+        solve_global(&mut global, &constraints, 5);
+        assert!(max_error(&global, &constraints) < max_error(&iterative, &constraints) * 1e-3);
+    }
+}
@@ -0,0 +1,454 @@
+use crate::contact::{ParticleContact, ParticleContactGenerator};
+use crate::particle::Particle;
+use crate::vec::Vec3;
+use num::Float;
+use num_traits::NumAssign;
+
+/// A cable between two particles, which keeps them from moving more than `max_length` apart.
+///
+/// While the cable is taut it generates a contact pulling the particles back together, bouncing
+/// with `restitution` (a cable is usually a little springy). A slack cable does nothing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParticleCable<T: Float> {
+    pub particles: (usize, usize),
+    pub max_length: T,
+    pub restitution: T,
+}
+
+impl<T: Float + NumAssign> ParticleCable<T> {
+    pub fn new(a: usize, b: usize, max_length: T, restitution: T) -> Self {
+        Self {
+            particles: (a, b),
+            max_length,
+            restitution,
+        }
+    }
+}
+
+impl<T: Float + NumAssign> ParticleContactGenerator<T> for ParticleCable<T> {
+    fn add_contacts(
+        &self,
+        particles: &[Particle<T>],
+        contacts: &mut Vec<ParticleContact<T>>,
+        limit: usize,
+    ) -> usize {
+        let (a, b) = self.particles;
+        let offset = particles[b].position - particles[a].position;
+        let length = offset.mag();
+        if limit == 0 || length < self.max_length {
+            return 0;
+        }
+        contacts.push(ParticleContact {
+            particles: (a, Some(b)),
+            restitution: self.restitution,
+            contact_normal: offset.norm(),
+            penetration: length - self.max_length,
+            static_friction: T::zero(),
+            dynamic_friction: T::zero(),
+        });
+        1
+    }
+}
+
+/// A cable from a particle to a fixed point in space, which keeps the particle within
+/// `max_length` of `anchor`.
+///
+/// Like `ParticleCable`, it generates a contact only while taut. The anchor can be moved
+/// between frames, to hang the particle from something that moves; if it jumps, the particle is
+/// yanked back within reach on the next resolution.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParticleCableConstraint<T: Float> {
+    pub particle: usize,
+    pub anchor: Vec3<T>,
+    pub max_length: T,
+    pub restitution: T,
+}
+
+impl<T: Float + NumAssign> ParticleCableConstraint<T> {
+    pub fn new(particle: usize, anchor: Vec3<T>, max_length: T, restitution: T) -> Self {
+        Self {
+            particle,
+            anchor,
+            max_length,
+            restitution,
+        }
+    }
+
+    pub fn set_anchor(&mut self, anchor: Vec3<T>) {
+        self.anchor = anchor;
+    }
+}
+
+impl<T: Float + NumAssign> ParticleContactGenerator<T> for ParticleCableConstraint<T> {
+    fn add_contacts(
+        &self,
+        particles: &[Particle<T>],
+        contacts: &mut Vec<ParticleContact<T>>,
+        limit: usize,
+    ) -> usize {
+        let offset = self.anchor - particles[self.particle].position;
+        let length = offset.mag();
+        if limit == 0 || length < self.max_length {
+            return 0;
+        }
+        contacts.push(ParticleContact {
+            particles: (self.particle, None),
+            restitution: self.restitution,
+            contact_normal: offset.norm(),
+            penetration: length - self.max_length,
+            static_friction: T::zero(),
+            dynamic_friction: T::zero(),
+        });
+        1
+    }
+}
+
+/// A rigid rod between two particles, which keeps them exactly `length` apart.
+///
+/// Whenever the particles are too far apart or too close together, the rod generates a contact
+/// pulling or pushing them back. Its restitution is always zero, since a rod that bounced
+/// along its length would never come to rest. Coincident particles can't be pushed apart, as
+/// there's no direction to push them in, so a rod's contact does nothing for them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParticleRod<T: Float> {
+    pub particles: (usize, usize),
+    pub length: T,
+}
+
+impl<T: Float + NumAssign> ParticleRod<T> {
+    pub fn new(a: usize, b: usize, length: T) -> Self {
+        Self {
+            particles: (a, b),
+            length,
+        }
+    }
+}
+
+impl<T: Float + NumAssign> ParticleContactGenerator<T> for ParticleRod<T> {
+    fn add_contacts(
+        &self,
+        particles: &[Particle<T>],
+        contacts: &mut Vec<ParticleContact<T>>,
+        limit: usize,
+    ) -> usize {
+        let (a, b) = self.particles;
+        let offset = particles[b].position - particles[a].position;
+        let length = offset.mag();
+        if limit == 0 || length == self.length {
+            return 0;
+        }
+        let (contact_normal, penetration) = if length > self.length {
+            (offset.norm(), length - self.length)
+        } else {
+            (offset.norm().invert(), self.length - length)
+        };
+        contacts.push(ParticleContact {
+            particles: (a, Some(b)),
+            restitution: T::zero(),
+            contact_normal,
+            penetration,
+            static_friction: T::zero(),
+            dynamic_friction: T::zero(),
+        });
+        1
+    }
+}
+
+/// A rigid rod from a particle to a fixed point in space, which keeps the particle exactly
+/// `length` from `anchor`.
+///
+/// Like `ParticleRod`, it pushes or pulls whichever way the particle is off, with no
+/// restitution, so a particle on one swinging under gravity makes a pendulum whose length
+/// doesn't drift.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParticleRodConstraint<T: Float> {
+    pub particle: usize,
+    pub anchor: Vec3<T>,
+    pub length: T,
+}
+
+impl<T: Float + NumAssign> ParticleRodConstraint<T> {
+    pub fn new(particle: usize, anchor: Vec3<T>, length: T) -> Self {
+        Self {
+            particle,
+            anchor,
+            length,
+        }
+    }
+
+    pub fn set_anchor(&mut self, anchor: Vec3<T>) {
+        self.anchor = anchor;
+    }
+}
+
+impl<T: Float + NumAssign> ParticleContactGenerator<T> for ParticleRodConstraint<T> {
+    fn add_contacts(
+        &self,
+        particles: &[Particle<T>],
+        contacts: &mut Vec<ParticleContact<T>>,
+        limit: usize,
+    ) -> usize {
+        let offset = self.anchor - particles[self.particle].position;
+        let length = offset.mag();
+        if limit == 0 || length == self.length {
+            return 0;
+        }
+        let (contact_normal, penetration) = if length > self.length {
+            (offset.norm(), length - self.length)
+        } else {
+            (offset.norm().invert(), self.length - length)
+        };
+        contacts.push(ParticleContact {
+            particles: (self.particle, None),
+            restitution: T::zero(),
+            contact_normal,
+            penetration,
+            static_friction: T::zero(),
+            dynamic_friction: T::zero(),
+        });
+        1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::contact::{ParticleContactGenerator, ParticleContactResolver};
+    use crate::link::{ParticleCable, ParticleCableConstraint, ParticleRod, ParticleRodConstraint};
+    use crate::particle::Particle;
+    use crate::vec::Vec3;
+    use crate::world::ParticleWorld;
+    use assert_approx_eq::assert_approx_eq;
+
+    /// Two particles `separation` apart along x, tied by a cable of length 2.
+    fn tethered(separation: f64) -> ParticleWorld<f64> {
+        let mut world = ParticleWorld::new(4).with_iterations(4);
+        world.add_particle(Particle::default());
+        world.add_particle(Particle {
+            position: Vec3(separation, 0.0, 0.0),
+            ..Particle::default()
+        });
+        world.add_contact_generator(ParticleCable::new(0, 1, 2.0, 0.3));
+        world
+    }
+
+    fn step(world: &mut ParticleWorld<f64>) {
+        world.integrate(0.01);
+        world.generate_contacts();
+        world.resolve_contacts(0.01);
+    }
+
+    fn length(world: &ParticleWorld<f64>) -> f64 {
+        let particles = world.particles();
+        (particles[1].position - particles[0].position).mag()
+    }
+
+    #[test]
+    fn yanks_back() {
+        let mut world = tethered(1.0);
+        world.particles_mut()[0].velocity = Vec3(-5.0, 1.0, 0.0);
+        world.particles_mut()[1].velocity = Vec3(5.0, 0.0, 0.0);
+        let mut taut = 0;
+        for _ in 0..200 {
+            step(&mut world);
+            assert!(length(&world) <= 2.0 + 1e-9);
+            if world.generate_contacts() > 0 {
+                taut += 1;
+            }
+        }
+        assert!(taut > 0);
+        let particles = world.particles();
+        // Momentum is conserved, so the pair drifts with the one velocity it started with.
+        let momentum = particles[0].velocity + particles[1].velocity;
+        assert_approx_eq!(momentum.0, 0.0);
+        assert_approx_eq!(momentum.1, 1.0);
+    }
+
+    #[test]
+    fn slack() {
+        let mut world = tethered(1.0);
+        let mut free = Particle::default();
+        for particle in world.particles_mut().iter_mut().chain(Some(&mut free)) {
+            particle.acceleration = Vec3(0.0, -9.8, 0.0);
+            particle.velocity = Vec3(0.0, 3.0, 0.0);
+        }
+        for _ in 0..100 {
+            step(&mut world);
+            free.integrate(0.01);
+            assert_eq!(world.contacts().len(), 0);
+        }
+        assert_eq!(world.particles()[0], free);
+    }
+
+    #[test]
+    fn immovable_end() {
+        let mut world = tethered(3.0);
+        world.particles_mut()[0].inverse_mass = 0.0;
+        step(&mut world);
+        assert_eq!(world.particles()[0].position, Vec3(0.0, 0.0, 0.0));
+        assert_approx_eq!(world.particles()[1].position.0, 2.0);
+    }
+
+    /// A particle hanging from a cable of length 1, starting level with the anchor.
+    fn pendulum() -> (Vec<Particle<f64>>, ParticleCableConstraint<f64>) {
+        let particles = vec![Particle {
+            position: Vec3(1.0, 2.0, 0.0),
+            acceleration: Vec3(0.0, -9.8, 0.0),
+            ..Particle::default()
+        }];
+        (
+            particles,
+            ParticleCableConstraint::new(0, Vec3(0.0, 2.0, 0.0), 1.0, 0.0),
+        )
+    }
+
+    fn step_constraint<G>(particles: &mut [Particle<f64>], constraint: &G, duration: f64)
+    where
+        G: ParticleContactGenerator<f64>,
+    {
+        particles[0].integrate(duration);
+        let mut contacts = Vec::new();
+        constraint.add_contacts(particles, &mut contacts, 1);
+        ParticleContactResolver::new(4, 0.0, 1.0).resolve_contacts(
+            &mut contacts,
+            particles,
+            duration,
+        );
+    }
+
+    #[test]
+    fn constraint_swings() {
+        let (mut particles, cable) = pendulum();
+        let mut leftmost = 1.0_f64;
+        for _ in 0..500 {
+            step_constraint(&mut particles, &cable, 0.01);
+            let length = (particles[0].position - cable.anchor).mag();
+            assert!(length <= 1.0 + 1e-9);
+            leftmost = leftmost.min(particles[0].position.0);
+        }
+        assert!(leftmost < -0.5);
+    }
+
+    #[test]
+    fn constraint_set_anchor() {
+        let (mut particles, mut cable) = pendulum();
+        step_constraint(&mut particles, &cable, 0.01);
+        cable.set_anchor(Vec3(10.0, 5.0, 0.0));
+        step_constraint(&mut particles, &cable, 0.01);
+        let particle = particles[0];
+        assert_approx_eq!((particle.position - cable.anchor).mag(), 1.0);
+        assert!(particle.velocity.iter().all(f64::is_finite));
+    }
+
+    #[test]
+    fn constraint_set_anchor_in_world() {
+        let (particles, cable) = pendulum();
+        let mut world = ParticleWorld::new(4);
+        world.add_particle(particles[0]);
+        let id = world.add_contact_generator(cable);
+        step(&mut world);
+        world
+            .contact_generator_mut::<ParticleCableConstraint<f64>>(id)
+            .unwrap()
+            .set_anchor(Vec3(10.0, 5.0, 0.0));
+        step(&mut world);
+        let anchor = Vec3(10.0, 5.0, 0.0);
+        assert_approx_eq!((world.particles()[0].position - anchor).mag(), 1.0);
+        assert!(world
+            .contact_generator_mut::<ParticleRodConstraint<f64>>(id)
+            .is_none());
+    }
+
+    #[test]
+    fn rod_spinning() {
+        let mut world = ParticleWorld::new(4).with_iterations(4);
+        for (x, vy) in &[(0.0, -2.0), (1.0, 3.0)] {
+            world.add_particle(Particle {
+                position: Vec3(*x, 0.0, 0.0),
+                velocity: Vec3(0.5, *vy, 0.0),
+                acceleration: Vec3(0.0, -9.8, 0.0),
+                ..Particle::default()
+            });
+        }
+        world.add_contact_generator(ParticleRod::new(0, 1, 1.0));
+        for _ in 0..500 {
+            step(&mut world);
+            assert_approx_eq!(length(&world), 1.0, 1e-9);
+        }
+    }
+
+    #[test]
+    fn rod_corrects() {
+        for &separation in &[0.5, 1.0, 3.5] {
+            let mut world = tethered(separation);
+            world.add_contact_generator(ParticleRod::new(0, 1, 2.0));
+            step(&mut world);
+            assert_approx_eq!(length(&world), 2.0);
+            assert_eq!(world.particles()[0].velocity, Vec3(0.0, 0.0, 0.0));
+        }
+    }
+
+    #[test]
+    fn rod_zero_length() {
+        let mut world = tethered(0.0);
+        world.add_contact_generator(ParticleRod::new(0, 1, 1.0));
+        step(&mut world);
+        for particle in world.particles() {
+            assert_eq!(particle.position, Vec3(0.0, 0.0, 0.0));
+        }
+        let mut world = tethered(1.0);
+        world.add_contact_generator(ParticleRod::new(0, 1, 0.0));
+        for _ in 0..3 {
+            step(&mut world);
+            assert_approx_eq!(length(&world), 0.0);
+        }
+        assert!(world.particles()[0].position.0.is_finite());
+    }
+
+    #[test]
+    fn rod_constraint_pendulum() {
+        let (mut particles, _) = pendulum();
+        let rod = ParticleRodConstraint::new(0, Vec3(0.0, 2.0, 0.0), 1.0);
+        let mut leftmost = 1.0_f64;
+        for _ in 0..10 * 120 {
+            step_constraint(&mut particles, &rod, 1.0 / 120.0);
+            let length = (particles[0].position - rod.anchor).mag();
+            assert!((length - 1.0).abs() < 0.01);
+            leftmost = leftmost.min(particles[0].position.0);
+        }
+        assert!(leftmost < -0.5);
+    }
+
+    #[test]
+    fn rod_constraint_corrects() {
+        for &x in &[0.2, 3.0] {
+            let mut particles = vec![Particle {
+                position: Vec3(x, 0.0, 0.0),
+                ..Particle::default()
+            }];
+            let rod = ParticleRodConstraint::new(0, Vec3(0.0, 0.0, 0.0), 1.0);
+            for _ in 0..3 {
+                step_constraint(&mut particles, &rod, 0.01);
+            }
+            assert_approx_eq!(particles[0].position.mag(), 1.0);
+        }
+    }
+
+    #[test]
+    fn rod_constraint_set_anchor_in_world() {
+        let mut world = ParticleWorld::new(4);
+        world.add_particle(Particle {
+            position: Vec3(1.0, 0.0, 0.0),
+            ..Particle::default()
+        });
+        let rod = ParticleRodConstraint::new(0, Vec3(0.0, 0.0, 0.0), 1.0);
+        let id = world.add_contact_generator(rod);
+        world
+            .contact_generator_mut::<ParticleRodConstraint<f64>>(id)
+            .unwrap()
+            .set_anchor(Vec3(3.0, 0.0, 0.0));
+        for _ in 0..3 {
+            step(&mut world);
+        }
+        assert_approx_eq!(world.particles()[0].position.0, 2.0);
+    }
+}
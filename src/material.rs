@@ -0,0 +1,170 @@
+//! What things are made of, and how two materials in contact decide how bouncy and grippy it is.
+use num::Float;
+use num_traits::NumAssign;
+use std::collections::HashMap;
+
+/// Identifies a material added to a `MaterialTable`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MaterialId(usize);
+
+/// How bouncy and grippy a material is; see `ParticleContact` for what each coefficient does.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Material<T: Float> {
+    pub restitution: T,
+    pub static_friction: T,
+    pub dynamic_friction: T,
+}
+
+impl<T: Float + NumAssign> Material<T> {
+    pub fn new(restitution: T, static_friction: T, dynamic_friction: T) -> Self {
+        Self {
+            restitution,
+            static_friction,
+            dynamic_friction,
+        }
+    }
+}
+
+/// How two materials' values for a coefficient combine into the one their contact uses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum CombineRule {
+    Min,
+    Max,
+    Multiply,
+    #[default]
+    Average,
+}
+
+impl CombineRule {
+    pub fn combine<T: Float>(self, a: T, b: T) -> T {
+        match self {
+            CombineRule::Min => a.min(b),
+            CombineRule::Max => a.max(b),
+            CombineRule::Multiply => a * b,
+            CombineRule::Average => (a + b) / (T::one() + T::one()),
+        }
+    }
+}
+
+/// The materials in a world, and how the restitution and friction of a contact between two
+/// of them is found.
+///
+/// A pair given its own coefficients with `set_pair` uses those. Any other pair combines the
+/// two materials' coefficients, restitution by `restitution_rule` and both frictions by
+/// `friction_rule`. Both rules average by default.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaterialTable<T: Float> {
+    pub restitution_rule: CombineRule,
+    pub friction_rule: CombineRule,
+    materials: Vec<Material<T>>,
+    /// Keyed with the lower id first.
+    pairs: HashMap<(MaterialId, MaterialId), Material<T>>,
+}
+
+impl<T: Float + NumAssign> Default for MaterialTable<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Float + NumAssign> MaterialTable<T> {
+    pub fn new() -> Self {
+        Self {
+            restitution_rule: CombineRule::default(),
+            friction_rule: CombineRule::default(),
+            materials: Vec::new(),
+            pairs: HashMap::new(),
+        }
+    }
+
+    pub fn with_rules(mut self, restitution_rule: CombineRule, friction_rule: CombineRule) -> Self {
+        self.restitution_rule = restitution_rule;
+        self.friction_rule = friction_rule;
+        self
+    }
+
+    pub fn add(&mut self, material: Material<T>) -> MaterialId {
+        self.materials.push(material);
+        MaterialId(self.materials.len() - 1)
+    }
+
+    pub fn get(&self, id: MaterialId) -> Option<&Material<T>> {
+        self.materials.get(id.0)
+    }
+
+    pub fn get_mut(&mut self, id: MaterialId) -> Option<&mut Material<T>> {
+        self.materials.get_mut(id.0)
+    }
+
+    /// Makes contacts between `a` and `b`, in either order, use `material`'s coefficients
+    /// instead of combining theirs.
+    pub fn set_pair(&mut self, a: MaterialId, b: MaterialId, material: Material<T>) {
+        self.pairs.insert(Self::key(a, b), material);
+    }
+
+    /// Goes back to combining the coefficients of `a` and `b`, returning what the pair used.
+    pub fn remove_pair(&mut self, a: MaterialId, b: MaterialId) -> Option<Material<T>> {
+        self.pairs.remove(&Self::key(a, b))
+    }
+
+    /// The coefficients for a contact between `a` and `b`, or `None` if either isn't in the
+    /// table.
+    pub fn combine(&self, a: MaterialId, b: MaterialId) -> Option<Material<T>> {
+        let (first, second) = (self.get(a)?, self.get(b)?);
+        if let Some(&material) = self.pairs.get(&Self::key(a, b)) {
+            return Some(material);
+        }
+        let (restitution, friction) = (self.restitution_rule, self.friction_rule);
+        Some(Material::new(
+            restitution.combine(first.restitution, second.restitution),
+            friction.combine(first.static_friction, second.static_friction),
+            friction.combine(first.dynamic_friction, second.dynamic_friction),
+        ))
+    }
+
+    fn key(a: MaterialId, b: MaterialId) -> (MaterialId, MaterialId) {
+        (a.min(b), a.max(b))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::material::{CombineRule, Material, MaterialTable};
+
+    #[test]
+    fn rules() {
+        let (a, b) = (0.25, 0.5);
+        assert_eq!(CombineRule::Min.combine(a, b), 0.25);
+        assert_eq!(CombineRule::Max.combine(a, b), 0.5);
+        assert_eq!(CombineRule::Multiply.combine(a, b), 0.125);
+        assert_eq!(CombineRule::Average.combine(a, b), 0.375);
+    }
+
+    #[test]
+    fn combine() {
+        let mut table = MaterialTable::new().with_rules(CombineRule::Max, CombineRule::Multiply);
+        let rubber = table.add(Material::new(0.75, 1.0, 0.5));
+        let steel = table.add(Material::new(0.5, 0.5, 0.25));
+        let ice = table.add(Material::new(0.25, 0.125, 0.0));
+        assert_eq!(
+            table.combine(rubber, steel),
+            Some(Material::new(0.75, 0.5, 0.125))
+        );
+        assert_eq!(table.combine(steel, rubber), table.combine(rubber, steel));
+        // An explicit pair beats the rules, whichever way round it's asked for.
+        let squeaky = Material::new(0.0, 2.0, 1.5);
+        table.set_pair(steel, rubber, squeaky);
+        assert_eq!(table.combine(rubber, steel), Some(squeaky));
+        assert_eq!(table.combine(steel, rubber), Some(squeaky));
+        assert_eq!(
+            table.combine(rubber, ice),
+            Some(Material::new(0.75, 0.125, 0.0))
+        );
+        assert_eq!(table.remove_pair(rubber, steel), Some(squeaky));
+        assert_eq!(
+            table.combine(rubber, steel),
+            Some(Material::new(0.75, 0.5, 0.125))
+        );
+        assert_eq!(MaterialTable::<f64>::new().combine(rubber, steel), None);
+    }
+}
@@ -0,0 +1,74 @@
+//! The floating-point functions whose results the `deterministic` feature pins down.
+//!
+//! Without the feature these are `T`'s own methods. With it they're computed in `f64` by
+//! `libm`'s software implementations and rounded back to `T`, so they give the same bits on
+//! every platform, at some cost in speed.
+use num::Float;
+
+#[cfg(feature = "deterministic")]
+fn via_f64<T: Float>(x: T, f: impl FnOnce(f64) -> f64) -> T {
+    T::from(f(x.to_f64().unwrap())).unwrap()
+}
+
+#[cfg(not(feature = "deterministic"))]
+pub(crate) fn sqrt<T: Float>(x: T) -> T {
+    x.sqrt()
+}
+
+#[cfg(feature = "deterministic")]
+pub(crate) fn sqrt<T: Float>(x: T) -> T {
+    via_f64(x, libm::sqrt)
+}
+
+#[cfg(not(feature = "deterministic"))]
+pub(crate) fn powf<T: Float>(x: T, y: T) -> T {
+    x.powf(y)
+}
+
+#[cfg(feature = "deterministic")]
+pub(crate) fn powf<T: Float>(x: T, y: T) -> T {
+    let y = y.to_f64().unwrap();
+    via_f64(x, |x| libm::pow(x, y))
+}
+
+#[cfg(not(feature = "deterministic"))]
+pub(crate) fn exp<T: Float>(x: T) -> T {
+    x.exp()
+}
+
+#[cfg(feature = "deterministic")]
+pub(crate) fn exp<T: Float>(x: T) -> T {
+    via_f64(x, libm::exp)
+}
+
+#[cfg(not(feature = "deterministic"))]
+pub(crate) fn sin_cos<T: Float>(x: T) -> (T, T) {
+    x.sin_cos()
+}
+
+#[cfg(feature = "deterministic")]
+pub(crate) fn sin_cos<T: Float>(x: T) -> (T, T) {
+    (via_f64(x, libm::sin), via_f64(x, libm::cos))
+}
+
+#[cfg(not(feature = "deterministic"))]
+pub(crate) fn atan2<T: Float>(y: T, x: T) -> T {
+    y.atan2(x)
+}
+
+#[cfg(feature = "deterministic")]
+pub(crate) fn atan2<T: Float>(y: T, x: T) -> T {
+    let x = x.to_f64().unwrap();
+    via_f64(y, |y| libm::atan2(y, x))
+}
+
+#[cfg(not(feature = "deterministic"))]
+pub(crate) fn hypot<T: Float>(x: T, y: T) -> T {
+    x.hypot(y)
+}
+
+#[cfg(feature = "deterministic")]
+pub(crate) fn hypot<T: Float>(x: T, y: T) -> T {
+    let y = y.to_f64().unwrap();
+    via_f64(x, |x| libm::hypot(x, y))
+}
@@ -1,3 +1,4 @@
+use crate::math;
 use crate::vec::Vec3;
 use num::Float;
 use num_traits::NumAssign;
@@ -7,7 +8,18 @@ pub struct Particle<T: Float> {
     pub position: Vec3<T>,
     pub velocity: Vec3<T>,
     pub acceleration: Vec3<T>,
+    /// The fraction of velocity retained after one second, applied by `integrate` as
+    /// `damping.powf(duration)`.
+    ///
+    /// `1.0` is no damping and `0.5` halves the speed every second, regardless of the step size.
+    /// See `set_linear_damping_per_second` and `drag_half_life`, and `integrate_anisotropic` to
+    /// damp each axis differently.
     pub damping: T,
+    /// Scales the force `ParticleGravity` applies to this particle.
+    ///
+    /// `0.5` makes it float down like an ember, and `0.0` makes it ignore gravity entirely.
+    /// Neither the `acceleration` field nor any other force is affected.
+    pub gravity_scale: T,
     /// We store the inverse mass because it makes infinite mass possible and zero mass impossible.
     ///
     /// Zero mass would be problematic because any force would result in infinite acceleration,
@@ -21,12 +33,377 @@ pub struct Particle<T: Float> {
     /// ```
     /// `(1/m)` is inverse mass.
     pub inverse_mass: T,
+    /// Forces added since the last `integrate`. They are applied and then cleared by `integrate`.
+    pub force_accum: Vec3<T>,
+}
+
+impl<T: Float> Default for Particle<T> {
+    /// A stationary particle of unit mass at the origin, with no damping and full gravity.
+    fn default() -> Self {
+        let zero = Vec3(T::zero(), T::zero(), T::zero());
+        Self {
+            position: zero,
+            velocity: zero,
+            acceleration: zero,
+            damping: T::one(),
+            gravity_scale: T::one(),
+            inverse_mass: T::one(),
+            force_accum: zero,
+        }
+    }
 }
 
 impl<T: Float + NumAssign> Particle<T> {
     pub fn integrate(&mut self, duration: T) {
+        self.integrate_reporting(duration);
+    }
+
+    /// Like `integrate`, returning how far the particle moved.
+    ///
+    /// For continuous collision detection: sweeping the segment from the old position to the new
+    /// catches collisions a fast particle would otherwise tunnel through.
+    pub fn integrate_reporting(&mut self, duration: T) -> Vec3<T> {
+        let retained = math::powf(self.damping, duration);
+        self.advance(duration, Vec3(retained, retained, retained))
+    }
+
+    /// Like `integrate`, but damping each component of the velocity by its own factor from
+    /// `damping` instead of by `self.damping`.
+    ///
+    /// For media that resist motion more in some directions than others, like grass, which
+    /// stops a particle sinking into it but barely slows one skimming across the top.
+    pub fn integrate_anisotropic(&mut self, duration: T, damping: Vec3<T>) {
+        let retained = |damping| math::powf(damping, duration);
+        self.advance(
+            duration,
+            Vec3(
+                retained(damping.0),
+                retained(damping.1),
+                retained(damping.2),
+            ),
+        );
+    }
+
+    /// Integrates, scaling the velocity by `retained` component by component.
+    fn advance(&mut self, duration: T, retained: Vec3<T>) -> Vec3<T> {
+        let start = self.position;
         self.position += self.velocity * duration;
-        self.velocity += self.acceleration * duration;
-        self.velocity *= self.damping.powf(duration);
+        let acceleration = self.acceleration + self.force_accum * self.inverse_mass;
+        self.velocity += acceleration * duration;
+        self.velocity *= retained;
+        self.clear_accumulator();
+        // Not `velocity * duration`, which can differ from the actual move by a rounding error.
+        self.position - start
+    }
+
+    /// The launch velocity with magnitude `speed` that carries a particle from `position` to
+    /// `target` under constant `gravity`, or `None` if the target is out of range.
+    ///
+    /// When two trajectories reach the target, the quicker (flatter) one is returned.
+    pub fn aim_at(
+        position: Vec3<T>,
+        target: Vec3<T>,
+        speed: T,
+        gravity: Vec3<T>,
+    ) -> Option<Vec3<T>> {
+        // With offset d and time of flight t, the launch velocity is v = (d - ½gt²) / t.
+        // Requiring |v| = speed gives a quadratic in t²:
+        //   ¼|g|²t⁴ - (speed² + d·g)t² + |d|² = 0
+        let two = T::one() + T::one();
+        let offset = target - position;
+        let a = gravity.mag_squared() / (two * two);
+        let b = -(speed.powi(2) + offset.dot(gravity));
+        let c = offset.mag_squared();
+        let t_squared = if a.is_zero() {
+            if b.is_zero() {
+                return None;
+            }
+            -c / b
+        } else {
+            let discriminant = b.powi(2) - two * two * a * c;
+            if discriminant < T::zero() {
+                return None;
+            }
+            let low = (-b - math::sqrt(discriminant)) / (two * a);
+            let high = (-b + math::sqrt(discriminant)) / (two * a);
+            if low > T::zero() {
+                low
+            } else {
+                high
+            }
+        };
+        if t_squared <= T::zero() {
+            return None;
+        }
+        let t = math::sqrt(t_squared);
+        Some((offset - gravity * (t_squared / two)) / t)
+    }
+
+    /// Sets `damping` so that, without forces, the particle keeps `retain_fraction` of its
+    /// velocity after each second of integration.
+    ///
+    /// `retain_fraction` should be in `0.0..=1.0`: `1.0` disables damping and `0.0` stops the
+    /// particle within any step.
+    pub fn set_linear_damping_per_second(&mut self, retain_fraction: T) {
+        self.damping = retain_fraction;
+    }
+
+    /// The `damping` that halves a particle's velocity every `half_life` seconds.
+    ///
+    /// A `half_life` of infinity gives `1.0`, no damping at all.
+    pub fn drag_half_life(half_life: T) -> T {
+        let half = T::one() / (T::one() + T::one());
+        math::powf(half, half_life.recip())
+    }
+
+    pub fn add_force(&mut self, force: Vec3<T>) {
+        self.force_accum += force;
+    }
+
+    /// Changes the particle's momentum by `impulse` at once, as a collision or explosion does,
+    /// rather than over a frame like a force. Particles with infinite mass aren't moved.
+    pub fn apply_impulse(&mut self, impulse: Vec3<T>) {
+        self.velocity += impulse * self.inverse_mass;
+    }
+
+    pub fn clear_accumulator(&mut self) {
+        self.force_accum = Vec3(T::zero(), T::zero(), T::zero());
+    }
+
+    pub fn has_finite_mass(&self) -> bool {
+        self.inverse_mass > T::zero()
+    }
+
+    /// Infinite for particles with an inverse mass of zero.
+    pub fn mass(&self) -> T {
+        if self.inverse_mass.is_zero() {
+            T::infinity()
+        } else {
+            self.inverse_mass.recip()
+        }
+    }
+}
+
+/// Adds the same `force` to every particle's accumulator.
+///
+/// Much cheaper than registering a generator per particle when a force is uniform, like wind
+/// without turbulence. For uniform gravity, which scales with mass, set `acceleration` instead.
+pub fn add_force_all<T: Float + NumAssign>(particles: &mut [Particle<T>], force: Vec3<T>) {
+    for particle in particles {
+        particle.add_force(force);
+    }
+}
+
+pub fn clear_accumulators_all<T: Float + NumAssign>(particles: &mut [Particle<T>]) {
+    for particle in particles {
+        particle.clear_accumulator();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::particle::{add_force_all, clear_accumulators_all, Particle};
+    use crate::vec::Vec3;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn aim_at() {
+        let gravity = Vec3(0.0, -10.0, 0.0);
+        let target = Vec3(5.0, 0.0, 0.0);
+        // Range is speed² * sin(2θ) / g, so sin(2θ) = 0.5 and θ = 15°.
+        let velocity = Particle::aim_at(Vec3(0.0, 0.0, 0.0), target, 10.0, gravity).unwrap();
+        let theta = 15.0_f64.to_radians();
+        assert_approx_eq!(velocity.0, 10.0 * theta.cos());
+        assert_approx_eq!(velocity.1, 10.0 * theta.sin());
+        assert_approx_eq!(velocity.2, 0.0);
+
+        let mut p = Particle {
+            velocity,
+            acceleration: gravity,
+            ..Particle::default()
+        };
+        let flight_time = 2.0 * velocity.1 / 10.0;
+        let steps = 10_000;
+        for _ in 0..steps {
+            p.integrate(flight_time / f64::from(steps));
+        }
+        assert_approx_eq!(p.position.0, 5.0, 1e-9);
+        assert_approx_eq!(p.position.1, 0.0, 1e-3);
+
+        let too_far = Vec3(10.1, 0.0, 0.0);
+        assert_eq!(
+            Particle::aim_at(Vec3(0.0, 0.0, 0.0), too_far, 10.0, gravity),
+            None
+        );
+    }
+
+    #[test]
+    fn aim_at_weightless() {
+        let zero = Vec3(0.0_f64, 0.0, 0.0);
+        let velocity = Particle::aim_at(zero, Vec3(0.0, 3.0, 4.0), 10.0, zero).unwrap();
+        assert_approx_eq!(velocity.1, 6.0);
+        assert_approx_eq!(velocity.2, 8.0);
+        assert_eq!(Particle::aim_at(zero, Vec3(0.0, 3.0, 4.0), 0.0, zero), None);
+    }
+
+    #[test]
+    fn mass() {
+        let mut p = Particle::<f64> {
+            inverse_mass: 0.5,
+            ..Particle::default()
+        };
+        assert!(p.has_finite_mass());
+        assert_eq!(p.mass(), 2.0);
+        p.inverse_mass = 0.0;
+        assert!(!p.has_finite_mass());
+        assert!(p.mass().is_infinite());
+    }
+
+    #[test]
+    fn apply_impulse() {
+        let mut p = Particle::<f64> {
+            velocity: Vec3(1.0, 0.0, 0.0),
+            inverse_mass: 0.5,
+            ..Particle::default()
+        };
+        p.apply_impulse(Vec3(2.0, 4.0, 0.0));
+        assert_eq!(p.velocity, Vec3(2.0, 2.0, 0.0));
+        p.inverse_mass = 0.0;
+        p.apply_impulse(Vec3(2.0, 4.0, 0.0));
+        assert_eq!(p.velocity, Vec3(2.0, 2.0, 0.0));
+    }
+
+    #[test]
+    fn half_life() {
+        let mut p = Particle {
+            velocity: Vec3(4.0_f64, 0.0, -2.0),
+            ..Particle::default()
+        };
+        p.set_linear_damping_per_second(Particle::drag_half_life(1.0));
+        for _ in 0..60 {
+            p.integrate(1.0 / 60.0);
+        }
+        assert_approx_eq!(p.velocity.0, 2.0);
+        assert_approx_eq!(p.velocity.2, -1.0);
+        for _ in 0..30 {
+            p.integrate(1.0 / 30.0);
+        }
+        assert_approx_eq!(p.velocity.0, 1.0);
+        assert_approx_eq!(Particle::drag_half_life(2.0_f64), 0.5_f64.sqrt());
+        assert_eq!(Particle::drag_half_life(f64::INFINITY), 1.0);
+    }
+
+    #[cfg(feature = "deterministic")]
+    #[test]
+    fn deterministic() {
+        let mut p = Particle {
+            velocity: Vec3(3.0_f64, 4.0, 12.0),
+            acceleration: Vec3(0.0, -9.8, 0.0),
+            damping: 0.25,
+            ..Particle::default()
+        };
+        for _ in 0..10 {
+            p.integrate(0.5);
+        }
+        let bits = |v: Vec3<f64>| [v.0.to_bits(), v.1.to_bits(), v.2.to_bits()];
+        // Recorded bit for bit. The damping works out to exactly 0.5 per step, so these don't
+        // depend on how accurately `pow` rounds, only on it being the same everywhere.
+        assert_eq!(
+            bits(p.position),
+            [
+                0x4007_fa00_0000_0000,
+                0xc02f_37a6_6666_6667,
+                0x4027_fa00_0000_0000
+            ]
+        );
+        assert_eq!(
+            bits(p.velocity),
+            [
+                0x3f68_0000_0000_0000,
+                0xc013_90b3_3333_3334,
+                0x3f88_0000_0000_0000
+            ]
+        );
+        assert_eq!(p.velocity.mag().to_bits(), 0x4013_90b7_1c28_58ce);
+        assert_eq!(
+            bits(p.velocity.norm()),
+            [
+                0x3f43_a068_3018_a82d,
+                0xbfef_fff9_9ae2_77de,
+                0x3f63_a068_3018_a82d
+            ]
+        );
+    }
+
+    #[test]
+    fn integrate_reporting() {
+        let mut p = Particle {
+            velocity: Vec3(1.5, -2.0, 0.25),
+            acceleration: Vec3(0.0, -9.8, 0.0),
+            damping: 0.9,
+            ..Particle::default()
+        };
+        for _ in 0..5 {
+            let before = p.position;
+            let displacement = p.integrate_reporting(0.1);
+            assert_eq!(displacement, p.position - before);
+        }
+        assert_ne!(p.position, Vec3(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn integrate_anisotropic() {
+        let mut p = Particle {
+            velocity: Vec3(3.0, -4.0, 1.0_f64),
+            ..Particle::default()
+        };
+        let mut q = p;
+        for _ in 0..10 {
+            p.integrate_anisotropic(0.1, Vec3(1.0, 0.0, 1.0));
+            q.integrate_anisotropic(0.1, Vec3(0.5, 0.5, 0.5));
+        }
+        assert_eq!(p.velocity, Vec3(3.0, 0.0, 1.0));
+        assert_approx_eq!(p.position.1, -0.4);
+        // The same damping on every axis is just `integrate`.
+        let mut r = Particle {
+            velocity: Vec3(3.0, -4.0, 1.0),
+            damping: 0.5,
+            ..Particle::default()
+        };
+        for _ in 0..10 {
+            r.integrate(0.1);
+        }
+        assert_eq!(q.velocity, r.velocity);
+        assert_eq!(q.position, r.position);
+    }
+
+    #[test]
+    fn all() {
+        let mut particles = vec![Particle::default(); 4];
+        particles[2].force_accum = Vec3(1.0, 0.0, 0.0);
+        add_force_all(&mut particles, Vec3(0.0, 2.0, -1.0));
+        assert_eq!(particles[0].force_accum, Vec3(0.0, 2.0, -1.0));
+        assert_eq!(particles[2].force_accum, Vec3(1.0, 2.0, -1.0));
+        assert_eq!(particles[3].force_accum, Vec3(0.0, 2.0, -1.0));
+        clear_accumulators_all(&mut particles);
+        assert!(particles
+            .iter()
+            .all(|p| p.force_accum == Vec3(0.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn integrate_force() {
+        let mut p = Particle {
+            inverse_mass: 0.5,
+            ..Particle::default()
+        };
+        p.add_force(Vec3(2.0, 0.0, 0.0));
+        p.add_force(Vec3(2.0, 0.0, 0.0));
+        p.integrate(1.0);
+        assert_eq!(p.velocity, Vec3(2.0, 0.0, 0.0));
+        assert_eq!(p.force_accum, Vec3(0.0, 0.0, 0.0));
+        p.integrate(1.0);
+        assert_eq!(p.position, Vec3(2.0, 0.0, 0.0));
+        assert_eq!(p.velocity, Vec3(2.0, 0.0, 0.0));
     }
 }
@@ -0,0 +1,259 @@
+//! Storage for particles that come and go in bursts, like sparks and debris.
+use crate::particle::Particle;
+use num::Float;
+use num_traits::NumAssign;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+/// Refers to a particle in a `ParticlePool`.
+///
+/// Each slot counts how many times it has been reused, so a handle to a despawned particle
+/// never refers to whatever is spawned into its slot afterward.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Handle {
+    index: usize,
+    generation: u32,
+}
+
+impl Handle {
+    /// The particle's index into `ParticlePool::particles`, for generators that take indices.
+    pub fn index(self) -> usize {
+        self.index
+    }
+}
+
+/// Particles in one contiguous `Vec`, whose despawned slots are reused by later spawns.
+///
+/// Spawning and despawning never allocate once the pool has grown to its busiest size, and
+/// particles never move, so a handle's index stays the same for as long as it's alive.
+#[derive(Debug, Clone)]
+pub struct ParticlePool<T: Float> {
+    particles: Vec<Particle<T>>,
+    generations: Vec<u32>,
+    active: Vec<bool>,
+    free: Vec<usize>,
+}
+
+impl<T: Float + NumAssign> Default for ParticlePool<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Float + NumAssign> ParticlePool<T> {
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            particles: Vec::with_capacity(capacity),
+            generations: Vec::with_capacity(capacity),
+            active: Vec::with_capacity(capacity),
+            free: Vec::new(),
+        }
+    }
+
+    /// Adds `particle`, in the most recently freed slot if there is one.
+    pub fn spawn(&mut self, particle: Particle<T>) -> Handle {
+        if let Some(index) = self.free.pop() {
+            self.particles[index] = particle;
+            self.active[index] = true;
+            Handle {
+                index,
+                generation: self.generations[index],
+            }
+        } else {
+            self.particles.push(particle);
+            self.generations.push(0);
+            self.active.push(true);
+            Handle {
+                index: self.particles.len() - 1,
+                generation: 0,
+            }
+        }
+    }
+
+    /// Frees the particle's slot, returning the particle as it was, or `None` if it's already
+    /// been despawned.
+    pub fn despawn(&mut self, handle: Handle) -> Option<Particle<T>> {
+        if !self.contains(handle) {
+            return None;
+        }
+        self.active[handle.index] = false;
+        self.generations[handle.index] = self.generations[handle.index].wrapping_add(1);
+        self.free.push(handle.index);
+        Some(self.particles[handle.index])
+    }
+
+    pub fn contains(&self, handle: Handle) -> bool {
+        self.is_active(handle.index) && self.generations[handle.index] == handle.generation
+    }
+
+    pub fn get(&self, handle: Handle) -> Option<&Particle<T>> {
+        if self.contains(handle) {
+            Some(&self.particles[handle.index])
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, handle: Handle) -> Option<&mut Particle<T>> {
+        if self.contains(handle) {
+            Some(&mut self.particles[handle.index])
+        } else {
+            None
+        }
+    }
+
+    /// How many particles are alive.
+    pub fn len(&self) -> usize {
+        self.particles.len() - self.free.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Every slot, alive or not, indexed by `Handle::index`.
+    ///
+    /// Despawned slots hold whatever particle was last in them; generators given this slice
+    /// should only be told about the indices of live handles.
+    pub fn particles(&self) -> &[Particle<T>] {
+        &self.particles
+    }
+
+    pub fn particles_mut(&mut self) -> &mut [Particle<T>] {
+        &mut self.particles
+    }
+
+    /// Visits the live particles in slot order.
+    pub fn iter_active(&self) -> impl Iterator<Item = (Handle, &Particle<T>)> {
+        let generations = &self.generations;
+        self.particles
+            .iter()
+            .zip(&self.active)
+            .enumerate()
+            .filter(|(_, (_, &active))| active)
+            .map(move |(index, (particle, _))| {
+                let generation = generations[index];
+                (Handle { index, generation }, particle)
+            })
+    }
+
+    pub fn iter_active_mut(&mut self) -> impl Iterator<Item = (Handle, &mut Particle<T>)> {
+        let generations = &self.generations;
+        self.particles
+            .iter_mut()
+            .zip(&self.active)
+            .enumerate()
+            .filter(|(_, (_, &active))| active)
+            .map(move |(index, (particle, _))| {
+                let generation = generations[index];
+                (Handle { index, generation }, particle)
+            })
+    }
+
+    /// Whether the slot at `index` holds a live particle.
+    pub(crate) fn is_active(&self, index: usize) -> bool {
+        self.active.get(index) == Some(&true)
+    }
+
+    /// The handle of the live particle at `index`, if there is one.
+    pub(crate) fn handle(&self, index: usize) -> Option<Handle> {
+        if self.is_active(index) {
+            Some(Handle {
+                index,
+                generation: self.generations[index],
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Integrates each live particle.
+    #[cfg(not(feature = "rayon"))]
+    pub fn integrate(&mut self, duration: T) {
+        for (_, particle) in self.iter_active_mut() {
+            particle.integrate(duration);
+        }
+    }
+
+    /// Despawns every particle for which `keep` returns false.
+    pub fn retain(&mut self, mut keep: impl FnMut(Handle, &Particle<T>) -> bool) {
+        for index in 0..self.particles.len() {
+            let handle = Handle {
+                index,
+                generation: self.generations[index],
+            };
+            if self.active[index] && !keep(handle, &self.particles[index]) {
+                self.despawn(handle);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<T: Float + NumAssign + Send + Sync> ParticlePool<T> {
+    /// Integrates each live particle, spreading the particles over threads.
+    pub fn integrate(&mut self, duration: T) {
+        self.particles
+            .par_iter_mut()
+            .zip(self.active.par_iter())
+            .filter(|(_, &active)| active)
+            .for_each(|(particle, _)| particle.integrate(duration));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::particle::Particle;
+    use crate::pool::ParticlePool;
+    use crate::vec::Vec3;
+
+    fn at(x: f64) -> Particle<f64> {
+        Particle {
+            position: Vec3(x, 0.0, 0.0),
+            ..Particle::default()
+        }
+    }
+
+    #[test]
+    fn reuses_slots() {
+        let mut pool = ParticlePool::new();
+        let handles: Vec<_> = (0..4).map(|x| pool.spawn(at(x as f64))).collect();
+        assert_eq!(pool.despawn(handles[1]), Some(at(1.0)));
+        assert_eq!(pool.despawn(handles[1]), None);
+        assert_eq!(pool.despawn(handles[2]), Some(at(2.0)));
+        assert_eq!(pool.len(), 2);
+        let reused = [pool.spawn(at(5.0)), pool.spawn(at(6.0))];
+        let mut indices: Vec<_> = reused.iter().map(|handle| handle.index()).collect();
+        indices.sort();
+        assert_eq!(indices, vec![1, 2]);
+        assert_eq!(pool.particles().len(), 4);
+        // The old handles don't see the new particles in their slots.
+        assert_eq!(pool.get(handles[1]), None);
+        assert_eq!(pool.get(reused[0]), Some(&at(5.0)));
+        assert_eq!(pool.spawn(at(7.0)).index(), 4);
+    }
+
+    #[test]
+    fn iter_active() {
+        let mut pool = ParticlePool::new();
+        let handles: Vec<_> = (0..5).map(|x| pool.spawn(at(x as f64))).collect();
+        pool.despawn(handles[0]);
+        pool.despawn(handles[3]);
+        let active: Vec<_> = pool.iter_active().map(|(handle, _)| handle).collect();
+        assert_eq!(active, vec![handles[1], handles[2], handles[4]]);
+        for (_, particle) in pool.iter_active_mut() {
+            particle.velocity = Vec3(1.0, 0.0, 0.0);
+        }
+        pool.integrate(1.0);
+        assert_eq!(pool.particles()[0].position.0, 0.0);
+        assert_eq!(pool.particles()[3].position.0, 3.0);
+        assert!(pool.get(handles[4]).unwrap().position.0 > 4.0);
+        pool.retain(|_, particle| particle.position.0 < 3.5);
+        assert_eq!(pool.len(), 2);
+        assert!(!pool.contains(handles[4]));
+    }
+}
@@ -0,0 +1,719 @@
+use crate::math;
+use crate::vec::Vec3;
+use num::Float;
+use num_traits::NumAssign;
+use std::ops::{Mul, MulAssign};
+
+/// An orientation, as a unit quaternion `r + i𝐢 + j𝐣 + k𝐤`.
+///
+/// Orientations treat +Z as forward, +Y as up, and +X as right, in a right-handed space.
+///
+/// `q` and `-q` are the same rotation, but they aren't equal: `PartialEq` compares components,
+/// as if they were any four numbers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quaternion<T: Float> {
+    pub r: T,
+    pub i: T,
+    pub j: T,
+    pub k: T,
+}
+
+impl<T: Float + NumAssign> Quaternion<T> {
+    pub fn new(r: T, i: T, j: T, k: T) -> Self {
+        Self { r, i, j, k }
+    }
+
+    /// No rotation at all.
+    pub fn identity() -> Self {
+        Self::new(T::one(), T::zero(), T::zero(), T::zero())
+    }
+
+    /// The rotation by `angle` radians about `axis`, counterclockwise looking back along it
+    /// (the right-hand rule). The axis needn't be unit length; a zero axis gives the identity.
+    pub fn from_axis_angle(axis: Vec3<T>, angle: T) -> Self {
+        let axis = axis.norm();
+        if axis.mag_squared().is_zero() {
+            return Self::identity();
+        }
+        let (sin, cos) = math::sin_cos(angle / (T::one() + T::one()));
+        Self::new(cos, axis.0 * sin, axis.1 * sin, axis.2 * sin)
+    }
+
+    /// The unit axis and angle, between zero and π radians, that `from_axis_angle` would make
+    /// this rotation from. `self` is normalized first.
+    ///
+    /// A rotation by more than π about an axis is the same as one by less than π about the
+    /// opposite axis, which is the one returned. No rotation at all has no axis, so the identity
+    /// gives +X and zero. The angle is found with `atan2` rather than from `r` alone, so it stays
+    /// accurate near π, where `acos` would lose most of its precision.
+    pub fn to_axis_angle(self) -> (Vec3<T>, T) {
+        let q = self.normalize();
+        // `q` and `-q` are the same rotation; this one has `r >= 0`.
+        let q = if q.r < T::zero() {
+            Self::new(-q.r, -q.i, -q.j, -q.k)
+        } else {
+            q
+        };
+        let vector = Vec3(q.i, q.j, q.k);
+        let sin = vector.mag();
+        if sin.is_zero() {
+            return (Vec3::axis(0), T::zero());
+        }
+        let two = T::one() + T::one();
+        (vector / sin, two * math::atan2(sin, q.r))
+    }
+
+    /// The orientation turned by `yaw` about +Y, `pitch` about +X, and `roll` about +Z, each in
+    /// radians by the right-hand rule, as `from_axis_angle` turns.
+    ///
+    /// The turns are about the body's own axes, in that order: yaw first, then pitch about the
+    /// already-yawed right, then roll about the resulting forward. So a positive yaw turns
+    /// forward toward +X, a positive pitch tips it down toward -Y, and a positive roll lowers
+    /// the right side.
+    pub fn from_euler(yaw: T, pitch: T, roll: T) -> Self {
+        let (zero, one) = (T::zero(), T::one());
+        Self::from_axis_angle(Vec3(zero, one, zero), yaw)
+            * Self::from_axis_angle(Vec3(one, zero, zero), pitch)
+            * Self::from_axis_angle(Vec3(zero, zero, one), roll)
+    }
+
+    /// The yaw, pitch, and roll that `from_euler` would make this orientation from. `self`
+    /// should be unit length.
+    ///
+    /// Yaw and roll are between -π and π, and pitch between -π/2 and π/2. Facing straight up or
+    /// down (gimbal lock), yaw and roll turn about the same axis and only their sum matters; by
+    /// convention the roll is then zero and the yaw takes the whole turn.
+    pub fn to_euler(self) -> (T, T, T) {
+        let (x, y, z) = self.to_columns();
+        // How far forward is from vertical, the cosine of the pitch.
+        let level = math::hypot(x.1, y.1);
+        let pitch = math::atan2(-z.1, level);
+        if level <= math::sqrt(T::epsilon()) {
+            return (math::atan2(-x.2, x.0), pitch, T::zero());
+        }
+        (math::atan2(z.0, z.2), pitch, math::atan2(x.1, y.1))
+    }
+
+    /// `v` rotated by `self`, which must be a unit quaternion; debug builds check that it's close.
+    ///
+    /// This is the expansion of `self * v * self.conjugate()` for a unit quaternion, which takes
+    /// two cross products rather than two quaternion products. The identity leaves `v` exactly
+    /// as it was.
+    pub fn rotate(self, v: Vec3<T>) -> Vec3<T> {
+        debug_assert!(
+            (self.mag_squared() - T::one()).abs() < T::from(1e-3).unwrap(),
+            "rotating by a quaternion that isn't unit length"
+        );
+        let vector = Vec3(self.i, self.j, self.k);
+        let two = T::one() + T::one();
+        v + vector.cross(vector.cross(v) + v * self.r) * two
+    }
+
+    /// Turns the orientation by `angular_velocity` (in radians per second about its direction)
+    /// over `duration`, as Cyclone's `addScaledVector` does: `q += (ω q) * duration / 2`.
+    ///
+    /// This is a first-order step, which leaves `self` slightly longer than unit length; it
+    /// isn't a valid orientation again until it's normalized. Normalize after each step (or
+    /// every few), or use `integrated`, which does.
+    pub fn add_scaled_vector(&mut self, angular_velocity: Vec3<T>, duration: T) {
+        let zero = T::zero();
+        let w = angular_velocity * duration;
+        let spin = Self::new(zero, w.0, w.1, w.2) * *self;
+        let half = T::one() / (T::one() + T::one());
+        self.r += spin.r * half;
+        self.i += spin.i * half;
+        self.j += spin.j * half;
+        self.k += spin.k * half;
+    }
+
+    /// The orientation after turning by `angular_velocity` over `duration`, with
+    /// `add_scaled_vector`, normalized.
+    pub fn integrated(mut self, angular_velocity: Vec3<T>, duration: T) -> Self {
+        self.add_scaled_vector(angular_velocity, duration);
+        self.normalize()
+    }
+
+    /// `self.mag_squared()` will avoid a `sqrt` call that `self.mag().powi(2)` would make.
+    pub fn mag_squared(self) -> T {
+        self.dot(self)
+    }
+
+    pub fn mag(self) -> T {
+        math::sqrt(self.mag_squared())
+    }
+
+    /// Scales `self` to unit length, as an orientation must be, undoing the drift that builds up
+    /// as rotations are composed. A quaternion too near zero to have a direction becomes the
+    /// identity.
+    pub fn normalize(self) -> Self {
+        let mag = self.mag();
+        if mag <= T::epsilon() {
+            return Self::identity();
+        }
+        Self::new(self.r / mag, self.i / mag, self.j / mag, self.k / mag)
+    }
+
+    /// The opposite rotation, for a unit quaternion; cheaper than `inverse`.
+    pub fn conjugate(self) -> Self {
+        Self::new(self.r, -self.i, -self.j, -self.k)
+    }
+
+    /// The quaternion that multiplies with `self` to give the identity. Zero has no inverse,
+    /// so it gives the identity too.
+    pub fn inverse(self) -> Self {
+        let mag_squared = self.mag_squared();
+        if mag_squared.is_zero() {
+            return Self::identity();
+        }
+        let q = self.conjugate();
+        Self::new(
+            q.r / mag_squared,
+            q.i / mag_squared,
+            q.j / mag_squared,
+            q.k / mag_squared,
+        )
+    }
+
+    fn dot(self, other: Self) -> T {
+        self.r * other.r + self.i * other.i + self.j * other.j + self.k * other.k
+    }
+
+    /// The orientation that faces `forward`, with its up as close to `up` as it can be.
+    ///
+    /// When `forward` and `up` are parallel, no single orientation is closest, so +Z (or +X,
+    /// if that's parallel too) is used for up instead. A zero `forward` gives the identity.
+    pub fn look_rotation(forward: Vec3<T>, up: Vec3<T>) -> Self {
+        let (zero, one) = (T::zero(), T::one());
+        let forward = forward.norm();
+        let basis = forward
+            .basis(up)
+            .or_else(|| forward.basis(Vec3(zero, zero, one)))
+            .or_else(|| forward.basis(Vec3(one, zero, zero)));
+        let (forward, up, left) = match basis {
+            Some(basis) => basis,
+            None => return Self::identity(),
+        };
+        Self::from_columns(left.invert(), up, forward)
+    }
+
+    /// Roughly the average of the weighted orientations, for blending between a few of them.
+    ///
+    /// A quaternion and its negative are the same orientation, so each is first flipped, if need
+    /// be, to the same side as the first; then the weighted sum is normalized. This is close to
+    /// the true average when the orientations are within a right angle or so of one another,
+    /// and drifts from it as they spread further apart. With no weight at all, or weights that
+    /// cancel out, it's the identity.
+    pub fn weighted_average(quaternions: &[(Self, T)]) -> Self {
+        let zero = T::zero();
+        let reference = match quaternions.first() {
+            Some(&(reference, _)) => reference,
+            None => return Self::identity(),
+        };
+        let mut sum = Self::new(zero, zero, zero, zero);
+        for &(q, weight) in quaternions {
+            let weight = if reference.dot(q) < zero {
+                -weight
+            } else {
+                weight
+            };
+            sum.r += q.r * weight;
+            sum.i += q.i * weight;
+            sum.j += q.j * weight;
+            sum.k += q.k * weight;
+        }
+        sum.normalize()
+    }
+
+    /// The orientation a fraction `t` of the way from `self` to `other`, turning at a steady
+    /// rate along the shortest path between them.
+    ///
+    /// Orientations less than a hair apart are blended linearly instead, since the angle between
+    /// them is too small to divide by. Either way the result is normalized.
+    pub fn slerp(self, other: Self, t: T) -> Self {
+        let one = T::one();
+        let mut cos = self.dot(other);
+        let other = if cos < T::zero() {
+            cos = -cos;
+            Self::new(-other.r, -other.i, -other.j, -other.k)
+        } else {
+            other
+        };
+        let sin = math::sqrt((one - cos * cos).max(T::zero()));
+        let (from, to) = if sin <= math::sqrt(T::epsilon()) {
+            (one - t, t)
+        } else {
+            let angle = math::atan2(sin, cos);
+            let (after, _) = math::sin_cos((one - t) * angle);
+            let (before, _) = math::sin_cos(t * angle);
+            (after / sin, before / sin)
+        };
+        Self::new(
+            self.r * from + other.r * to,
+            self.i * from + other.i * to,
+            self.j * from + other.j * to,
+            self.k * from + other.k * to,
+        )
+        .normalize()
+    }
+
+    /// Where the rotation takes the X, Y, and Z axes: the columns of its rotation matrix.
+    pub(crate) fn to_columns(self) -> (Vec3<T>, Vec3<T>, Vec3<T>) {
+        let one = T::one();
+        let two = one + one;
+        let Self { r, i, j, k } = self;
+        (
+            Vec3(
+                one - two * (j * j + k * k),
+                two * (i * j + k * r),
+                two * (i * k - j * r),
+            ),
+            Vec3(
+                two * (i * j - k * r),
+                one - two * (i * i + k * k),
+                two * (j * k + i * r),
+            ),
+            Vec3(
+                two * (i * k + j * r),
+                two * (j * k - i * r),
+                one - two * (i * i + j * j),
+            ),
+        )
+    }
+
+    /// The rotation taking the X, Y, and Z axes to the orthonormal `x`, `y`, and `z`.
+    fn from_columns(x: Vec3<T>, y: Vec3<T>, z: Vec3<T>) -> Self {
+        let two = T::one() + T::one();
+        let quarter = T::one() / (two * two);
+        // Divide by whichever of the four components is largest, to stay accurate.
+        let trace = x.0 + y.1 + z.2;
+        if trace > T::zero() {
+            let s = math::sqrt(trace + T::one()) * two;
+            Self::new(
+                s * quarter,
+                (y.2 - z.1) / s,
+                (z.0 - x.2) / s,
+                (x.1 - y.0) / s,
+            )
+        } else if x.0 > y.1 && x.0 > z.2 {
+            let s = math::sqrt(T::one() + x.0 - y.1 - z.2) * two;
+            Self::new(
+                (y.2 - z.1) / s,
+                s * quarter,
+                (y.0 + x.1) / s,
+                (z.0 + x.2) / s,
+            )
+        } else if y.1 > z.2 {
+            let s = math::sqrt(T::one() + y.1 - x.0 - z.2) * two;
+            Self::new(
+                (z.0 - x.2) / s,
+                (y.0 + x.1) / s,
+                s * quarter,
+                (z.1 + y.2) / s,
+            )
+        } else {
+            let s = math::sqrt(T::one() + z.2 - x.0 - y.1) * two;
+            Self::new(
+                (x.1 - y.0) / s,
+                (z.0 + x.2) / s,
+                (z.1 + y.2) / s,
+                s * quarter,
+            )
+        }
+    }
+}
+
+impl<T: Float> Mul for Quaternion<T> {
+    type Output = Self;
+
+    /// Hamilton Product: the rotation by `other`, then by `self`.
+    /// ```
+    /// # use cyclone::quaternion::Quaternion;
+    /// let i = Quaternion::new(0.0, 1.0, 0.0, 0.0);
+    /// let j = Quaternion::new(0.0, 0.0, 1.0, 0.0);
+    /// assert_eq!(i * j, Quaternion::new(0.0, 0.0, 0.0, 1.0));
+    /// ```
+    fn mul(self, other: Self) -> Self {
+        let (a, b) = (self, other);
+        Self {
+            r: a.r * b.r - a.i * b.i - a.j * b.j - a.k * b.k,
+            i: a.r * b.i + a.i * b.r + a.j * b.k - a.k * b.j,
+            j: a.r * b.j - a.i * b.k + a.j * b.r + a.k * b.i,
+            k: a.r * b.k + a.i * b.j - a.j * b.i + a.k * b.r,
+        }
+    }
+}
+
+impl<T: Float + NumAssign> MulAssign for Quaternion<T> {
+    fn mul_assign(&mut self, other: Self) {
+        *self = *self * other;
+    }
+}
+
+impl<T: Float + NumAssign> Mul<Vec3<T>> for Quaternion<T> {
+    type Output = Vec3<T>;
+
+    /// Rotation, as with `rotate`.
+    fn mul(self, v: Vec3<T>) -> Vec3<T> {
+        self.rotate(v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::quaternion::Quaternion;
+    use crate::vec::Vec3;
+    use assert_approx_eq::assert_approx_eq;
+
+    fn assert_vec_eq(a: Vec3<f64>, b: Vec3<f64>) {
+        assert_approx_eq!(a.0, b.0, 1e-9);
+        assert_approx_eq!(a.1, b.1, 1e-9);
+        assert_approx_eq!(a.2, b.2, 1e-9);
+    }
+
+    #[test]
+    fn look_rotation() {
+        let z = Vec3(0.0, 0.0, 1.0);
+        let y = Vec3(0.0, 1.0, 0.0);
+        assert_eq!(Quaternion::look_rotation(z, y), Quaternion::identity());
+        let forwards = [
+            Vec3(1.0, 0.0, 0.0),
+            Vec3(-1.0, 0.0, 0.0),
+            Vec3(0.0, 0.0, -3.0),
+            Vec3(1.0, 2.0, -0.5),
+            Vec3(-0.2, -1.0, 0.1),
+        ];
+        for &forward in &forwards {
+            let q: Quaternion<f64> = Quaternion::look_rotation(forward, y);
+            let length = (q.r * q.r + q.i * q.i + q.j * q.j + q.k * q.k).sqrt();
+            assert_approx_eq!(length, 1.0);
+            assert_vec_eq(q.rotate(z), forward.norm());
+            // Up stays as upright as facing `forward` allows.
+            let up = q.rotate(y);
+            assert_approx_eq!(up.dot(forward), 0.0);
+            assert!(up.1 > 0.0);
+            assert_approx_eq!(up.cross(y).dot(forward), 0.0);
+        }
+    }
+
+    #[test]
+    fn look_rotation_degenerate() {
+        let up = Vec3(0.0, 1.0, 0.0);
+        for &forward in &[up, up.invert()] {
+            let q = Quaternion::look_rotation(forward, up);
+            assert_vec_eq(q.rotate(Vec3(0.0, 0.0, 1.0)), forward);
+        }
+        let z = Vec3(0.0, 0.0, 1.0);
+        assert_vec_eq(Quaternion::look_rotation(z, z).rotate(z), z);
+        assert_eq!(
+            Quaternion::look_rotation(Vec3(0.0, 0.0, 0.0), up),
+            Quaternion::identity()
+        );
+    }
+
+    /// A rotation by `angle` about +Y.
+    fn yaw(angle: f64) -> Quaternion<f64> {
+        let (sin, cos) = (angle / 2.0).sin_cos();
+        Quaternion::new(cos, 0.0, sin, 0.0)
+    }
+
+    #[test]
+    fn weighted_average() {
+        let between = Quaternion::weighted_average(&[(yaw(0.2), 1.0), (yaw(0.4), 1.0)]);
+        let expected = yaw(0.3);
+        assert_approx_eq!(between.r, expected.r, 1e-9);
+        assert_approx_eq!(between.j, expected.j, 1e-9);
+        assert_approx_eq!(between.i, 0.0);
+        assert_approx_eq!(between.k, 0.0);
+        // Heavier weights pull the average towards them.
+        let pulled = Quaternion::weighted_average(&[(yaw(0.2), 3.0), (yaw(0.4), 1.0)]);
+        assert!(pulled.j < between.j && pulled.j > yaw(0.2).j);
+
+        let q = Quaternion::look_rotation(Vec3(1.0, 2.0, -0.5), Vec3(0.0, 1.0, 0.0));
+        let same = Quaternion::weighted_average(&[(q, 0.5), (q, 2.0), (q, 1.0)]);
+        assert_vec_eq(Vec3(same.i, same.j, same.k), Vec3(q.i, q.j, q.k));
+        assert_approx_eq!(same.r, q.r);
+        // The negative of an orientation is the same orientation, and doesn't cancel it out.
+        let negative = Quaternion::new(-q.r, -q.i, -q.j, -q.k);
+        let flipped = Quaternion::weighted_average(&[(q, 1.0), (negative, 1.0)]);
+        assert_approx_eq!(flipped.r, q.r);
+        assert_approx_eq!(flipped.k, q.k);
+        assert_eq!(
+            Quaternion::<f64>::weighted_average(&[]),
+            Quaternion::identity()
+        );
+        assert_eq!(
+            Quaternion::weighted_average(&[(q, 0.0)]),
+            Quaternion::identity()
+        );
+    }
+
+    fn assert_quaternion_eq(a: Quaternion<f64>, b: Quaternion<f64>) {
+        assert_approx_eq!(a.r, b.r, 1e-9);
+        assert_approx_eq!(a.i, b.i, 1e-9);
+        assert_approx_eq!(a.j, b.j, 1e-9);
+        assert_approx_eq!(a.k, b.k, 1e-9);
+    }
+
+    #[test]
+    fn slerp() {
+        use std::f64::consts::{FRAC_PI_2, FRAC_PI_4};
+        let (a, b) = (yaw(0.0), yaw(FRAC_PI_2));
+        assert_quaternion_eq(a.slerp(b, 0.0), a);
+        assert_quaternion_eq(a.slerp(b, 1.0), b);
+        assert_quaternion_eq(a.slerp(b, 0.5), yaw(FRAC_PI_4));
+        assert_quaternion_eq(a.slerp(b, 0.25), yaw(FRAC_PI_4 / 2.0));
+        // -b is the same orientation, but the long way round from a; slerp goes the short way.
+        let negative = Quaternion::new(-b.r, -b.i, -b.j, -b.k);
+        assert_quaternion_eq(a.slerp(negative, 0.5), yaw(FRAC_PI_4));
+        // Turning from yaw(-3) to yaw(3) crosses ±π rather than passing through zero.
+        let across = yaw(-3.0).slerp(yaw(3.0), 0.5);
+        assert_approx_eq!(across.r.abs(), 0.0, 1e-9);
+        assert_approx_eq!(across.j.abs(), 1.0, 1e-9);
+        let nearly = yaw(1e-10);
+        let between = a.slerp(nearly, 0.5);
+        assert!(!between.r.is_nan() && !between.j.is_nan());
+        assert_quaternion_eq(between, yaw(5e-11));
+        assert_eq!(a.slerp(a, 0.3), a);
+    }
+
+    #[test]
+    fn mul() {
+        let one = Quaternion::identity();
+        let i = Quaternion::new(0.0, 1.0, 0.0, 0.0);
+        let j = Quaternion::new(0.0, 0.0, 1.0, 0.0);
+        let k = Quaternion::new(0.0, 0.0, 0.0, 1.0);
+        let minus_one = Quaternion::new(-1.0, 0.0, 0.0, 0.0);
+        for &q in &[i, j, k] {
+            assert_eq!(q * q, minus_one);
+            assert_eq!(q * one, q);
+            assert_eq!(one * q, q);
+        }
+        assert_eq!(i * j, k);
+        assert_eq!(j * k, i);
+        assert_eq!(k * i, j);
+        assert_eq!(j * i, Quaternion::new(0.0, 0.0, 0.0, -1.0));
+        let (a, b) = (
+            Quaternion::new(1.0, 2.0, 3.0, 4.0),
+            Quaternion::new(5.0, 6.0, 7.0, 8.0),
+        );
+        assert_eq!(a * b, Quaternion::new(-60.0, 12.0, 30.0, 24.0));
+        assert_eq!(b * a, Quaternion::new(-60.0, 20.0, 14.0, 32.0));
+        let mut c = a;
+        c *= b;
+        assert_eq!(c, a * b);
+        // Composing rotates by the right-hand one first.
+        let (turn, tilt) = (
+            yaw(0.7),
+            Quaternion::new(0.4_f64.cos(), 0.4_f64.sin(), 0.0, 0.0),
+        );
+        let v = Vec3(1.0, -2.0, 0.5);
+        assert_vec_eq((turn * tilt).rotate(v), turn.rotate(tilt.rotate(v)));
+    }
+
+    #[test]
+    fn normalize() {
+        let q = Quaternion::new(1.0_f64, 1.0, -1.0, 1.0);
+        assert_eq!(q.mag_squared(), 4.0);
+        assert_eq!(q.mag(), 2.0);
+        assert_eq!(q.normalize(), Quaternion::new(0.5, 0.5, -0.5, 0.5));
+        let drifted = Quaternion::new(0.3_f64, 1.2, -0.4, 2.5).normalize();
+        assert_approx_eq!(drifted.mag(), 1.0);
+        assert_approx_eq!(drifted.i / drifted.r, 4.0);
+        assert_eq!(
+            Quaternion::new(0.0, 0.0, 0.0, 0.0).normalize(),
+            Quaternion::identity()
+        );
+        assert_eq!(
+            Quaternion::new(1e-20, 0.0, -1e-20, 0.0).normalize(),
+            Quaternion::identity()
+        );
+    }
+
+    #[test]
+    fn inverse() {
+        let one = Quaternion::identity();
+        let q = Quaternion::new(0.3, 1.2, -0.4, 2.5);
+        assert_quaternion_eq(q * q.inverse(), one);
+        assert_quaternion_eq(q.inverse() * q, one);
+        let unit = q.normalize();
+        assert_eq!(
+            unit.conjugate(),
+            Quaternion::new(unit.r, -unit.i, -unit.j, -unit.k)
+        );
+        assert_quaternion_eq(unit * unit.conjugate(), one);
+        assert_quaternion_eq(unit.conjugate(), unit.inverse());
+        let v = Vec3(1.0, -2.0, 0.5);
+        assert_vec_eq(unit.conjugate().rotate(unit.rotate(v)), v);
+        assert_eq!(Quaternion::new(0.0, 0.0, 0.0, 0.0).inverse(), one);
+    }
+
+    #[test]
+    fn axis_angle() {
+        use std::f64::consts::PI;
+        let axes = [
+            Vec3(1.0, 0.0, 0.0),
+            Vec3(0.0, 0.0, -1.0),
+            Vec3(1.0, 1.0, 0.0),
+            Vec3(-0.3, 2.0, 0.7),
+            Vec3(0.2, -0.1, -5.0),
+        ];
+        for &axis in &axes {
+            let axis = axis.norm();
+            for step in 1..=20 {
+                let angle = PI * f64::from(step) / 20.0;
+                let q = Quaternion::from_axis_angle(axis * 3.0, angle);
+                assert_approx_eq!(q.mag(), 1.0);
+                // Matches rotating by the angle in the plane across the axis.
+                let across = Vec3(0.1, 0.2, 0.3).project_on_plane(axis).norm();
+                let turned = q.rotate(across);
+                assert_approx_eq!(turned.dot(across), angle.cos());
+                assert_approx_eq!(axis.cross(across).dot(turned), angle.sin());
+                let (round_axis, round_angle) = q.to_axis_angle();
+                assert_approx_eq!(round_angle, angle, 1e-9);
+                assert_vec_eq(round_axis, axis);
+                // Past π, the same rotation comes back about the opposite axis. (At exactly π,
+                // either axis will do.)
+                if step < 20 {
+                    let (flipped, remainder) =
+                        Quaternion::from_axis_angle(axis, 2.0 * PI - angle).to_axis_angle();
+                    assert_approx_eq!(remainder, angle, 1e-9);
+                    assert_vec_eq(flipped, axis.invert());
+                }
+            }
+            // Near π, where `r` is tiny.
+            let (near_axis, near) = Quaternion::from_axis_angle(axis, PI - 1e-9).to_axis_angle();
+            assert_approx_eq!(near, PI - 1e-9, 1e-14);
+            assert_vec_eq(near_axis, axis);
+        }
+        assert_eq!(
+            Quaternion::from_axis_angle(Vec3(0.0, 0.0, 0.0), 1.0),
+            Quaternion::identity()
+        );
+        assert_eq!(
+            Quaternion::<f64>::identity().to_axis_angle(),
+            (Vec3(1.0, 0.0, 0.0), 0.0)
+        );
+        let (_, angle) = Quaternion::from_axis_angle(Vec3(0.0, 1.0, 0.0), 0.0_f64).to_axis_angle();
+        assert_eq!(angle, 0.0);
+    }
+
+    #[test]
+    fn euler() {
+        use std::f64::consts::{FRAC_PI_2, PI};
+        let (x, y, z) = (
+            Vec3(1.0, 0.0, 0.0),
+            Vec3(0.0, 1.0, 0.0),
+            Vec3(0.0, 0.0, 1.0),
+        );
+        assert_eq!(
+            Quaternion::from_euler(0.0, 0.0, 0.0),
+            Quaternion::identity()
+        );
+        assert_quaternion_eq(Quaternion::from_euler(0.7, 0.0, 0.0), yaw(0.7));
+        let turned = Quaternion::from_euler(FRAC_PI_2, FRAC_PI_2 / 3.0, 0.0);
+        // Yawed to face +X, then tipped down toward -Y; the pitch is about the yawed right.
+        assert_vec_eq(turned.rotate(z), Vec3(3.0_f64.sqrt() / 2.0, -0.5, 0.0));
+        assert_vec_eq(turned.rotate(x), Vec3(0.0, 0.0, -1.0));
+        let rolled = Quaternion::from_euler(0.0, 0.0, FRAC_PI_2);
+        assert_vec_eq(rolled.rotate(x), y);
+
+        for &yaw in &[-3.0_f64, -1.5, -0.2, 0.0, 0.4, 2.0, 3.1] {
+            for &pitch in &[-1.5, -0.7, 0.0, 0.3, 1.2] {
+                for &roll in &[-2.5, -0.1, 0.0, 1.0, 3.0] {
+                    let q = Quaternion::from_euler(yaw, pitch, roll);
+                    let (a, b, c) = q.to_euler();
+                    assert_approx_eq!(a, yaw, 1e-9);
+                    assert_approx_eq!(b, pitch, 1e-9);
+                    assert_approx_eq!(c, roll, 1e-9);
+                }
+            }
+        }
+
+        // At the poles only yaw minus (or plus) roll survives, and roll comes back as zero.
+        for &pitch in &[FRAC_PI_2, -FRAC_PI_2] {
+            for &(yaw, roll) in &[(0.3, 0.5), (-1.0, 2.0), (PI / 3.0, 0.0)] {
+                let q = Quaternion::from_euler(yaw, pitch, roll);
+                let (a, b, c) = q.to_euler();
+                assert_approx_eq!(b, pitch, 1e-6);
+                assert_eq!(c, 0.0);
+                let back = Quaternion::from_euler(a, b, c);
+                // Either sign is the same orientation.
+                let back = if back.r * q.r + back.j * q.j < 0.0 {
+                    Quaternion::new(-back.r, -back.i, -back.j, -back.k)
+                } else {
+                    back
+                };
+                assert_quaternion_eq(back, q);
+            }
+        }
+    }
+
+    #[test]
+    fn rotate() {
+        use std::f64::consts::FRAC_PI_2;
+        let (x, y, z) = (
+            Vec3(1.0, 0.0, 0.0),
+            Vec3(0.0, 1.0, 0.0),
+            Vec3(0.0, 0.0, 1.0),
+        );
+        // A quarter turn about each axis takes the next axis round to the one after.
+        for &(axis, from, to) in &[(x, y, z), (y, z, x), (z, x, y)] {
+            let q = Quaternion::from_axis_angle(axis, FRAC_PI_2);
+            assert_vec_eq(q.rotate(from), to);
+            assert_vec_eq(q.rotate(to), from.invert());
+            assert_vec_eq(q.rotate(axis), axis);
+            assert_eq!(q * from, q.rotate(from));
+        }
+        let q1 = Quaternion::from_axis_angle(Vec3(1.0, 2.0, -0.5), 0.8);
+        let q2 = Quaternion::from_axis_angle(Vec3(-3.0, 0.2, 1.0), 2.1);
+        let v = Vec3(0.3, -1.7, 2.2);
+        assert_vec_eq((q1 * q2).rotate(v), q1.rotate(q2.rotate(v)));
+        assert_approx_eq!(q1.rotate(v).mag(), v.mag());
+        for &v in &[v, Vec3(-1e10, 1e-10, 3.0), Vec3(0.1, 0.2, 0.3)] {
+            let same = Quaternion::identity().rotate(v);
+            assert_eq!(same.0.to_bits(), v.0.to_bits());
+            assert_eq!(same.1.to_bits(), v.1.to_bits());
+            assert_eq!(same.2.to_bits(), v.2.to_bits());
+        }
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "rotating by a quaternion that isn't unit length")]
+    fn rotate_not_unit() {
+        Quaternion::new(1.0, 1.0, 0.0, 0.0).rotate(Vec3(1.0_f64, 0.0, 0.0));
+    }
+
+    #[test]
+    fn integrated() {
+        use std::f64::consts::FRAC_PI_2;
+        let z = Vec3(0.0, 0.0, 1.0);
+        // A quarter turn about Z over a second, in a thousand steps.
+        let spin = z * FRAC_PI_2;
+        let start = Quaternion::from_axis_angle(Vec3(1.0, 1.0, 0.0), 0.3);
+        let mut q = start;
+        for _ in 0..1000 {
+            q = q.integrated(spin, 0.001);
+        }
+        // Each step is first-order, so this is only close.
+        let expected = Quaternion::from_axis_angle(z, FRAC_PI_2) * start;
+        for &(a, b) in &[
+            (q.r, expected.r),
+            (q.i, expected.i),
+            (q.j, expected.j),
+            (q.k, expected.k),
+        ] {
+            assert_approx_eq!(a, b, 1e-6);
+        }
+        assert_approx_eq!(q.mag(), 1.0, 1e-12);
+        // A single step is only normalized when asked for.
+        let mut stepped = start;
+        stepped.add_scaled_vector(spin, 0.1);
+        assert!(stepped.mag() > 1.0);
+        assert_eq!(stepped.normalize(), start.integrated(spin, 0.1));
+        let mut still = start;
+        still.add_scaled_vector(Vec3(0.0, 0.0, 0.0), 0.1);
+        assert_eq!(still, start);
+        assert_eq!(start.integrated(spin, 0.0), start.normalize());
+    }
+}
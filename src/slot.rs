@@ -0,0 +1,131 @@
+//! Storage that hands out keys which stay valid until their value is removed.
+
+/// Refers to a value in a `SlotMap`.
+///
+/// Each slot counts how many times it has been reused, so a key to a removed value never
+/// refers to whatever takes its slot afterward.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub(crate) struct Key {
+    index: usize,
+    generation: u32,
+}
+
+#[derive(Debug, Clone)]
+struct Slot<V> {
+    generation: u32,
+    value: Option<V>,
+}
+
+/// A `Vec` whose removed slots are reused, with O(1) insertion, lookup and removal by key.
+#[derive(Debug, Clone)]
+pub(crate) struct SlotMap<V> {
+    slots: Vec<Slot<V>>,
+    free: Vec<usize>,
+    len: usize,
+}
+
+impl<V> Default for SlotMap<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V> SlotMap<V> {
+    pub(crate) fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            free: Vec::new(),
+            len: 0,
+        }
+    }
+
+    pub(crate) fn insert(&mut self, value: V) -> Key {
+        self.len += 1;
+        if let Some(index) = self.free.pop() {
+            let slot = &mut self.slots[index];
+            slot.value = Some(value);
+            Key {
+                index,
+                generation: slot.generation,
+            }
+        } else {
+            self.slots.push(Slot {
+                generation: 0,
+                value: Some(value),
+            });
+            Key {
+                index: self.slots.len() - 1,
+                generation: 0,
+            }
+        }
+    }
+
+    pub(crate) fn get(&self, key: Key) -> Option<&V> {
+        self.slots
+            .get(key.index)
+            .filter(|slot| slot.generation == key.generation)
+            .and_then(|slot| slot.value.as_ref())
+    }
+
+    pub(crate) fn get_mut(&mut self, key: Key) -> Option<&mut V> {
+        self.slots
+            .get_mut(key.index)
+            .filter(|slot| slot.generation == key.generation)
+            .and_then(|slot| slot.value.as_mut())
+    }
+
+    pub(crate) fn remove(&mut self, key: Key) -> Option<V> {
+        let slot = self.slots.get_mut(key.index)?;
+        if slot.generation != key.generation {
+            return None;
+        }
+        let value = slot.value.take()?;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(key.index);
+        self.len -= 1;
+        Some(value)
+    }
+
+    pub(crate) fn clear(&mut self) {
+        let keys: Vec<Key> = self.iter_mut().map(|(key, _)| key).collect();
+        for key in keys {
+            self.remove(key);
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Visits values in slot order.
+    pub(crate) fn iter_mut(&mut self) -> impl Iterator<Item = (Key, &mut V)> {
+        self.slots
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(index, slot)| {
+                let generation = slot.generation;
+                slot.value
+                    .as_mut()
+                    .map(|value| (Key { index, generation }, value))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::slot::SlotMap;
+
+    #[test]
+    fn stale_keys() {
+        let mut map = SlotMap::new();
+        let a = map.insert('a');
+        let b = map.insert('b');
+        assert_eq!(map.remove(a), Some('a'));
+        assert_eq!(map.remove(a), None);
+        let c = map.insert('c');
+        assert_eq!(map.get(a), None);
+        assert_eq!(map.get(c), Some(&'c'));
+        assert_eq!(map.get(b), Some(&'b'));
+        assert_eq!(map.len(), 2);
+    }
+}
@@ -0,0 +1,243 @@
+//! Broad phases, which quickly rule out pairs of objects too far apart to touch.
+use crate::vec::Vec3;
+use num::Float;
+use num_traits::NumAssign;
+use std::collections::HashMap;
+
+type Cell = (i64, i64, i64);
+
+/// Buckets spheres by the cube-shaped cell their center falls in, so that only spheres in
+/// nearby cells need checking against each other.
+///
+/// Cells about twice the largest radius across work best: any two touching spheres are then in
+/// the same or adjacent cells. Smaller cells are still correct, since the neighborhood searched
+/// grows to cover the largest radius, but much smaller cells search many empty ones. Much
+/// larger cells hold too many spheres each.
+///
+/// Spheres whose centers aren't finite, or are too far out to number their cell, are left out,
+/// so a particle that has blown up finds no neighbors rather than stopping the simulation.
+#[derive(Debug, Clone)]
+pub struct SpatialHash<T: Float> {
+    /// The width of each cell. Changes take effect on the next `rebuild`.
+    pub cell_size: T,
+    cells: HashMap<Cell, Vec<usize>>,
+    /// The cell each sphere's center is in, if any.
+    homes: Vec<Option<Cell>>,
+    /// How many cells away along each axis a sphere's neighbors can be.
+    reach: i64,
+}
+
+impl<T: Float + NumAssign> SpatialHash<T> {
+    pub fn new(cell_size: T) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+            homes: Vec::new(),
+            reach: 1,
+        }
+    }
+
+    /// Replaces the contents with spheres centered at `positions` with matching `radii`. The
+    /// spheres are known by their index in these slices.
+    pub fn rebuild(&mut self, positions: &[Vec3<T>], radii: &[T]) {
+        self.cells.clear();
+        self.homes.clear();
+        for (index, &position) in positions.iter().enumerate() {
+            let cell = self.cell(position);
+            if let Some(cell) = cell {
+                self.cells.entry(cell).or_default().push(index);
+            }
+            self.homes.push(cell);
+        }
+        // `max` passes over NaN radii, and infinite ones would search forever.
+        let largest = radii
+            .iter()
+            .filter(|r| r.is_finite())
+            .fold(T::zero(), |largest, &r| largest.max(r));
+        let one = T::one();
+        self.reach = ((largest + largest) / self.cell_size)
+            .ceil()
+            .max(one)
+            .to_i64()
+            .unwrap_or(i64::MAX);
+    }
+
+    /// The spheres whose centers are in the cells a ball of `radius` around `center` reaches.
+    ///
+    /// Every sphere whose center is within `radius` of `center` is among them, along with some
+    /// a little further away. They come in the same order for the same query, but not sorted.
+    /// A `radius` many cells across visits every one of those cells, however empty. A ball
+    /// that isn't finite, or reaches too far out to number its cells, finds nothing.
+    pub fn candidates_near(&self, center: Vec3<T>, radius: T) -> impl Iterator<Item = usize> + '_ {
+        let reach = Vec3::splat(radius);
+        let bounds = self
+            .cell(center - reach)
+            .and_then(|low| Some((low, self.cell(center + reach)?)));
+        bounds
+            .into_iter()
+            .flat_map(|(low, high)| {
+                (low.0..=high.0).flat_map(move |x| {
+                    (low.1..=high.1).flat_map(move |y| (low.2..=high.2).map(move |z| (x, y, z)))
+                })
+            })
+            .flat_map(move |cell| self.cells.get(&cell).into_iter().flatten().copied())
+    }
+
+    /// The cell `position` is in, or `None` if it isn't finite or is too far out.
+    fn cell(&self, position: Vec3<T>) -> Option<Cell> {
+        let floor = (position / self.cell_size).floor();
+        Some((floor.0.to_i64()?, floor.1.to_i64()?, floor.2.to_i64()?))
+    }
+
+    /// Each pair of spheres in the same or nearby cells, once, as `(a, b)` with `a < b`.
+    ///
+    /// Every pair of touching spheres is among them, along with some that only nearly touch.
+    /// Pairs come in order of their first sphere, so the same spheres always give the same
+    /// pairs in the same order.
+    pub fn candidate_pairs(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let reach = self.reach;
+        self.homes
+            .iter()
+            .enumerate()
+            .filter_map(|(a, home)| Some((a, (*home)?)))
+            .flat_map(move |(a, (x, y, z))| {
+                let offsets = move || -reach..=reach;
+                offsets()
+                    .flat_map(move |dx| {
+                        offsets().flat_map(move |dy| offsets().map(move |dz| (dx, dy, dz)))
+                    })
+                    .flat_map(move |(dx, dy, dz)| {
+                        let cell = (
+                            x.saturating_add(dx),
+                            y.saturating_add(dy),
+                            z.saturating_add(dz),
+                        );
+                        let neighbors = self.cells.get(&cell);
+                        neighbors.into_iter().flatten().copied()
+                    })
+                    .filter(move |&b| b > a)
+                    .map(move |b| (a, b))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::spatial::SpatialHash;
+    use crate::vec::Vec3;
+    use std::collections::HashSet;
+
+    /// Deterministic numbers in `[0, 1)`.
+    fn uniform(seed: &mut u64) -> f64 {
+        *seed ^= *seed << 13;
+        *seed ^= *seed >> 7;
+        *seed ^= *seed << 17;
+        (*seed >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn touching(positions: &[Vec3<f64>], radii: &[f64]) -> HashSet<(usize, usize)> {
+        let mut pairs = HashSet::new();
+        for a in 0..positions.len() {
+            for b in a + 1..positions.len() {
+                if (positions[a] - positions[b]).mag() <= radii[a] + radii[b] {
+                    pairs.insert((a, b));
+                }
+            }
+        }
+        pairs
+    }
+
+    #[test]
+    fn finds_every_touching_pair() {
+        let mut seed = 0x2545_f491_4f6c_dd1d;
+        for &cell_size in &[1.0, 0.3, 4.0] {
+            for _ in 0..5 {
+                let mut random = || uniform(&mut seed);
+                let positions: Vec<_> = (0..300)
+                    .map(|_| Vec3(random(), random(), random()) * 6.0 - Vec3(3.0, 3.0, 3.0))
+                    .collect();
+                let radii: Vec<_> = (0..300).map(|_| 0.1 + 0.4 * random()).collect();
+                let mut hash = SpatialHash::new(cell_size);
+                hash.rebuild(&positions, &radii);
+                let pairs: Vec<_> = hash.candidate_pairs().collect();
+                let unique: HashSet<_> = pairs.iter().copied().collect();
+                assert_eq!(unique.len(), pairs.len());
+                assert!(pairs.iter().all(|&(a, b)| a < b));
+                let expected = touching(&positions, &radii);
+                assert!(!expected.is_empty());
+                assert!(expected.is_subset(&unique));
+                if cell_size == 1.0 {
+                    assert!(pairs.len() < 300 * 299 / 2 / 4);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn cell_boundaries() {
+        let positions = vec![
+            Vec3(1.0, 0.0, 0.0),
+            Vec3(0.9, 0.0, 0.0),
+            Vec3(-1.0, -1.0, -1.0),
+            Vec3(-1.0, -2.0, -1.0),
+            Vec3(2.0, 2.0, 2.0),
+            Vec3(2.0, 2.0, 1.0),
+        ];
+        let radii = vec![0.5; positions.len()];
+        let mut hash = SpatialHash::new(1.0);
+        hash.rebuild(&positions, &radii);
+        let pairs: HashSet<_> = hash.candidate_pairs().collect();
+        assert!(pairs.contains(&(0, 1)));
+        assert!(pairs.contains(&(2, 3)));
+        assert!(pairs.contains(&(4, 5)));
+        assert!(!pairs.contains(&(0, 4)));
+    }
+
+    #[test]
+    fn non_finite() {
+        let positions = vec![
+            Vec3(0.0, 0.0, 0.0),
+            Vec3(f64::NAN, 0.0, 0.0),
+            Vec3(0.5, 0.0, 0.0),
+            Vec3(0.0, f64::INFINITY, 0.0),
+            Vec3(0.0, 0.0, 1e300),
+        ];
+        let radii = vec![0.5, 0.5, f64::NAN, f64::INFINITY, 0.5];
+        let mut hash = SpatialHash::new(1.0);
+        hash.rebuild(&positions, &radii);
+        assert_eq!(hash.candidate_pairs().collect::<Vec<_>>(), vec![(0, 2)]);
+        let mut near: Vec<_> = hash.candidates_near(Vec3(0.0, 0.0, 0.0), 1.0).collect();
+        near.sort_unstable();
+        assert_eq!(near, vec![0, 2]);
+        assert_eq!(
+            hash.candidates_near(Vec3(f64::NAN, 0.0, 0.0), 1.0).count(),
+            0
+        );
+        assert_eq!(
+            hash.candidates_near(Vec3(0.0, 0.0, 0.0), f64::INFINITY)
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn candidates_near() {
+        let mut seed = 7;
+        let positions: Vec<_> = (0..200)
+            .map(|_| {
+                let mut coordinate = || uniform(&mut seed) * 10.0 - 5.0;
+                Vec3(coordinate(), coordinate(), coordinate())
+            })
+            .collect();
+        let mut hash = SpatialHash::new(1.5);
+        hash.rebuild(&positions, &vec![0.0; positions.len()]);
+        let center = Vec3(0.5, -1.0, 2.0);
+        let candidates: HashSet<_> = hash.candidates_near(center, 2.0).collect();
+        let near: Vec<_> = (0..positions.len())
+            .filter(|&i| (positions[i] - center).mag() <= 2.0)
+            .collect();
+        assert!(!near.is_empty());
+        assert!(near.iter().all(|i| candidates.contains(i)));
+        assert!(candidates.len() < positions.len() / 2);
+    }
+}
@@ -0,0 +1,77 @@
+use crate::vec::Vec3;
+use num::Float;
+use std::collections::VecDeque;
+
+/// The most recent positions of something moving, for drawing motion trails.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trail<T: Float> {
+    points: VecDeque<Vec3<T>>,
+    max_len: usize,
+}
+
+impl<T: Float> Trail<T> {
+    pub fn new(max_len: usize) -> Self {
+        Self {
+            points: VecDeque::with_capacity(max_len),
+            max_len,
+        }
+    }
+
+    /// Records a position, dropping the oldest once there are more than `max_len`.
+    pub fn push(&mut self, position: Vec3<T>) {
+        if self.max_len == 0 {
+            return;
+        }
+        if self.points.len() == self.max_len {
+            self.points.pop_back();
+        }
+        self.points.push_front(position);
+    }
+
+    /// Positions from newest to oldest.
+    pub fn iter(&self) -> impl Iterator<Item = &Vec3<T>> {
+        self.points.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    pub fn max_len(&self) -> usize {
+        self.max_len
+    }
+
+    pub fn clear(&mut self) {
+        self.points.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::trail::Trail;
+    use crate::vec::Vec3;
+
+    #[test]
+    fn evicts_oldest() {
+        let mut trail = Trail::new(3);
+        for i in 0..5 {
+            trail.push(Vec3(f64::from(i), 0.0, 0.0));
+        }
+        assert_eq!(trail.len(), 3);
+        let xs: Vec<f64> = trail.iter().map(|p| p.0).collect();
+        assert_eq!(xs, vec![4.0, 3.0, 2.0]);
+        trail.clear();
+        assert!(trail.is_empty());
+    }
+
+    #[test]
+    fn zero_length() {
+        let mut trail = Trail::new(0);
+        trail.push(Vec3(1.0, 2.0, 3.0));
+        assert!(trail.is_empty());
+    }
+}
@@ -0,0 +1,178 @@
+//! Sensors: regions that notice particles coming and going without pushing them around.
+use crate::collide::CollisionFilter;
+use crate::force::Region;
+use crate::pool::{Handle, ParticlePool};
+use num::Float;
+use num_traits::NumAssign;
+
+/// A region that reports which particles have entered it, stayed in it, and left it since its
+/// last `update`, without ever touching them.
+///
+/// A particle is inside when its position is, boundary included. Only particles whose
+/// `CollisionFilter` collides with `filter` are noticed. A particle that's removed while inside
+/// is reported as having left on the next update, like one that moved out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TriggerVolume<T: Float> {
+    pub region: Region<T>,
+    pub filter: CollisionFilter,
+    /// Sorted, so that each update can look up who was inside before.
+    occupants: Vec<Handle>,
+    entered: Vec<Handle>,
+    stayed: Vec<Handle>,
+    exited: Vec<Handle>,
+}
+
+impl<T: Float + NumAssign> TriggerVolume<T> {
+    pub fn new(region: Region<T>) -> Self {
+        Self {
+            region,
+            filter: CollisionFilter::default(),
+            occupants: Vec::new(),
+            entered: Vec::new(),
+            stayed: Vec::new(),
+            exited: Vec::new(),
+        }
+    }
+
+    pub fn with_filter(mut self, filter: CollisionFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Finds who's inside now, comparing with who was inside at the last update.
+    ///
+    /// `filters` are the particles' collision filters, indexed like the pool's slots; a particle
+    /// past its end has the default filter.
+    pub fn update(&mut self, particles: &ParticlePool<T>, filters: &[CollisionFilter]) {
+        let (region, filter) = (self.region, self.filter);
+        let inside = particles
+            .iter_active()
+            .filter(|&(handle, particle)| {
+                filter.collides(CollisionFilter::at(filters, handle.index()))
+                    && region.contains(particle.position)
+            })
+            .map(|(handle, _)| handle);
+        let previous = std::mem::take(&mut self.occupants);
+        self.occupants.extend(inside);
+        self.occupants.sort();
+        self.entered.clear();
+        self.stayed.clear();
+        for &handle in &self.occupants {
+            if previous.binary_search(&handle).is_ok() {
+                self.stayed.push(handle);
+            } else {
+                self.entered.push(handle);
+            }
+        }
+        self.exited.clear();
+        for &handle in &previous {
+            if self.occupants.binary_search(&handle).is_err() {
+                self.exited.push(handle);
+            }
+        }
+    }
+
+    /// The particles inside as of the last update.
+    pub fn occupants(&self) -> &[Handle] {
+        &self.occupants
+    }
+
+    /// The particles inside at the last update that weren't at the one before.
+    pub fn entered(&self) -> &[Handle] {
+        &self.entered
+    }
+
+    /// The particles inside at both of the last two updates.
+    pub fn stayed(&self) -> &[Handle] {
+        &self.stayed
+    }
+
+    /// The particles inside at the update before the last one, but not at the last.
+    pub fn exited(&self) -> &[Handle] {
+        &self.exited
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bounds::{Aabb, BoundingSphere};
+    use crate::force::Region;
+    use crate::particle::Particle;
+    use crate::trigger::TriggerVolume;
+    use crate::vec::Vec3;
+    use crate::world::ParticleWorld;
+
+    fn moving(x: f64, speed: f64) -> Particle<f64> {
+        Particle {
+            position: Vec3(x, 0.0, 0.0),
+            velocity: Vec3(speed, 0.0, 0.0),
+            ..Particle::default()
+        }
+    }
+
+    #[test]
+    fn fly_through() {
+        for &region in &[
+            Region::Aabb(Aabb::new(Vec3(-1.0, -1.0, -1.0), Vec3(1.0, 1.0, 1.0))),
+            Region::Sphere(BoundingSphere::new(Vec3(0.0, 0.0, 0.0), 1.0)),
+        ] {
+            let mut world = ParticleWorld::new(4);
+            let trigger = world.add_trigger(TriggerVolume::new(region));
+            let particle = world.add_particle(moving(-3.0, 10.0));
+            let (mut entered, mut stayed, mut exited) = (0, 0, 0);
+            for _ in 0..60 {
+                world.start_frame();
+                world.run_physics(0.01);
+                let volume = world.trigger(trigger).unwrap();
+                entered += volume.entered().len();
+                stayed += volume.stayed().len();
+                exited += volume.exited().len();
+            }
+            assert_eq!((entered, exited), (1, 1));
+            assert!(stayed > 0);
+            // Triggers don't push back.
+            assert_eq!(
+                world.particle(particle).unwrap().velocity,
+                Vec3(10.0, 0.0, 0.0)
+            );
+        }
+    }
+
+    #[test]
+    fn spawned_inside() {
+        let mut world = ParticleWorld::new(4);
+        let region = Region::Sphere(BoundingSphere::new(Vec3(0.0, 0.0, 0.0), 1.0));
+        let trigger = world.add_trigger(TriggerVolume::new(region));
+        let particle = world.add_particle(moving(0.0, 0.0));
+        world.start_frame();
+        world.run_physics(0.01);
+        assert_eq!(world.trigger(trigger).unwrap().entered(), &[particle]);
+        world.start_frame();
+        world.run_physics(0.01);
+        let volume = world.trigger(trigger).unwrap();
+        assert!(volume.entered().is_empty());
+        assert_eq!(volume.stayed(), &[particle]);
+    }
+
+    #[test]
+    fn removed_inside() {
+        let mut world = ParticleWorld::new(4);
+        let region = Region::Aabb(Aabb::new(Vec3(-1.0, -1.0, -1.0), Vec3(1.0, 1.0, 1.0)));
+        let trigger = world.add_trigger(TriggerVolume::new(region));
+        let particle = world.add_particle(moving(0.0, 0.0));
+        let other = world.add_particle(moving(0.5, 0.0));
+        world.start_frame();
+        world.run_physics(0.01);
+        world.remove_particle(particle);
+        world.start_frame();
+        world.run_physics(0.01);
+        let volume = world.trigger(trigger).unwrap();
+        assert_eq!(volume.exited(), &[particle]);
+        assert_eq!(volume.occupants(), &[other]);
+        world.start_frame();
+        world.run_physics(0.01);
+        assert!(world.trigger(trigger).unwrap().exited().is_empty());
+        assert!(world.remove_trigger(trigger).is_some());
+        assert!(world.trigger(trigger).is_none());
+    }
+}
@@ -1,5 +1,8 @@
-use num::Float;
+use crate::math;
+use num::{clamp, Float};
 use num_traits::NumAssign;
+use std::hash::{Hash, Hasher};
+use std::iter::once;
 use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Sub, SubAssign};
 
 // We may want to derive an Eq implementation for Vec3,
@@ -15,7 +18,7 @@ impl<T: Float + NumAssign> Vec3<T> {
     }
 
     pub fn mag(self) -> T {
-        (self.0.powi(2) + self.1.powi(2) + self.2.powi(2)).sqrt()
+        math::sqrt(self.0.powi(2) + self.1.powi(2) + self.2.powi(2))
     }
 
     /// `vec.mag_squared()` will avoid a `sqrt` call that `vec.mag().powi(2)` would make.
@@ -32,6 +35,17 @@ impl<T: Float + NumAssign> Vec3<T> {
         }
     }
 
+    /// The unit vector and the length, together; finding both separately would take the length
+    /// twice. A zero vector gives zero for both.
+    pub fn normalize_and_length(self) -> (Self, T) {
+        let mag = self.mag();
+        if mag.is_zero() {
+            (self, mag)
+        } else {
+            (self / mag, mag)
+        }
+    }
+
     pub fn dot(self, other: Self) -> T {
         self.0 * other.0 + self.1 * other.1 + self.2 * other.2
     }
@@ -44,6 +58,29 @@ impl<T: Float + NumAssign> Vec3<T> {
         )
     }
 
+    /// `(self.dot(other), self.cross(other))`, reading each component only once.
+    pub fn dot_cross(self, other: Self) -> (T, Self) {
+        let Self(ax, ay, az) = self;
+        let Self(bx, by, bz) = other;
+        (
+            ax * bx + ay * by + az * bz,
+            Self(ay * bz - az * by, az * bx - ax * bz, ax * by - ay * bx),
+        )
+    }
+
+    /// The magnitude of the cross product, `|a||b|sinθ`, which is the area of the
+    /// parallelogram spanned by the two vectors.
+    /// ```
+    /// # use cyclone::vec::Vec3;
+    /// let a = Vec3(2.0, 0.0, 0.0);
+    /// let b = Vec3(3.0, 3.0, 0.0);
+    /// assert_eq!(a.cross_mag(b), 6.0);
+    /// ```
+    // Finding sinθ would cost more than the cross product itself, so we don't go that route.
+    pub fn cross_mag(self, other: Self) -> T {
+        self.cross(other).mag()
+    }
+
     pub fn basis(self, other: Self) -> Option<(Self, Self, Self)> {
         let a = self;
         let b = other;
@@ -54,6 +91,291 @@ impl<T: Float + NumAssign> Vec3<T> {
             Some((a, c.cross(a), c))
         }
     }
+
+    /// Compares each component with a tolerance relative to the larger of the two magnitudes.
+    ///
+    /// Components that are both near zero are compared with an absolute tolerance of
+    /// `T::epsilon()` instead, since no relative tolerance can call `0.0` close to anything.
+    pub fn approx_eq_relative(self, other: Self, rel_tol: T) -> bool {
+        let close = |a: T, b: T| {
+            let tolerance = (rel_tol * a.abs().max(b.abs())).max(T::epsilon());
+            (a - b).abs() <= tolerance
+        };
+        close(self.0, other.0) && close(self.1, other.1) && close(self.2, other.2)
+    }
+
+    /// Clamps each component to `[0, 1]`.
+    /// ```
+    /// # use cyclone::vec::Vec3;
+    /// assert_eq!(Vec3(-0.5, 0.5, 1.5).saturate(), Vec3(0.0, 0.5, 1.0));
+    /// ```
+    pub fn saturate(self) -> Self {
+        let saturate = |c| clamp(c, T::zero(), T::one());
+        Self(saturate(self.0), saturate(self.1), saturate(self.2))
+    }
+
+    /// Rounds each component down.
+    /// ```
+    /// # use cyclone::vec::Vec3;
+    /// assert_eq!(Vec3(-0.5, 0.5, 2.0).floor(), Vec3(-1.0, 0.0, 2.0));
+    /// ```
+    pub fn floor(self) -> Self {
+        Self(self.0.floor(), self.1.floor(), self.2.floor())
+    }
+
+    /// The weights `(u, v, w)` for which `a * u + b * v + c * w` is the point nearest `self` in
+    /// the plane of the triangle `abc`. They sum to one, and are all between zero and one when
+    /// that point is inside the triangle.
+    ///
+    /// A triangle with no area has no single set of weights, so `(1, 0, 0)` is returned for it.
+    /// ```
+    /// # use cyclone::vec::Vec3;
+    /// let (a, b, c) = (Vec3(0.0, 0.0, 0.0), Vec3(2.0, 0.0, 0.0), Vec3(0.0, 2.0, 0.0));
+    /// assert_eq!(Vec3(0.5, 1.0, 3.0).barycentric(a, b, c), (0.25, 0.25, 0.5));
+    /// ```
+    pub fn barycentric(self, a: Self, b: Self, c: Self) -> (T, T, T) {
+        let (ab, ac, ap) = (b - a, c - a, self - a);
+        let (d00, d01, d11) = (ab.dot(ab), ab.dot(ac), ac.dot(ac));
+        let (d20, d21) = (ap.dot(ab), ap.dot(ac));
+        let denominator = d00 * d11 - d01 * d01;
+        if denominator.is_zero() {
+            return (T::one(), T::zero(), T::zero());
+        }
+        let v = (d11 * d20 - d01 * d21) / denominator;
+        let w = (d00 * d21 - d01 * d20) / denominator;
+        (T::one() - v - w, v, w)
+    }
+
+    /// The point a fraction `t` of the way from `self` to `other`.
+    pub fn lerp(self, other: Self, t: T) -> Self {
+        self + (other - self) * t
+    }
+
+    /// Blends two directions by interpolating linearly, then normalizing the result.
+    ///
+    /// Cheaper than a spherical interpolation, and close enough for small angles. When the
+    /// interpolated vector is (nearly) zero, as halfway between opposite directions, there is no
+    /// direction to normalize, so `self` is returned unchanged.
+    pub fn nlerp(self, other: Self, t: T) -> Self {
+        let lerp = self.lerp(other, t);
+        if lerp.mag_squared() <= T::epsilon() {
+            self
+        } else {
+            lerp.norm()
+        }
+    }
+
+    /// Turns `self` toward `other` at a steady rate, while its length changes linearly from
+    /// one magnitude to the other.
+    ///
+    /// Steering a velocity this way keeps its speed from dipping partway through the turn, as
+    /// `lerp` would. Vectors that (nearly) point the same way or opposite ways, or that are zero,
+    /// have no single plane to turn in, so they're blended with `lerp` instead.
+    pub fn slerp(self, other: Self, t: T) -> Self {
+        let (from, to) = (self.mag(), other.mag());
+        if from.is_zero() || to.is_zero() {
+            return self.lerp(other, t);
+        }
+        let (a, b) = (self / from, other / to);
+        let (cos, sin) = (a.dot(b), a.cross_mag(b));
+        if sin <= math::sqrt(T::epsilon()) {
+            return self.lerp(other, t);
+        }
+        let angle = math::atan2(sin, cos);
+        let (after, _) = math::sin_cos((T::one() - t) * angle);
+        let (before, _) = math::sin_cos(t * angle);
+        (a * after + b * before) * ((from + (to - from) * t) / sin)
+    }
+
+    /// The part of `self` lying in the plane with the unit normal `normal`, with the part along
+    /// the normal removed.
+    ///
+    /// Removing the normal part of a velocity leaves a particle sliding along a surface:
+    /// ```
+    /// # use cyclone::vec::Vec3;
+    /// let velocity = Vec3(3.0, -4.0, 1.0);
+    /// let slide = velocity.project_on_plane(Vec3(0.0, 1.0, 0.0));
+    /// assert_eq!(slide, Vec3(3.0, 0.0, 1.0));
+    /// ```
+    pub fn project_on_plane(self, normal: Self) -> Self {
+        self - normal * self.dot(normal)
+    }
+
+    /// `self` bounced off a surface with the unit normal `normal`, as a mirror reflects light.
+    ///
+    /// The part along the normal is reversed and the part in the plane is left unchanged.
+    pub fn reflect(self, normal: Self) -> Self {
+        let two = T::one() + T::one();
+        self - normal * (two * self.dot(normal))
+    }
+
+    /// The unit direction `self` bent by Snell's law as it passes through a surface with the
+    /// unit normal `normal`, which faces back against `self`.
+    ///
+    /// `eta` is the ratio of the refractive indices, the side `self` comes from over the side
+    /// it's entering. Going into a denser medium (`eta` less than one) bends toward the normal.
+    /// Coming out of one at too shallow an angle, nothing gets through: that's total internal
+    /// reflection, and gives `None`.
+    pub fn refract(self, normal: Self, eta: T) -> Option<Self> {
+        let cos = -self.dot(normal);
+        let k = T::one() - eta * eta * (T::one() - cos * cos);
+        if k < T::zero() {
+            None
+        } else {
+            Some(self * eta + normal * (eta * cos - math::sqrt(k)))
+        }
+    }
+
+    /// A vector with every component `v`, as for a uniform scale.
+    /// ```
+    /// # use cyclone::vec::Vec3;
+    /// let scale = Vec3::splat(2.0);
+    /// assert_eq!(Vec3(1.0, -2.0, 0.5) * scale, Vec3(2.0, -4.0, 1.0));
+    /// ```
+    pub fn splat(v: T) -> Self {
+        Self(v, v, v)
+    }
+
+    /// The unit vector along axis `i`: +X for 0, +Y for 1, and +Z for 2.
+    /// ```
+    /// # use cyclone::vec::Vec3;
+    /// let v = Vec3(4.0, 5.0, 6.0);
+    /// let components: Vec<f64> = (0..3).map(|i| v.dot(Vec3::axis(i))).collect();
+    /// assert_eq!(components, vec![4.0, 5.0, 6.0]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If `i` is more than 2.
+    pub fn axis(i: usize) -> Self {
+        let (zero, one) = (T::zero(), T::one());
+        match i {
+            0 => Self(one, zero, zero),
+            1 => Self(zero, one, zero),
+            2 => Self(zero, zero, one),
+            _ => panic!("axis {} is out of range for a Vec3", i),
+        }
+    }
+
+    /// A vector in the XZ plane (with `y` zero). An `angle` of zero points along +X, and the
+    /// angle increases toward +Z.
+    pub fn from_polar_xz(radius: T, angle: T) -> Self {
+        let (sin, cos) = math::sin_cos(angle);
+        Self(radius * cos, T::zero(), radius * sin)
+    }
+
+    /// `(radius, angle)` of the vector's projection onto the XZ plane, ignoring `y`.
+    /// The angle is in `[-π, π]`, following the convention of `from_polar_xz`.
+    pub fn polar_xz(self) -> (T, T) {
+        (math::hypot(self.0, self.2), math::atan2(self.2, self.0))
+    }
+
+    /// The components in order: `.0`, `.1`, then `.2`.
+    /// ```
+    /// # use cyclone::vec::Vec3;
+    /// assert!(Vec3(1.0, 2.0, 3.0).iter().all(f64::is_finite));
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = T> {
+        once(self.0).chain(once(self.1)).chain(once(self.2))
+    }
+
+    pub fn max_component(self) -> T {
+        self.0.max(self.1).max(self.2)
+    }
+
+    pub fn min_component(self) -> T {
+        self.0.min(self.1).min(self.2)
+    }
+
+    /// The index (0, 1, or 2) of the largest component. Ties go to the lowest index.
+    pub fn max_axis(self) -> usize {
+        if self.1 > self.0 {
+            if self.2 > self.1 {
+                2
+            } else {
+                1
+            }
+        } else if self.2 > self.0 {
+            2
+        } else {
+            0
+        }
+    }
+
+    /// The index (0, 1, or 2) of the smallest component. Ties go to the lowest index.
+    pub fn min_axis(self) -> usize {
+        self.invert().max_axis()
+    }
+
+    /// Returns a copy with the x component replaced.
+    pub fn with_x(self, x: T) -> Self {
+        Self(x, self.1, self.2)
+    }
+
+    /// Returns a copy with the y component replaced.
+    ///
+    /// Handy for flattening a velocity onto the ground plane:
+    /// ```
+    /// # use cyclone::vec::Vec3;
+    /// let v = Vec3(3.0, -9.8, 4.0);
+    /// assert_eq!(v.with_y(0.0), Vec3(3.0, 0.0, 4.0));
+    /// ```
+    pub fn with_y(self, y: T) -> Self {
+        Self(self.0, y, self.2)
+    }
+
+    /// Returns a copy with the z component replaced.
+    pub fn with_z(self, z: T) -> Self {
+        Self(self.0, self.1, z)
+    }
+
+    /// Reorders the components, taking the new x from component `i`, y from `j`, and z from
+    /// `k`. Any may repeat.
+    /// ```
+    /// # use cyclone::vec::Vec3;
+    /// assert_eq!(Vec3(1.0, 2.0, 3.0).swizzle(2, 0, 0), Vec3(3.0, 1.0, 1.0));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If any index is more than 2.
+    pub fn swizzle(self, i: usize, j: usize, k: usize) -> Self {
+        Self(self.component(i), self.component(j), self.component(k))
+    }
+
+    fn component(self, i: usize) -> T {
+        match i {
+            0 => self.0,
+            1 => self.1,
+            2 => self.2,
+            _ => panic!("component {} is out of range for a Vec3", i),
+        }
+    }
+
+    pub fn xyz(self) -> Self {
+        self
+    }
+
+    /// Swaps y and z, as between Y-up and Z-up spaces (which also flips handedness).
+    pub fn xzy(self) -> Self {
+        Self(self.0, self.2, self.1)
+    }
+
+    pub fn yxz(self) -> Self {
+        Self(self.1, self.0, self.2)
+    }
+
+    pub fn yzx(self) -> Self {
+        Self(self.1, self.2, self.0)
+    }
+
+    pub fn zxy(self) -> Self {
+        Self(self.2, self.0, self.1)
+    }
+
+    pub fn zyx(self) -> Self {
+        Self(self.2, self.1, self.0)
+    }
 }
 
 impl<T: Float> Add for Vec3<T> {
@@ -143,6 +465,48 @@ impl<T: Float + NumAssign> DivAssign<T> for Vec3<T> {
     }
 }
 
+/// A `Vec3` compared and hashed by the exact bits of its components, for use as a map key.
+///
+/// Two keys are equal only if their components have identical bit patterns. That makes every
+/// NaN equal to itself (but not to NaNs with other payloads), and it makes `0.0` and `-0.0`
+/// different keys even though they compare equal as floats.
+#[derive(Debug, Clone, Copy)]
+pub struct OrderedVec3<T: Float>(pub Vec3<T>);
+
+impl<T: Float> From<Vec3<T>> for OrderedVec3<T> {
+    fn from(v: Vec3<T>) -> Self {
+        Self(v)
+    }
+}
+
+macro_rules! impl_ordered_vec3 {
+    ($t:ty, $bits:ty) => {
+        impl OrderedVec3<$t> {
+            fn bits(&self) -> [$bits; 3] {
+                let Vec3(x, y, z) = self.0;
+                [x.to_bits(), y.to_bits(), z.to_bits()]
+            }
+        }
+
+        impl PartialEq for OrderedVec3<$t> {
+            fn eq(&self, other: &Self) -> bool {
+                self.bits() == other.bits()
+            }
+        }
+
+        impl Eq for OrderedVec3<$t> {}
+
+        impl Hash for OrderedVec3<$t> {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                self.bits().hash(state);
+            }
+        }
+    };
+}
+
+impl_ordered_vec3!(f32, u32);
+impl_ordered_vec3!(f64, u64);
+
 #[cfg(test)]
 mod tests {
     use crate::vec::Vec3;
@@ -177,6 +541,19 @@ mod tests {
         assert_eq!(Vec3(0.0, 0.0, 0.0).norm(), Vec3(0.0, 0.0, 0.0));
     }
 
+    #[test]
+    fn normalize_and_length() {
+        for &v in &[
+            Vec3(3.0_f64, 1.0, 1.0),
+            Vec3(0.0, -2.0, 0.0),
+            Vec3(1e-3, 4e2, -7.5),
+        ] {
+            assert_eq!(v.normalize_and_length(), (v.norm(), v.mag()));
+        }
+        let zero = Vec3(0.0, 0.0, 0.0);
+        assert_eq!(zero.normalize_and_length(), (zero, 0.0));
+    }
+
     #[test]
     fn dot() {
         let a = Vec3(1.0, 2.0, 3.0);
@@ -191,6 +568,23 @@ mod tests {
         assert_eq!(a.cross(b), Vec3(-3.0, 6.0, -3.0));
     }
 
+    #[test]
+    fn dot_cross() {
+        let a = Vec3(2.0, 3.0, 4.0);
+        let b = Vec3(5.0, -6.0, 7.0);
+        assert_eq!(a.dot_cross(b), (a.dot(b), a.cross(b)));
+    }
+
+    #[test]
+    fn cross_mag() {
+        let a = Vec3(2.0_f64, 3.0, 4.0);
+        let b = Vec3(5.0, -6.0, 7.0);
+        assert_approx_eq!(a.cross_mag(b), a.cross(b).mag());
+        let theta = (a.dot(b) / (a.mag() * b.mag())).acos();
+        assert_approx_eq!(a.cross_mag(b), a.mag() * b.mag() * theta.sin(), 1e-9);
+        assert_eq!(a.cross_mag(a * 2.0), 0.0);
+    }
+
     #[test]
     fn basis() {
         let a = Vec3(1.0, 0.0, 0.0);
@@ -202,6 +596,165 @@ mod tests {
         assert_eq!(a.basis(a), None);
     }
 
+    #[test]
+    fn approx_eq_relative() {
+        let a = Vec3(1.0e9, -2.0e9, 3.0e9);
+        assert!(a.approx_eq_relative(a + Vec3(1.0, 1.0, 1.0), 1e-6));
+        assert!(!a.approx_eq_relative(a + Vec3(0.0, 1.0e4, 0.0), 1e-6));
+        let b = Vec3(1.0e-12, -2.0e-12, 0.0);
+        assert!(b.approx_eq_relative(b * 1.000_000_1, 1e-6));
+        assert!(!b.approx_eq_relative(b * 2.0, 1e-6));
+        assert!(Vec3(0.0, 0.0, 0.0).approx_eq_relative(Vec3(1e-17, 0.0, -1e-17), 1e-6));
+    }
+
+    #[test]
+    fn polar_xz() {
+        let v = Vec3::from_polar_xz(2.0_f64, std::f64::consts::FRAC_PI_2);
+        assert_approx_eq!(v.0, 0.0);
+        assert_eq!(v.1, 0.0);
+        assert_approx_eq!(v.2, 2.0);
+        assert_eq!(Vec3(3.0, 7.0, 0.0).polar_xz(), (3.0, 0.0));
+        let (radius, angle) = Vec3(-1.0, 5.0, -1.0).polar_xz();
+        assert_approx_eq!(radius, 2.0_f64.sqrt());
+        assert_approx_eq!(angle, -0.75 * std::f64::consts::PI);
+        let back = Vec3::from_polar_xz(radius, angle);
+        assert_approx_eq!(back.0, -1.0);
+        assert_approx_eq!(back.2, -1.0);
+    }
+
+    #[test]
+    fn axis() {
+        let axes: Vec<Vec3<f64>> = (0..3).map(Vec3::axis).collect();
+        assert_eq!(axes[0].cross(axes[1]), axes[2]);
+        assert_eq!(
+            axes.iter().fold(Vec3::splat(0.0), |sum, &a| sum + a),
+            Vec3::splat(1.0)
+        );
+    }
+
+    #[test]
+    fn swizzle() {
+        let v = Vec3(1.0, 2.0, 3.0);
+        assert_eq!(v.xzy(), Vec3(1.0, 3.0, 2.0));
+        assert_eq!(v.zyx(), Vec3(3.0, 2.0, 1.0));
+        assert_eq!(v.yzx().zxy(), v);
+        let permutations = [
+            (v.xyz(), (0, 1, 2)),
+            (v.xzy(), (0, 2, 1)),
+            (v.yxz(), (1, 0, 2)),
+            (v.yzx(), (1, 2, 0)),
+            (v.zxy(), (2, 0, 1)),
+            (v.zyx(), (2, 1, 0)),
+        ];
+        for &(swizzled, (i, j, k)) in &permutations {
+            assert_eq!(v.swizzle(i, j, k), swizzled);
+        }
+        assert_eq!(v.swizzle(1, 1, 1), Vec3::splat(2.0));
+    }
+
+    #[test]
+    #[should_panic(expected = "component 3 is out of range for a Vec3")]
+    fn swizzle_out_of_range() {
+        Vec3(1.0, 2.0, 3.0).swizzle(0, 3, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "axis 3 is out of range for a Vec3")]
+    fn axis_out_of_range() {
+        Vec3::<f64>::axis(3);
+    }
+
+    #[test]
+    fn nlerp() {
+        let a = Vec3(1.0, 0.0, 0.0);
+        let b = Vec3(0.0, 0.0, 1.0);
+        assert_eq!(a.nlerp(b, 0.0), a);
+        assert_eq!(a.nlerp(b, 1.0), b);
+        for i in 0..=10 {
+            let t = f64::from(i) / 10.0;
+            assert_approx_eq!(a.nlerp(b, t).mag(), 1.0);
+            assert_approx_eq!(a.nlerp(Vec3(-0.6, 0.8, 0.0), t).mag(), 1.0);
+        }
+        let halfway = a.nlerp(b, 0.5);
+        assert_approx_eq!(halfway.0, halfway.2);
+        assert_eq!(a.nlerp(a.invert(), 0.5), a);
+    }
+
+    #[test]
+    fn barycentric() {
+        let (a, b, c) = (
+            Vec3(1.0_f64, 0.0, -1.0),
+            Vec3(3.0, 1.0, 0.0),
+            Vec3(-1.0, 2.0, 4.0),
+        );
+        assert_eq!(a.barycentric(a, b, c), (1.0, 0.0, 0.0));
+        assert_eq!(b.barycentric(a, b, c), (0.0, 1.0, 0.0));
+        assert_eq!(c.barycentric(a, b, c), (0.0, 0.0, 1.0));
+        let (u, v, w) = ((a + b + c) / 3.0).barycentric(a, b, c);
+        assert_approx_eq!(u, 1.0 / 3.0);
+        assert_approx_eq!(v, 1.0 / 3.0);
+        assert_approx_eq!(w, 1.0 / 3.0);
+        // Points off the plane get the weights of the nearest point on it.
+        let normal = (b - a).cross(c - a).norm();
+        let p = a * 0.2 + b * 0.5 + c * 0.3;
+        let (u, v, w) = (p + normal * 2.0).barycentric(a, b, c);
+        assert_approx_eq!(u, 0.2);
+        assert_approx_eq!(v, 0.5);
+        assert_approx_eq!(w, 0.3);
+        // Outside the triangle, some weight is negative.
+        assert!((b + (b - a)).barycentric(a, b, c).0 < 0.0);
+        assert_eq!(p.barycentric(a, b, a + (b - a) * 2.0), (1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn slerp() {
+        let a: Vec3<f64> = Vec3(2.0, 0.0, 0.0);
+        let b = Vec3(0.0, 4.0, 0.0);
+        assert_eq!(a.slerp(b, 0.0), a);
+        assert!(a.slerp(b, 1.0).approx_eq_relative(b, 1e-12));
+        let halfway = a.slerp(b, 0.5);
+        assert_approx_eq!(halfway.mag(), 3.0);
+        assert_approx_eq!(halfway.0, halfway.1);
+        // The angle changes at a steady rate.
+        let quarter = a.slerp(b, 0.25);
+        assert_approx_eq!(quarter.1.atan2(quarter.0), std::f64::consts::PI / 8.0);
+        // Parallel and opposite vectors fall back to `lerp`.
+        assert_eq!(a.slerp(a * 3.0, 0.5), a * 2.0);
+        assert_eq!(a.slerp(a.invert(), 0.25), a.lerp(a.invert(), 0.25));
+        assert_eq!(a.slerp(Vec3(0.0, 0.0, 0.0), 0.5), a * 0.5);
+    }
+
+    #[test]
+    fn iter() {
+        let v = Vec3(1.0, -2.0, 3.0);
+        assert_eq!(v.iter().collect::<Vec<_>>(), vec![v.0, v.1, v.2]);
+        assert_eq!(v.iter().fold(0.0, f64::max), 3.0);
+        assert!(!Vec3(0.0, f64::NAN, 0.0).iter().all(f64::is_finite));
+    }
+
+    #[test]
+    fn extreme_components() {
+        let v = Vec3(-1.0, 4.0, 2.5);
+        assert_eq!(v.max_component(), 4.0);
+        assert_eq!(v.min_component(), -1.0);
+        assert_eq!(v.max_axis(), 1);
+        assert_eq!(v.min_axis(), 0);
+        let tied = Vec3(3.0, -2.0, 3.0);
+        assert_eq!(tied.max_axis(), 0);
+        assert_eq!(Vec3(1.0, -2.0, -2.0).min_axis(), 1);
+        assert_eq!(Vec3(0.0, 5.0, 5.0).max_axis(), 1);
+        assert_eq!(Vec3(7.0, 7.0, 7.0).max_axis(), 0);
+        assert_eq!(Vec3(7.0, 7.0, 7.0).min_axis(), 0);
+    }
+
+    #[test]
+    fn with() {
+        let v = Vec3(1.0, 2.0, 3.0);
+        assert_eq!(v.with_x(9.0), Vec3(9.0, 2.0, 3.0));
+        assert_eq!(v.with_y(9.0), Vec3(1.0, 9.0, 3.0));
+        assert_eq!(v.with_z(9.0), Vec3(1.0, 2.0, 9.0));
+    }
+
     #[test]
     fn ops() {
         let a = Vec3(1.0, 1.0, 1.0);
@@ -227,4 +780,58 @@ mod tests {
         assert_eq!(scale, Vec3(2.0, 2.0, 2.0));
         assert_eq!(div, Vec3(0.5, 0.5, 0.5));
     }
+
+    #[test]
+    fn ordered() {
+        use crate::vec::OrderedVec3;
+        use std::collections::hash_map::DefaultHasher;
+        use std::collections::HashSet;
+        use std::hash::{Hash, Hasher};
+        let hash = |v: Vec3<f32>| {
+            let mut hasher = DefaultHasher::new();
+            OrderedVec3(v).hash(&mut hasher);
+            hasher.finish()
+        };
+        let a = Vec3(1.0_f32, 2.0, 3.0);
+        assert_eq!(OrderedVec3(a), OrderedVec3(Vec3(1.0, 2.0, 3.0)));
+        assert_eq!(hash(a), hash(Vec3(1.0, 2.0, 3.0)));
+        assert_ne!(hash(a), hash(Vec3(3.0, 2.0, 1.0)));
+        assert_ne!(hash(a), hash(Vec3(1.0, 2.0, 3.0000002)));
+        assert_ne!(
+            OrderedVec3(Vec3(0.0, 0.0, 0.0)),
+            OrderedVec3(Vec3(-0.0, 0.0, 0.0))
+        );
+        let nan = Vec3(f32::NAN, 0.0, 0.0);
+        assert_eq!(OrderedVec3(nan), OrderedVec3(nan));
+        let set: HashSet<_> = vec![a, Vec3(1.0, 2.0, 3.0), Vec3(3.0, 2.0, 1.0)]
+            .into_iter()
+            .map(OrderedVec3)
+            .collect();
+        assert_eq!(set.len(), 2);
+        assert_eq!(
+            OrderedVec3(Vec3(1.0_f64, 2.0, 3.0)),
+            Vec3(1.0, 2.0, 3.0).into()
+        );
+    }
+
+    #[test]
+    fn reflect() {
+        let normal = Vec3(0.0, 1.0, 0.0);
+        assert_eq!(Vec3(3.0, -4.0, 1.0).reflect(normal), Vec3(3.0, 4.0, 1.0));
+        assert_eq!(Vec3(3.0, 4.0, 1.0).reflect(normal), Vec3(3.0, -4.0, 1.0));
+    }
+
+    #[test]
+    fn refract() {
+        let normal = Vec3(0.0, 1.0, 0.0_f64);
+        let incident = Vec3(1.0, -1.0, 0.0).norm();
+        assert_eq!(incident.refract(normal, 1.0), Some(incident));
+        // From air into glass, the sine of the angle to the normal shrinks by 1 / 1.5.
+        let bent = incident.refract(normal, 1.0 / 1.5).unwrap();
+        assert_approx_eq!(bent.mag(), 1.0);
+        assert_approx_eq!(bent.cross_mag(normal), incident.cross_mag(normal) / 1.5);
+        assert!(bent.1 < 0.0 && bent.0 > 0.0);
+        // From glass into air at 45°, the sine would need to be over 1.
+        assert_eq!(incident.refract(normal, 1.5), None);
+    }
 }
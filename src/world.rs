@@ -0,0 +1,1566 @@
+use crate::collide::CollisionFilter;
+use crate::contact::{ParticleContact, ParticleContactGenerator, ParticleContactResolver};
+use crate::force::{
+    MaybeSend, MaybeSync, ParticleForceGenerator, ParticleForceRegistry, RegistrationId,
+};
+use crate::material::{MaterialId, MaterialTable};
+use crate::particle::Particle;
+use crate::pool::{Handle, ParticlePool};
+use crate::spatial::SpatialHash;
+use crate::trigger::TriggerVolume;
+use crate::vec::Vec3;
+use num::Float;
+use num_traits::NumAssign;
+use std::any::Any;
+use std::collections::HashMap;
+
+/// Identifies a contact generator added to a `ParticleWorld`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ContactGeneratorId(usize);
+
+/// Identifies a contact from one frame to the next: the pair of particles, lower handle first,
+/// and how many contacts between the same pair came before it.
+type ContactKey = (Handle, Option<Handle>, usize);
+
+/// Gives `contacts`, all from `generator`, the coefficients `table` has for their sides'
+/// materials, indexed like the particles.
+fn apply_materials<T: Float + NumAssign>(
+    table: &MaterialTable<T>,
+    materials: &[Option<MaterialId>],
+    generator: &dyn ParticleContactGenerator<T>,
+    contacts: &mut [ParticleContact<T>],
+) {
+    if !generator.uses_materials() {
+        return;
+    }
+    let material = |index: usize| materials.get(index).copied().flatten();
+    let scenery = generator.scenery_material();
+    for contact in contacts {
+        let (a, b) = contact.particles;
+        let other = match b {
+            Some(b) => material(b),
+            None => scenery,
+        };
+        let combined = match (material(a), other) {
+            (Some(a), Some(b)) => table.combine(a, b),
+            _ => None,
+        };
+        if let Some(combined) = combined {
+            contact.restitution = combined.restitution;
+            contact.static_friction = combined.static_friction;
+            contact.dynamic_friction = combined.dynamic_friction;
+        }
+    }
+}
+
+/// How an impulse from `apply_radial_impulse` weakens from full strength at its center to none
+/// at its radius.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Falloff {
+    /// In proportion to the distance left to the edge, `1 - distance / radius`.
+    Linear,
+    /// With the square of the distance left to the edge, `(1 - distance / radius)²`: quickly
+    /// near the center, somewhat like the inverse-square law, but finite there.
+    QuadraticFromEdge,
+}
+
+/// Pushes each particle within `radius` of `center` directly away from it, with an impulse of
+/// `strength` weakened by `falloff` with distance. Like `query_radius`, a particle exactly
+/// `radius` away counts, but its push is zero. A particle right at the center is pushed up +Y.
+///
+/// Heavier particles are pushed the same amount and so move slower; particles with infinite
+/// mass aren't moved.
+pub fn apply_radial_impulse<T: Float + NumAssign>(
+    world: &mut ParticleWorld<T>,
+    center: Vec3<T>,
+    radius: T,
+    strength: T,
+    falloff: Falloff,
+) {
+    for index in world.query_radius(center, radius) {
+        let particle = &mut world.particles.particles_mut()[index];
+        let offset = particle.position - center;
+        let distance = offset.mag();
+        let direction = if distance.is_zero() {
+            Vec3::axis(1)
+        } else {
+            offset / distance
+        };
+        let remaining = if radius > T::zero() {
+            (T::one() - distance / radius).max(T::zero())
+        } else {
+            T::one()
+        };
+        let scale = match falloff {
+            Falloff::Linear => remaining,
+            Falloff::QuadraticFromEdge => remaining * remaining,
+        };
+        particle.apply_impulse(direction * (strength * scale));
+    }
+}
+
+/// A contact generator that can be downcast back to its own type, for
+/// `ParticleWorld::contact_generator_mut`.
+trait AnyContactGenerator<T: Float>: ParticleContactGenerator<T> {
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+
+    fn into_generator(self: Box<Self>) -> Box<dyn ParticleContactGenerator<T>>;
+}
+
+impl<T: Float, G: ParticleContactGenerator<T> + 'static> AnyContactGenerator<T> for G {
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn into_generator(self: Box<Self>) -> Box<dyn ParticleContactGenerator<T>> {
+        self
+    }
+}
+
+/// Identifies a trigger volume added to a `ParticleWorld`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TriggerId(usize);
+
+/// Called by a `ParticleWorld` for each contact it passes to its resolver; see `on_contact`.
+pub type ContactCallback<T> = Box<dyn FnMut(&ParticleContact<T>)>;
+
+/// A contact whose particles a `ParticleWorld` pushed apart; see `take_contact_events`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ContactEvent<T: Float> {
+    /// The second is `None` for contacts with scenery.
+    pub particles: (Handle, Option<Handle>),
+    /// Points toward the first particle, as for `ParticleContact`.
+    pub contact_normal: Vec3<T>,
+    /// How far the particles overlapped when the contact was generated.
+    pub penetration: T,
+    /// The total impulse along the normal the resolver applied to the contact, for scaling
+    /// effects by how hard the particles hit.
+    ///
+    /// Resting contacts get a little impulse every frame, to cancel the velocity gravity built
+    /// up, so a threshold a bit above that picks out real impacts.
+    pub impulse: T,
+}
+
+/// Owns a set of particles, the forces and contact generators that act on them, and the
+/// resolver for their contacts.
+///
+/// Each frame, call `start_frame`, apply any forces of your own, then `run_physics`.
+#[allow(clippy::module_name_repetitions)]
+pub struct ParticleWorld<T: Float> {
+    particles: ParticlePool<T>,
+    /// Indexed like the particles.
+    filters: Vec<CollisionFilter>,
+    /// Which particles use continuous collision detection, indexed like the particles.
+    ccd: Vec<bool>,
+    /// Contacts found by continuous collision detection in the last `integrate`.
+    ccd_contacts: Vec<ParticleContact<T>>,
+    /// The particle each one moves relative to, if any, indexed like the particles.
+    references: Vec<Option<Handle>>,
+    /// Indexed like the particles.
+    materials: Vec<Option<MaterialId>>,
+    material_table: MaterialTable<T>,
+    registry: ParticleForceRegistry<T>,
+    contact_generators: Vec<(ContactGeneratorId, Box<dyn AnyContactGenerator<T>>)>,
+    next_contact_generator: usize,
+    triggers: Vec<(TriggerId, TriggerVolume<T>)>,
+    next_trigger: usize,
+    contacts: Vec<ParticleContact<T>>,
+    max_contacts: usize,
+    resolver: ParticleContactResolver<T>,
+    /// The impulses last frame's contacts ended up with, while the resolver is warm starting.
+    contact_cache: HashMap<ContactKey, T>,
+    /// Whether the resolver gets two iterations per contact, rather than a fixed number.
+    auto_iterations: bool,
+    on_contact: Option<ContactCallback<T>>,
+    contact_events: Vec<ContactEvent<T>>,
+    elapsed: T,
+    step_count: u64,
+    /// For `query_radius`: the live particles as of the last `run_physics`, known by their
+    /// position in `hashed`, which holds their indices.
+    spatial_hash: Option<SpatialHash<T>>,
+    hashed: Vec<usize>,
+    /// Particles added since the spatial hash was rebuilt.
+    unhashed: Vec<usize>,
+}
+
+impl<T: Float + NumAssign> ParticleWorld<T> {
+    /// A world that generates at most `max_contacts` contacts per frame.
+    ///
+    /// Its resolver gets twice as many iterations as there are contacts each frame, unless
+    /// `with_iterations` fixes the number.
+    pub fn new(max_contacts: usize) -> Self {
+        Self {
+            particles: ParticlePool::new(),
+            filters: Vec::new(),
+            ccd: Vec::new(),
+            ccd_contacts: Vec::new(),
+            references: Vec::new(),
+            materials: Vec::new(),
+            material_table: MaterialTable::new(),
+            registry: ParticleForceRegistry::new(),
+            contact_generators: Vec::new(),
+            next_contact_generator: 0,
+            triggers: Vec::new(),
+            next_trigger: 0,
+            contacts: Vec::with_capacity(max_contacts),
+            max_contacts,
+            resolver: ParticleContactResolver::new(0, T::zero(), T::one()),
+            contact_cache: HashMap::new(),
+            auto_iterations: true,
+            on_contact: None,
+            contact_events: Vec::new(),
+            elapsed: T::zero(),
+            step_count: 0,
+            spatial_hash: None,
+            hashed: Vec::new(),
+            unhashed: Vec::new(),
+        }
+    }
+
+    /// Gives the resolver `iterations` iterations every frame, however many contacts there are.
+    pub fn with_iterations(mut self, iterations: usize) -> Self {
+        self.resolver.iterations = iterations;
+        self.auto_iterations = false;
+        self
+    }
+
+    /// Speeds up `query_radius` with a `SpatialHash` of `cell_size`, rebuilt at the end of each
+    /// `run_physics`. Cells about as wide as the radius usually queried work best.
+    ///
+    /// The hash finds particles by where they were when it was rebuilt, so one moved a long way
+    /// since, other than by `run_physics`, may be missed. Particles added since are always
+    /// checked.
+    pub fn with_spatial_hash(mut self, cell_size: T) -> Self {
+        self.spatial_hash = Some(SpatialHash::new(cell_size));
+        self.rebuild_spatial_hash();
+        self
+    }
+
+    /// The indices of the live particles whose positions are within `radius` of `center`, in
+    /// ascending order. A particle exactly `radius` away is included.
+    pub fn query_radius(&self, center: Vec3<T>, radius: T) -> Vec<usize> {
+        let within = |index: usize| {
+            self.particles.is_active(index)
+                && (self.particles.particles()[index].position - center).mag_squared()
+                    <= radius * radius
+        };
+        let mut indices: Vec<usize> = match &self.spatial_hash {
+            Some(hash) => hash
+                .candidates_near(center, radius)
+                .map(|candidate| self.hashed[candidate])
+                .chain(self.unhashed.iter().copied())
+                .filter(|&index| within(index))
+                .collect(),
+            None => (0..self.particles.particles().len())
+                .filter(|&index| within(index))
+                .collect(),
+        };
+        indices.sort_unstable();
+        indices.dedup();
+        indices
+    }
+
+    fn rebuild_spatial_hash(&mut self) {
+        let hash = match &mut self.spatial_hash {
+            Some(hash) => hash,
+            None => return,
+        };
+        self.hashed.clear();
+        self.unhashed.clear();
+        let mut positions = Vec::with_capacity(self.particles.len());
+        for (handle, particle) in self.particles.iter_active() {
+            self.hashed.push(handle.index());
+            positions.push(particle.position);
+        }
+        hash.rebuild(&positions, &vec![T::zero(); positions.len()]);
+    }
+
+    /// The simulated time that has passed, summed over every `run_physics` call.
+    pub fn elapsed(&self) -> T {
+        self.elapsed
+    }
+
+    /// How many times `run_physics` has been called.
+    pub fn step_count(&self) -> u64 {
+        self.step_count
+    }
+
+    pub fn resolver(&self) -> &ParticleContactResolver<T> {
+        &self.resolver
+    }
+
+    /// For adjusting the resolver's slop, correction factor, and warm starting. Its iterations
+    /// are overwritten each frame unless the world was made `with_iterations`.
+    ///
+    /// While warm starting, each contact starts from the impulse of the contact between the
+    /// same particles last frame, if there was one.
+    pub fn resolver_mut(&mut self) -> &mut ParticleContactResolver<T> {
+        &mut self.resolver
+    }
+
+    /// Adds a particle. Its handle's index is the one contact and force generators know it by.
+    ///
+    /// It has the default `CollisionFilter`, colliding with everything, until
+    /// `set_collision_filter` gives it another, and doesn't use continuous collision detection
+    /// until `set_ccd` says so.
+    pub fn add_particle(&mut self, particle: Particle<T>) -> Handle {
+        let handle = self.particles.spawn(particle);
+        let index = handle.index();
+        if index == self.filters.len() {
+            self.filters.push(CollisionFilter::default());
+            self.ccd.push(false);
+            self.references.push(None);
+            self.materials.push(None);
+        } else {
+            self.filters[index] = CollisionFilter::default();
+            self.ccd[index] = false;
+            self.references[index] = None;
+            self.materials[index] = None;
+        }
+        if self.spatial_hash.is_some() {
+            self.unhashed.push(index);
+        }
+        handle
+    }
+
+    /// Sets which collisions the particle takes part in, returning false if it's been removed.
+    ///
+    /// Contact generators that check filters, like `ParticleSphereCollisions` and
+    /// `GroundPlaneContacts`, skip the collisions it rules out; constraints like cables ignore
+    /// filters.
+    pub fn set_collision_filter(&mut self, handle: Handle, filter: CollisionFilter) -> bool {
+        if !self.particles.contains(handle) {
+            return false;
+        }
+        self.filters[handle.index()] = filter;
+        true
+    }
+
+    pub fn collision_filter(&self, handle: Handle) -> Option<CollisionFilter> {
+        if self.particles.contains(handle) {
+            Some(self.filters[handle.index()])
+        } else {
+            None
+        }
+    }
+
+    /// Gives the particle a material from `material_table`, or none, returning false if it's
+    /// been removed.
+    ///
+    /// Contacts from generators that use materials, like `ParticleSphereCollisions` and
+    /// `GroundPlaneContacts`, between two sides with materials get their restitution and
+    /// friction from `material_table` rather than from the generator. Particles have no
+    /// material until they're given one.
+    pub fn set_material(&mut self, handle: Handle, material: Option<MaterialId>) -> bool {
+        if !self.particles.contains(handle) {
+            return false;
+        }
+        self.materials[handle.index()] = material;
+        true
+    }
+
+    pub fn material(&self, handle: Handle) -> Option<MaterialId> {
+        if self.particles.contains(handle) {
+            self.materials[handle.index()]
+        } else {
+            None
+        }
+    }
+
+    pub fn material_table(&self) -> &MaterialTable<T> {
+        &self.material_table
+    }
+
+    pub fn material_table_mut(&mut self) -> &mut MaterialTable<T> {
+        &mut self.material_table
+    }
+
+    /// Turns continuous collision detection on or off for the particle, returning false if it's
+    /// been removed.
+    ///
+    /// A fast particle can move from one side of something to the other in a single step,
+    /// never being found overlapping it. With continuous collision detection, `integrate`
+    /// sweeps the particle's sphere along its path against each contact generator (see
+    /// `ParticleContactGenerator::sweep`). If it hits anything, the particle is moved back to
+    /// where it first touched, losing the rest of the step's movement, and the next
+    /// `generate_contacts` includes a contact there. Other particles stay where they are, so to
+    /// catch two fast particles passing through each other, turn it on for both.
+    ///
+    /// Each sweep costs as much as checking the particle against everything its generators
+    /// might collide it with, so it's best kept for the few particles fast enough to need it.
+    pub fn set_ccd(&mut self, handle: Handle, enabled: bool) -> bool {
+        if !self.particles.contains(handle) {
+            return false;
+        }
+        self.ccd[handle.index()] = enabled;
+        true
+    }
+
+    pub fn ccd(&self, handle: Handle) -> Option<bool> {
+        if self.particles.contains(handle) {
+            Some(self.ccd[handle.index()])
+        } else {
+            None
+        }
+    }
+
+    /// Makes the particle move relative to `reference`, as a payload rides a moving platform,
+    /// or relative to nothing if it's `None`. Returns false, changing nothing, if either
+    /// particle has been removed or the reference would end up moving relative to itself.
+    ///
+    /// The particle's `velocity` is then relative to the reference: each `integrate` carries it
+    /// along by the reference's velocity as well as its own, so a particle with no velocity
+    /// keeps its offset from a reference moving at a steady velocity. The reference's
+    /// acceleration isn't passed on. Forces and contacts see the relative velocity, so a
+    /// particle that's meant to collide with things moving independently of the reference is
+    /// best left unanchored. `absolute_velocity` gives the velocity a still observer would see.
+    pub fn set_reference(&mut self, handle: Handle, reference: Option<Handle>) -> bool {
+        if !self.particles.contains(handle) {
+            return false;
+        }
+        if let Some(reference) = reference {
+            let mut next = Some(reference);
+            while let Some(ancestor) = next {
+                if !self.particles.contains(ancestor) || ancestor == handle {
+                    return false;
+                }
+                next = self.references[ancestor.index()];
+            }
+        }
+        self.references[handle.index()] = reference;
+        true
+    }
+
+    pub fn reference(&self, handle: Handle) -> Option<Handle> {
+        if self.particles.contains(handle) {
+            self.references[handle.index()]
+        } else {
+            None
+        }
+    }
+
+    /// The particle's velocity plus that of everything it moves relative to; see
+    /// `set_reference`.
+    pub fn absolute_velocity(&self, handle: Handle) -> Option<Vec3<T>> {
+        let mut velocity = self.particle(handle)?.velocity;
+        let mut next = self.references[handle.index()];
+        while let Some(reference) = next.filter(|&reference| self.particles.contains(reference)) {
+            velocity += self.particles.particles()[reference.index()].velocity;
+            next = self.references[reference.index()];
+        }
+        Some(velocity)
+    }
+
+    /// Removes a particle, with every force registered on it and every current contact it's
+    /// part of. Particles moving relative to it keep moving as they were, but no longer
+    /// relative to anything.
+    ///
+    /// Generators that refer to it by index, like a spring on another particle anchored to it,
+    /// aren't removed; they'll keep seeing whatever is left in its slot.
+    ///
+    /// Returns the particle, or `None` if it was already removed.
+    pub fn remove_particle(&mut self, handle: Handle) -> Option<Particle<T>> {
+        let carried = self.absolute_velocity(handle)?;
+        for index in 0..self.references.len() {
+            if self.references[index] == Some(handle) {
+                self.references[index] = None;
+                self.particles.particles_mut()[index].velocity += carried;
+            }
+        }
+        let particle = self.particles.despawn(handle)?;
+        let index = handle.index();
+        self.registry.remove_all_for_particle(index);
+        self.contacts
+            .retain(|contact| contact.particles.0 != index && contact.particles.1 != Some(index));
+        Some(particle)
+    }
+
+    pub fn particle(&self, handle: Handle) -> Option<&Particle<T>> {
+        self.particles.get(handle)
+    }
+
+    pub fn particle_mut(&mut self, handle: Handle) -> Option<&mut Particle<T>> {
+        self.particles.get_mut(handle)
+    }
+
+    /// Every particle slot, indexed by `Handle::index`, including those of removed particles;
+    /// see `ParticlePool::particles`.
+    pub fn particles(&self) -> &[Particle<T>] {
+        self.particles.particles()
+    }
+
+    pub fn particles_mut(&mut self) -> &mut [Particle<T>] {
+        self.particles.particles_mut()
+    }
+
+    pub fn pool(&self) -> &ParticlePool<T> {
+        &self.particles
+    }
+
+    /// Registers `generator` to act on a particle each frame; see `ParticleForceRegistry::add`.
+    ///
+    /// Returns `None` if the particle has been removed.
+    pub fn add_force<G>(&mut self, particle: Handle, generator: G) -> Option<RegistrationId>
+    where
+        G: ParticleForceGenerator<T> + MaybeSend + 'static,
+    {
+        if !self.particles.contains(particle) {
+            return None;
+        }
+        Some(self.registry.add(particle.index(), generator))
+    }
+
+    /// Removes a registration, returning whether it was still registered.
+    pub fn remove_force(&mut self, registration: RegistrationId) -> bool {
+        self.registry.remove(registration)
+    }
+
+    /// The generator a registration applies, if it's still registered and is a `G`; see
+    /// `ParticleForceRegistry::generator_mut`.
+    pub fn force_generator_mut<G>(&mut self, registration: RegistrationId) -> Option<&mut G>
+    where
+        G: ParticleForceGenerator<T> + 'static,
+    {
+        self.registry.generator_mut(registration)
+    }
+
+    pub fn registry(&self) -> &ParticleForceRegistry<T> {
+        &self.registry
+    }
+
+    /// For priorities, shared generators, and break events.
+    pub fn registry_mut(&mut self) -> &mut ParticleForceRegistry<T> {
+        &mut self.registry
+    }
+
+    /// Contact generators run in the order they were added, so this generator's contacts follow
+    /// those of every generator added before it.
+    pub fn add_contact_generator<G>(&mut self, generator: G) -> ContactGeneratorId
+    where
+        G: ParticleContactGenerator<T> + 'static,
+    {
+        let id = ContactGeneratorId(self.next_contact_generator);
+        self.next_contact_generator += 1;
+        self.contact_generators.push((id, Box::new(generator)));
+        id
+    }
+
+    /// Removes a contact generator, keeping the remaining generators in their original order.
+    ///
+    /// Returns the generator, or `None` if it was already removed.
+    pub fn remove_contact_generator(
+        &mut self,
+        id: ContactGeneratorId,
+    ) -> Option<Box<dyn ParticleContactGenerator<T>>> {
+        let position = self
+            .contact_generators
+            .iter()
+            .position(|(generator_id, _)| *generator_id == id)?;
+        Some(self.contact_generators.remove(position).1.into_generator())
+    }
+
+    /// A contact generator, for changing its settings (like moving a constraint's anchor)
+    /// while it's in the world.
+    ///
+    /// Returns `None` if the generator has been removed, or isn't a `G`.
+    pub fn contact_generator_mut<G>(&mut self, id: ContactGeneratorId) -> Option<&mut G>
+    where
+        G: ParticleContactGenerator<T> + 'static,
+    {
+        let (_, generator) = self
+            .contact_generators
+            .iter_mut()
+            .find(|(generator_id, _)| *generator_id == id)?;
+        (**generator).as_any_mut().downcast_mut()
+    }
+
+    /// Adds a trigger volume, which `run_physics` updates at the end of every frame.
+    pub fn add_trigger(&mut self, trigger: TriggerVolume<T>) -> TriggerId {
+        let id = TriggerId(self.next_trigger);
+        self.next_trigger += 1;
+        self.triggers.push((id, trigger));
+        id
+    }
+
+    /// Removes a trigger volume, returning it, or `None` if it was already removed.
+    pub fn remove_trigger(&mut self, id: TriggerId) -> Option<TriggerVolume<T>> {
+        let position = self
+            .triggers
+            .iter()
+            .position(|(trigger_id, _)| *trigger_id == id)?;
+        Some(self.triggers.remove(position).1)
+    }
+
+    /// The trigger volume, with what it found at the last `update_triggers`.
+    pub fn trigger(&self, id: TriggerId) -> Option<&TriggerVolume<T>> {
+        self.triggers
+            .iter()
+            .find(|(trigger_id, _)| *trigger_id == id)
+            .map(|(_, trigger)| trigger)
+    }
+
+    /// Updates every trigger volume with where the particles are now.
+    pub fn update_triggers(&mut self) {
+        for (_, trigger) in &mut self.triggers {
+            trigger.update(&self.particles, &self.filters);
+        }
+    }
+
+    /// Clears the force accumulators, ready for this frame's forces.
+    pub fn start_frame(&mut self) {
+        for (_, particle) in self.particles.iter_active_mut() {
+            particle.clear_accumulator();
+        }
+    }
+
+    /// Replaces the current contacts with freshly generated ones, returning how many there are.
+    ///
+    /// Generators run in registration order and each appends its contacts in the order it
+    /// produces them, so the contact list is deterministic for a given world. Each is given the
+    /// particles' collision filters. Contacts found by continuous collision detection in the
+    /// last `integrate` come first. Once `max_contacts` is reached the remaining generators are
+    /// skipped. Generators that scan every particle also see the slots of removed particles, so
+    /// contacts with those are dropped.
+    pub fn generate_contacts(&mut self) -> usize {
+        self.contacts.clear();
+        let particles = &self.particles;
+        let live = |contact: &ParticleContact<T>| {
+            particles.is_active(contact.particles.0)
+                && contact
+                    .particles
+                    .1
+                    .into_iter()
+                    .all(|b| particles.is_active(b))
+        };
+        let limit = self.max_contacts;
+        self.contacts
+            .extend(self.ccd_contacts.drain(..).filter(live).take(limit));
+        for (_, generator) in &self.contact_generators {
+            let limit = self.max_contacts - self.contacts.len();
+            if limit == 0 {
+                break;
+            }
+            let start = self.contacts.len();
+            generator.add_filtered_contacts(
+                self.particles.particles(),
+                &self.filters,
+                &mut self.contacts,
+                limit,
+            );
+            apply_materials(
+                &self.material_table,
+                &self.materials,
+                generator.as_ref(),
+                &mut self.contacts[start..],
+            );
+            self.contacts.retain(live);
+        }
+        self.contacts.len()
+    }
+
+    /// The indices of the live particles moving relative to others, with the absolute velocity
+    /// of each one's reference.
+    fn carried(&self) -> Vec<(usize, Vec3<T>)> {
+        self.pool()
+            .iter_active()
+            .filter_map(|(handle, _)| {
+                let reference = self.reference(handle)?;
+                Some((handle.index(), self.absolute_velocity(reference)?))
+            })
+            .collect()
+    }
+
+    /// Moves each of the `carried` particles along by its reference's velocity.
+    fn carry(&mut self, carried: Vec<(usize, Vec3<T>)>, duration: T) {
+        let particles = self.particles.particles_mut();
+        for (index, velocity) in carried {
+            particles[index].position += velocity * duration;
+        }
+    }
+
+    /// Where each particle is before integrating, if any live particle uses continuous
+    /// collision detection.
+    fn ccd_starts(&self) -> Option<Vec<Vec3<T>>> {
+        let any =
+            (0..self.ccd.len()).any(|index| self.ccd[index] && self.particles.is_active(index));
+        if any {
+            Some(
+                self.particles
+                    .particles()
+                    .iter()
+                    .map(|p| p.position)
+                    .collect(),
+            )
+        } else {
+            None
+        }
+    }
+
+    /// Sweeps each particle using continuous collision detection from `starts` to where it is
+    /// now, moving it back to the first thing it hits.
+    fn sweep(&mut self, starts: &[Vec3<T>]) {
+        self.ccd_contacts.clear();
+        let mut hits = Vec::new();
+        let particles = self.particles.particles();
+        for index in 0..self.ccd.len() {
+            if !self.ccd[index] || !self.particles.is_active(index) {
+                continue;
+            }
+            let mut earliest: Option<(T, ParticleContact<T>)> = None;
+            for (_, generator) in &self.contact_generators {
+                if let Some(mut hit) = generator.sweep(particles, starts, &self.filters, index) {
+                    // Generators that scan every slot can hit where a removed particle was.
+                    if let Some(b) = hit.1.particles.1 {
+                        if !self.particles.is_active(b) {
+                            continue;
+                        }
+                    }
+                    apply_materials(
+                        &self.material_table,
+                        &self.materials,
+                        generator.as_ref(),
+                        std::slice::from_mut(&mut hit.1),
+                    );
+                    let earlier = match earliest {
+                        Some((t, _)) => hit.0 < t,
+                        None => true,
+                    };
+                    if earlier {
+                        earliest = Some(hit);
+                    }
+                }
+            }
+            if let Some(hit) = earliest {
+                hits.push((index, hit));
+            }
+        }
+        let particles = self.particles.particles_mut();
+        for (index, (t, contact)) in hits {
+            let start = starts[index];
+            particles[index].position = start + (particles[index].position - start) * t;
+            self.ccd_contacts.push(contact);
+        }
+    }
+
+    /// The contacts found by the last `generate_contacts`.
+    pub fn contacts(&self) -> &[ParticleContact<T>] {
+        &self.contacts
+    }
+
+    /// Calls `callback` for each contact `resolve_contacts` passes to the resolver, replacing any
+    /// earlier callback.
+    ///
+    /// The callback sees every contact found by `generate_contacts`, once each, just before
+    /// resolution and in the order they were generated, so the penetration is the one
+    /// generated. That includes contacts the resolver then leaves alone because they're already
+    /// separating; `take_contact_events` gives only the contacts that were pushed apart.
+    pub fn on_contact<F>(&mut self, callback: F)
+    where
+        F: FnMut(&ParticleContact<T>) + 'static,
+    {
+        self.on_contact = Some(Box::new(callback));
+    }
+
+    /// The contacts the last `resolve_contacts` applied an impulse to, once each, in the order
+    /// they were generated. Contacts that were already separating, or only overlapping, aren't
+    /// included.
+    ///
+    /// Each `resolve_contacts` replaces the events of the one before, so call this once per
+    /// frame to see them all.
+    pub fn take_contact_events(&mut self) -> Vec<ContactEvent<T>> {
+        std::mem::take(&mut self.contact_events)
+    }
+
+    /// Keys for this frame's contacts, by which to find them again next frame.
+    fn contact_keys(&self) -> Vec<ContactKey> {
+        let mut counts = HashMap::new();
+        self.contacts
+            .iter()
+            .map(|contact| {
+                // Contacts with removed particles were dropped by `generate_contacts`.
+                let a = self.particles.handle(contact.particles.0).unwrap();
+                let b = contact
+                    .particles
+                    .1
+                    .map(|b| self.particles.handle(b).unwrap());
+                let pair = match b {
+                    Some(b) if b < a => (b, Some(a)),
+                    _ => (a, b),
+                };
+                let count = counts.entry(pair).or_insert(0);
+                *count += 1;
+                (pair.0, pair.1, *count - 1)
+            })
+            .collect()
+    }
+
+    /// Resolves the contacts found by the last `generate_contacts`, after integrating by
+    /// `duration`.
+    pub fn resolve_contacts(&mut self, duration: T) {
+        if let Some(callback) = &mut self.on_contact {
+            for contact in &self.contacts {
+                callback(contact);
+            }
+        }
+        if self.auto_iterations {
+            self.resolver.iterations = 2 * self.contacts.len();
+        }
+        let penetrations: Vec<_> = self.contacts.iter().map(|c| c.penetration).collect();
+        let keys = if self.resolver.warm_starting > T::zero() {
+            self.contact_keys()
+        } else {
+            Vec::new()
+        };
+        let previous: Vec<_> = keys
+            .iter()
+            .map(|key| self.contact_cache.get(key).copied().unwrap_or_else(T::zero))
+            .collect();
+        let particles = self.particles.particles_mut();
+        let impulses = self.resolver.resolve_contacts_warm_started(
+            &mut self.contacts,
+            particles,
+            duration,
+            &previous,
+        );
+        // Pairs no longer in contact are forgotten.
+        self.contact_cache.clear();
+        for (&key, &impulse) in keys.iter().zip(&impulses) {
+            if impulse > T::zero() {
+                self.contact_cache.insert(key, impulse);
+            }
+        }
+        self.contact_events.clear();
+        for ((contact, penetration), impulse) in
+            self.contacts.iter().zip(penetrations).zip(impulses)
+        {
+            if impulse <= T::zero() {
+                continue;
+            }
+            let (a, b) = contact.particles;
+            // Contacts with removed particles were dropped by `generate_contacts`.
+            let a = self.particles.handle(a).unwrap();
+            let b = b.map(|b| self.particles.handle(b).unwrap());
+            self.contact_events.push(ContactEvent {
+                particles: (a, b),
+                contact_normal: contact.contact_normal,
+                penetration,
+                impulse,
+            });
+        }
+    }
+}
+
+impl<T: Float + NumAssign + MaybeSend + MaybeSync> ParticleWorld<T> {
+    /// Integrates every particle forward by `duration`, carrying those moving relative to
+    /// others along with them (see `set_reference`), then sweeps those using continuous
+    /// collision detection (see `set_ccd`). With the `rayon` feature, the particles are spread
+    /// over threads.
+    pub fn integrate(&mut self, duration: T) {
+        let starts = self.ccd_starts();
+        let carried = self.carried();
+        self.particles.integrate(duration);
+        self.carry(carried, duration);
+        if let Some(starts) = starts {
+            self.sweep(&starts);
+        }
+    }
+
+    /// Applies the registered forces, integrates by `duration`, generates and resolves
+    /// contacts, then updates the trigger volumes and any spatial hash. With the `rayon`
+    /// feature, forces and integration are spread over threads.
+    pub fn run_physics(&mut self, duration: T) {
+        self.registry
+            .update_forces(self.particles.particles_mut(), duration);
+        self.integrate(duration);
+        self.generate_contacts();
+        self.resolve_contacts(duration);
+        self.update_triggers();
+        self.rebuild_spatial_hash();
+        self.elapsed += duration;
+        self.step_count += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::collide::{CollisionFilter, GroundPlaneContacts, ParticleSphereCollisions};
+    use crate::contact::{ParticleContact, ParticleContactGenerator, ParticleContactResolver};
+    use crate::force::{ParticleGravity, ParticleSpring};
+    use crate::link::ParticleCable;
+    use crate::material::{CombineRule, Material};
+    use crate::particle::Particle;
+    use crate::pool::Handle;
+    use crate::vec::Vec3;
+    use crate::world::{apply_radial_impulse, Falloff, ParticleWorld};
+    use assert_approx_eq::assert_approx_eq;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    /// Produces `count` contacts tagged with `tag` in their penetration.
+    struct Tagged {
+        tag: f64,
+        count: usize,
+    }
+
+    impl ParticleContactGenerator<f64> for Tagged {
+        fn add_contacts(
+            &self,
+            _particles: &[Particle<f64>],
+            contacts: &mut Vec<ParticleContact<f64>>,
+            limit: usize,
+        ) -> usize {
+            let count = self.count.min(limit);
+            for i in 0..count {
+                contacts.push(ParticleContact {
+                    particles: (i, None),
+                    restitution: 0.0,
+                    contact_normal: Vec3(0.0, 1.0, 0.0),
+                    penetration: self.tag,
+                    static_friction: 0.0,
+                    dynamic_friction: 0.0,
+                });
+            }
+            count
+        }
+    }
+
+    fn tags(world: &ParticleWorld<f64>) -> Vec<(f64, usize)> {
+        world
+            .contacts()
+            .iter()
+            .map(|contact| (contact.penetration, contact.particles.0))
+            .collect()
+    }
+
+    #[test]
+    fn generator_order() {
+        let mut world = ParticleWorld::new(10);
+        world.add_particle(Particle::default());
+        world.add_particle(Particle::default());
+        let first = world.add_contact_generator(Tagged { tag: 1.0, count: 2 });
+        world.add_contact_generator(Tagged { tag: 2.0, count: 1 });
+        assert_eq!(world.generate_contacts(), 3);
+        assert_eq!(tags(&world), vec![(1.0, 0), (1.0, 1), (2.0, 0)]);
+        world.add_contact_generator(Tagged { tag: 3.0, count: 1 });
+        assert!(world.remove_contact_generator(first).is_some());
+        assert!(world.remove_contact_generator(first).is_none());
+        assert_eq!(world.generate_contacts(), 2);
+        assert_eq!(tags(&world), vec![(2.0, 0), (3.0, 0)]);
+    }
+
+    #[test]
+    fn on_contact() {
+        let mut world = ParticleWorld::new(10).with_iterations(1);
+        world.add_particle(Particle::default());
+        world.add_particle(Particle::default());
+        world.add_contact_generator(Tagged { tag: 1.0, count: 2 });
+        world.add_contact_generator(Tagged { tag: 2.0, count: 1 });
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let log = Rc::clone(&seen);
+        world.on_contact(move |contact| {
+            log.borrow_mut()
+                .push((contact.penetration, contact.particles.0))
+        });
+        world.generate_contacts();
+        world.resolve_contacts(0.1);
+        assert_eq!(*seen.borrow(), vec![(1.0, 0), (1.0, 1), (2.0, 0)]);
+        // The particles are at rest, so no contact needed an impulse.
+        assert!(world.take_contact_events().is_empty());
+    }
+
+    #[test]
+    fn integrate() {
+        let mut world = ParticleWorld::new(0);
+        for x in 0..3 {
+            world.add_particle(Particle {
+                velocity: Vec3(f64::from(x), 0.0, 0.0),
+                ..Particle::default()
+            });
+        }
+        world.integrate(0.5);
+        let xs: Vec<_> = world.particles().iter().map(|p| p.position.0).collect();
+        assert_eq!(xs, vec![0.0, 0.5, 1.0]);
+    }
+
+    #[test]
+    fn max_contacts() {
+        let mut world = ParticleWorld::new(3);
+        world.add_particle(Particle::default());
+        world.add_particle(Particle::default());
+        world.add_contact_generator(Tagged { tag: 1.0, count: 2 });
+        world.add_contact_generator(Tagged { tag: 2.0, count: 2 });
+        world.add_contact_generator(Tagged { tag: 3.0, count: 2 });
+        assert_eq!(world.generate_contacts(), 3);
+        assert_eq!(tags(&world), vec![(1.0, 0), (1.0, 1), (2.0, 0)]);
+    }
+
+    #[test]
+    fn scene() {
+        let mut world = ParticleWorld::new(16);
+        let gravity = Vec3(0.0, -9.8, 0.0);
+        let mut handles = Vec::new();
+        for x in 0..4 {
+            let handle = world.add_particle(Particle {
+                position: Vec3(f64::from(x), 3.0 + f64::from(x), 0.0),
+                velocity: Vec3(-1.0, 0.0, 0.5),
+                ..Particle::default()
+            });
+            assert!(world
+                .add_force(handle, ParticleGravity::new(gravity))
+                .is_some());
+            handles.push(handle);
+        }
+        world.add_contact_generator(GroundPlaneContacts::at_height(0.0, 0.25, 0.4));
+        for pair in handles.windows(2) {
+            let (a, b) = (pair[0].index(), pair[1].index());
+            world.add_contact_generator(ParticleCable::new(a, b, 1.5, 0.2));
+        }
+        for _ in 0..1000 {
+            world.start_frame();
+            world.run_physics(0.01);
+            assert_eq!(world.resolver().iterations, 2 * world.contacts().len());
+            for particle in world.particles() {
+                assert!(particle.position.iter().all(f64::is_finite));
+                assert!(particle.velocity.iter().all(f64::is_finite));
+                assert!(particle.position.1 > 0.0);
+            }
+        }
+        // Everything has come to rest on the ground.
+        for particle in world.particles() {
+            assert!((particle.position.1 - 0.25).abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn elapsed() {
+        let mut world: ParticleWorld<f64> = ParticleWorld::new(0);
+        world.add_particle(Particle::default());
+        assert_eq!((world.elapsed(), world.step_count()), (0.0, 0));
+        for _ in 0..300 {
+            world.start_frame();
+            world.run_physics(1.0 / 60.0);
+        }
+        assert_approx_eq!(world.elapsed(), 5.0);
+        assert_eq!(world.step_count(), 300);
+        // Integrating or resolving on their own aren't steps.
+        world.integrate(1.0);
+        world.resolve_contacts(1.0);
+        assert_eq!(world.step_count(), 300);
+    }
+
+    fn dropped_ball(height: f64) -> (ParticleWorld<f64>, Handle) {
+        let mut world = ParticleWorld::new(4);
+        let ball = world.add_particle(Particle {
+            position: Vec3(0.0, height, 0.0),
+            acceleration: Vec3(0.0, -10.0, 0.0),
+            ..Particle::default()
+        });
+        world.add_contact_generator(GroundPlaneContacts::at_height(0.0, 0.5, 0.5));
+        (world, ball)
+    }
+
+    #[test]
+    fn contact_events() {
+        let mut world: ParticleWorld<f64> = ParticleWorld::new(4);
+        world.start_frame();
+        world.run_physics(0.01);
+        assert!(world.take_contact_events().is_empty());
+
+        // Falling a metre takes about 0.45 s.
+        let (mut world, ball) = dropped_ball(1.5);
+        let mut events = Vec::new();
+        for frame in 0..60 {
+            world.start_frame();
+            let speed = -world.particle(ball).unwrap().velocity.1;
+            world.run_physics(0.01);
+            for event in world.take_contact_events() {
+                events.push((frame, speed, event));
+            }
+            assert!(world.take_contact_events().is_empty());
+        }
+        assert_eq!(events.len(), 1);
+        let (frame, speed, event) = events[0];
+        assert_eq!(frame, 45);
+        assert_eq!(event.particles, (ball, None));
+        assert_eq!(event.contact_normal, Vec3(0.0, 1.0, 0.0));
+        assert!(event.penetration >= 0.0);
+        // A unit mass, stopped and bounced back at half speed, less the last frame's gravity.
+        assert_approx_eq!(speed, 4.5, 0.1);
+        assert_approx_eq!(event.impulse, 1.5 * speed, 0.2);
+    }
+
+    #[test]
+    fn resting_contact_events() {
+        // Resting contacts report the small impulse that cancels each frame's gravity.
+        let (mut world, ball) = dropped_ball(0.5);
+        for _ in 0..100 {
+            world.start_frame();
+            world.run_physics(0.01);
+            let events = world.take_contact_events();
+            assert_eq!(events.len(), 1);
+            assert_approx_eq!(events[0].impulse, 10.0 * 0.01);
+            assert!(events.iter().all(|event| event.impulse < 0.5));
+        }
+        assert_approx_eq!(world.particle(ball).unwrap().position.1, 0.5);
+    }
+
+    #[test]
+    fn collision_filters() {
+        let mut world = ParticleWorld::new(16);
+        let mut collisions = ParticleSphereCollisions::new(0.5);
+        let debris = CollisionFilter::new(2, 1);
+        let handles: Vec<_> = (0..3)
+            .map(|x| {
+                let handle = world.add_particle(Particle {
+                    position: Vec3(f64::from(x) * 0.5, 2.0, 0.0),
+                    ..Particle::default()
+                });
+                collisions.add(handle.index(), 0.5);
+                handle
+            })
+            .collect();
+        world.add_contact_generator(collisions);
+        assert_eq!(world.generate_contacts(), 3);
+        assert_eq!(
+            world.collision_filter(handles[1]),
+            Some(CollisionFilter::default())
+        );
+        assert!(world.set_collision_filter(handles[1], debris));
+        assert!(world.set_collision_filter(handles[2], debris));
+        assert_eq!(world.generate_contacts(), 2);
+        // A particle spawned into a removed one's slot starts with the default again.
+        world.remove_particle(handles[2]);
+        assert!(!world.set_collision_filter(handles[2], debris));
+        let replacement = world.add_particle(Particle {
+            position: Vec3(1.0, 2.0, 0.0),
+            ..Particle::default()
+        });
+        assert_eq!(
+            world.collision_filter(replacement),
+            Some(CollisionFilter::default())
+        );
+        assert_eq!(world.generate_contacts(), 3);
+    }
+
+    #[test]
+    fn ccd_floor() {
+        for &speed in &[100.0_f64, 1e4, 1e7] {
+            for &ccd in &[true, false] {
+                let mut world = ParticleWorld::new(4);
+                let bullet = world.add_particle(Particle {
+                    position: Vec3(0.0, 1.0, 0.0),
+                    velocity: Vec3(speed, -speed, 0.0),
+                    ..Particle::default()
+                });
+                world.set_ccd(bullet, ccd);
+                world.add_contact_generator(GroundPlaneContacts::at_height(0.0, 0.1, 0.0));
+                world.start_frame();
+                world.run_physics(0.01);
+                let bullet = world.particle(bullet).unwrap();
+                assert_approx_eq!(bullet.position.1, 0.1, 1e-9 * speed);
+                assert_eq!(bullet.velocity.1, 0.0);
+                // Without sweeping, it's only pushed back up, far from where it hit.
+                if ccd {
+                    assert_approx_eq!(bullet.position.0, 0.9, 1e-9 * speed);
+                } else {
+                    assert_approx_eq!(bullet.position.0, speed * 0.01, 1e-9 * speed);
+                }
+            }
+        }
+    }
+
+    /// Two fast spheres, `gap` apart vertically, moving at `velocities` for one step.
+    fn ccd_spheres(gap: f64, velocities: [f64; 2]) -> (ParticleWorld<f64>, [Handle; 2]) {
+        let mut world = ParticleWorld::new(8);
+        let mut collisions = ParticleSphereCollisions::new(1.0);
+        let mut handles = Vec::new();
+        for (i, &velocity) in velocities.iter().enumerate() {
+            let handle = world.add_particle(Particle {
+                position: Vec3(-velocity * 0.005, gap * i as f64, 0.0),
+                velocity: Vec3(velocity, 0.0, 0.0),
+                ..Particle::default()
+            });
+            world.set_ccd(handle, true);
+            collisions.add(handle.index(), 0.5);
+            handles.push(handle);
+        }
+        world.add_contact_generator(collisions);
+        world.start_frame();
+        world.run_physics(0.01);
+        (world, [handles[0], handles[1]])
+    }
+
+    #[test]
+    fn ccd_head_on() {
+        // Each moves 10 in the step, so they'd swap places without ever overlapping.
+        let (mut world, [a, b]) = ccd_spheres(0.0, [1000.0, -1000.0]);
+        let (a, b) = (world.particle(a).unwrap(), world.particle(b).unwrap());
+        assert_approx_eq!(a.position.0, -0.5, 1e-9);
+        assert_approx_eq!(b.position.0, 0.5, 1e-9);
+        assert_eq!((a.velocity.0, b.velocity.0), (-1000.0, 1000.0));
+        assert!(!world.take_contact_events().is_empty());
+    }
+
+    #[test]
+    fn ccd_removed_slot() {
+        // The only contact slot goes to the sweep, which hits where a removed particle was.
+        let mut world = ParticleWorld::new(1);
+        let mut collisions = ParticleSphereCollisions::new(1.0);
+        let bullet = world.add_particle(Particle {
+            position: Vec3(-5.0, 0.0, 0.0),
+            velocity: Vec3(1000.0, 0.0, 0.0),
+            ..Particle::default()
+        });
+        let removed = world.add_particle(Particle::default());
+        world.set_ccd(bullet, true);
+        collisions.add(bullet.index(), 0.5);
+        collisions.add(removed.index(), 0.5);
+        world.add_contact_generator(collisions);
+        world.remove_particle(removed);
+        world.start_frame();
+        world.run_physics(0.01);
+        assert!(world.contacts().is_empty());
+        assert!(world.take_contact_events().is_empty());
+        // It flies on through, rather than stopping against nothing.
+        assert_approx_eq!(world.particle(bullet).unwrap().position.0, 5.0_f64);
+    }
+
+    #[test]
+    fn ccd_parallel() {
+        // Side by side, and just clear of each other.
+        let (mut world, [a, b]) = ccd_spheres(1.01, [1000.0, 1000.0]);
+        assert!(world.contacts().is_empty());
+        assert!(world.take_contact_events().is_empty());
+        assert_eq!(world.particle(a).unwrap().position.0, 5.0);
+        assert_eq!(world.particle(b).unwrap().position.0, 5.0);
+        // Side by side, one overtaking the other.
+        let (world, [a, _]) = ccd_spheres(1.01, [1000.0, 3000.0]);
+        assert!(world.contacts().is_empty());
+        assert_eq!(world.particle(a).unwrap().position.0, 5.0);
+    }
+
+    #[test]
+    fn reference_frame() {
+        let mut world = ParticleWorld::new(0);
+        let platform = world.add_particle(Particle {
+            velocity: Vec3(3.0, 0.0, -1.0),
+            ..Particle::default()
+        });
+        let payload = world.add_particle(Particle {
+            position: Vec3(0.0, 1.0, 0.0),
+            ..Particle::default()
+        });
+        let walker = world.add_particle(Particle {
+            position: Vec3(0.0, 1.0, 0.0),
+            velocity: Vec3(0.0, 0.0, 0.5),
+            ..Particle::default()
+        });
+        assert!(world.set_reference(payload, Some(platform)));
+        assert!(world.set_reference(walker, Some(payload)));
+        // No cycles.
+        assert!(!world.set_reference(platform, Some(walker)));
+        assert!(!world.set_reference(platform, Some(platform)));
+        assert_eq!(world.absolute_velocity(walker), Some(Vec3(3.0, 0.0, -0.5)));
+        for _ in 0..100 {
+            world.start_frame();
+            world.run_physics(0.01);
+            let origin = world.particle(platform).unwrap().position;
+            let offset = world.particle(payload).unwrap().position - origin;
+            assert!(offset.approx_eq_relative(Vec3(0.0, 1.0, 0.0), 1e-12));
+        }
+        let origin = world.particle(platform).unwrap().position;
+        assert!(origin.approx_eq_relative(Vec3(3.0, 0.0, -1.0), 1e-12));
+        let offset = world.particle(walker).unwrap().position - origin;
+        assert!(offset.approx_eq_relative(Vec3(0.0, 1.0, 0.5), 1e-12));
+        // Removing the platform leaves the payload moving as it was.
+        world.remove_particle(platform);
+        assert_eq!(world.reference(payload), None);
+        assert_eq!(
+            world.particle(payload).unwrap().velocity,
+            Vec3(3.0, 0.0, -1.0)
+        );
+        assert_eq!(world.absolute_velocity(walker), Some(Vec3(3.0, 0.0, -0.5)));
+    }
+
+    #[test]
+    fn remove_particle() {
+        let mut world = ParticleWorld::new(16);
+        let a = world.add_particle(Particle::default());
+        let b = world.add_particle(Particle {
+            position: Vec3(-1.0, 0.0, 0.0),
+            ..Particle::default()
+        });
+        world.add_force(a, ParticleSpring::new(b.index(), 1.0, 0.5));
+        world.add_force(b, ParticleSpring::new(a.index(), 1.0, 0.5));
+        world.add_force(b, ParticleGravity::new(Vec3(0.0, -1.0, 0.0)));
+        world.add_contact_generator(GroundPlaneContacts::at_height(0.0, 0.25, 0.0));
+        assert_eq!(world.generate_contacts(), 2);
+        assert_eq!(world.remove_particle(b).map(|p| p.position.0), Some(-1.0));
+        assert_eq!(world.remove_particle(b), None);
+        assert!(world.particle(b).is_none());
+        assert_eq!(world.registry().len(), 1);
+        assert_eq!(world.contacts().len(), 1);
+        assert_eq!(world.contacts()[0].particles, (a.index(), None));
+        assert!(world
+            .add_force(b, ParticleGravity::new(Vec3(0.0, -1.0, 0.0)))
+            .is_none());
+        // The removed particle's slot still holds it, but it gets no more contacts.
+        assert_eq!(world.generate_contacts(), 1);
+        let c = world.add_particle(Particle::default());
+        assert_eq!(c.index(), b.index());
+        assert_eq!(world.generate_contacts(), 2);
+    }
+
+    /// Steps a pyramid of ten balls, four fixed along the bottom, returning the fastest any
+    /// loose ball is moving after each step, and the last step's `iterations_used`.
+    fn pyramid(warm_starting: f64, iterations: usize, steps: usize) -> (Vec<f64>, usize) {
+        let mut world = ParticleWorld::new(64).with_iterations(iterations);
+        *world.resolver_mut() =
+            ParticleContactResolver::new(iterations, 0.01, 0.5).with_warm_starting(warm_starting);
+        let mut collisions = ParticleSphereCollisions::new(0.0);
+        let mut loose = Vec::new();
+        for row in 0..4 {
+            for column in 0..4 - row {
+                let handle = world.add_particle(Particle {
+                    position: Vec3(
+                        column as f64 + 0.5 * row as f64,
+                        0.5 + 0.99 * 0.75_f64.sqrt() * row as f64,
+                        0.0,
+                    ),
+                    acceleration: Vec3(0.0, if row == 0 { 0.0 } else { -10.0 }, 0.0),
+                    inverse_mass: if row == 0 { 0.0 } else { 1.0 },
+                    ..Particle::default()
+                });
+                collisions.add(handle.index(), 0.5);
+                if row > 0 {
+                    loose.push(handle);
+                }
+            }
+        }
+        world.add_contact_generator(collisions);
+        let mut speeds = Vec::new();
+        for _ in 0..steps {
+            world.start_frame();
+            world.run_physics(0.01);
+            let fastest = loose
+                .iter()
+                .map(|&handle| world.particle(handle).unwrap().velocity.mag())
+                .fold(0.0, f64::max);
+            speeds.push(fastest);
+        }
+        (speeds, world.resolver().iterations_used())
+    }
+
+    #[test]
+    fn warm_starting() {
+        let late =
+            |(speeds, _): (Vec<f64>, usize)| speeds[200..].iter().cloned().fold(0.0, f64::max);
+        let cold = late(pyramid(0.0, 10, 300));
+        let warm = late(pyramid(0.8, 10, 300));
+        // Warm starting settles better with a quarter of the iterations.
+        assert!(warm < cold / 4.0);
+        assert!(warm < late(pyramid(0.0, 40, 300)));
+    }
+
+    #[test]
+    fn contact_cache_evicts() {
+        let mut world: ParticleWorld<f64> = ParticleWorld::new(4);
+        world.resolver_mut().warm_starting = 0.8;
+        let ball = world.add_particle(Particle {
+            position: Vec3(0.0, 0.5, 0.0),
+            acceleration: Vec3(0.0, -10.0, 0.0),
+            ..Particle::default()
+        });
+        world.add_contact_generator(GroundPlaneContacts::at_height(0.0, 0.5, 0.0));
+        for _ in 0..10 {
+            world.start_frame();
+            world.run_physics(0.01);
+        }
+        assert_eq!(world.contact_cache.len(), 1);
+        let impulse = world.contact_cache.values().next().copied().unwrap();
+        assert_approx_eq!(impulse, 0.1, 1e-9);
+        world.particle_mut(ball).unwrap().velocity = Vec3(0.0, 5.0, 0.0);
+        world.start_frame();
+        world.run_physics(0.01);
+        assert!(world.contact_cache.is_empty());
+    }
+
+    #[test]
+    fn materials() {
+        let bounce = |ball_material: bool, pair: bool| {
+            let mut world: ParticleWorld<f64> = ParticleWorld::new(4);
+            let table = world.material_table_mut();
+            let rubber = table.add(Material::new(0.75, 0.0, 0.0));
+            let steel = table.add(Material::new(0.25, 0.0, 0.0));
+            if pair {
+                table.set_pair(rubber, steel, Material::new(1.0, 0.0, 0.0));
+            }
+            let ball = world.add_particle(Particle {
+                position: Vec3(0.0, 0.5, 0.0),
+                velocity: Vec3(0.0, -2.0, 0.0),
+                ..Particle::default()
+            });
+            if ball_material {
+                assert!(world.set_material(ball, Some(rubber)));
+            }
+            let ground = GroundPlaneContacts::at_height(0.0, 0.5, 0.0).with_material(steel);
+            world.add_contact_generator(ground);
+            world.start_frame();
+            world.run_physics(0.01);
+            world.particle(ball).unwrap().velocity.1
+        };
+        // Without a material of its own, the ball uses the plane's restitution.
+        assert_approx_eq!(bounce(false, false), 0.0);
+        assert_approx_eq!(bounce(true, false), 1.0);
+        assert_approx_eq!(bounce(true, true), 2.0);
+
+        let mut world: ParticleWorld<f64> = ParticleWorld::new(4);
+        let table = world.material_table_mut();
+        table.restitution_rule = CombineRule::Min;
+        let (rubber, steel) = (
+            table.add(Material::new(0.75, 0.0, 0.0)),
+            table.add(Material::new(0.25, 0.0, 0.0)),
+        );
+        let mut collisions = ParticleSphereCollisions::new(0.5);
+        let mut balls = Vec::new();
+        for (&x, &material) in [-0.5, 0.5].iter().zip(&[rubber, steel]) {
+            let ball = world.add_particle(Particle {
+                position: Vec3(x, 0.0, 0.0),
+                velocity: Vec3(-x * 4.0, 0.0, 0.0),
+                ..Particle::default()
+            });
+            world.set_material(ball, Some(material));
+            collisions.add(ball.index(), 0.5);
+            balls.push(ball);
+        }
+        world.add_contact_generator(collisions);
+        world.start_frame();
+        world.run_physics(0.01);
+        assert_approx_eq!(world.particle(balls[0]).unwrap().velocity.0, -0.5);
+        assert_eq!(world.material(balls[0]), Some(rubber));
+        world.remove_particle(balls[0]);
+        assert_eq!(world.material(balls[0]), None);
+    }
+
+    /// The heights of a ball dropped from `height` onto a floor with restitution 0.8, over
+    /// `frames` frames.
+    fn bouncy_ball(height: f64, resting_speed: f64, frames: usize) -> Vec<f64> {
+        let mut world = ParticleWorld::new(4);
+        world.resolver_mut().resting_speed = resting_speed;
+        let ball = world.add_particle(Particle {
+            position: Vec3(0.0, height, 0.0),
+            acceleration: Vec3(0.0, -10.0, 0.0),
+            ..Particle::default()
+        });
+        world.add_contact_generator(GroundPlaneContacts::at_height(0.0, 0.5, 0.8));
+        (0..frames)
+            .map(|_| {
+                world.start_frame();
+                world.run_physics(0.01);
+                world.particle(ball).unwrap().position.1
+            })
+            .collect()
+    }
+
+    #[test]
+    fn resting_speed() {
+        let variance = |heights: &[f64]| {
+            let mean = heights.iter().sum::<f64>() / heights.len() as f64;
+            heights.iter().map(|y| (y - mean).powi(2)).sum::<f64>() / heights.len() as f64
+        };
+        for &resting_speed in &[0.0, 0.5] {
+            // Resting on the floor, the ball stays put.
+            assert!(variance(&bouncy_ball(0.5, resting_speed, 300)) < 1e-20);
+            // Falling from 1.5, it hits at about 4.5 and bounces back at about 0.8 of that.
+            let heights = bouncy_ball(1.5, resting_speed, 100);
+            let peak = heights[50..].iter().cloned().fold(0.0, f64::max);
+            assert_approx_eq!(peak - 0.5, 0.64, 0.05);
+        }
+        // Settling from a small drop, it stops hopping once it's hitting slower than 0.5.
+        let heights = bouncy_ball(0.6, 0.5, 500);
+        assert!(variance(&heights[200..]) < 1e-20);
+        assert_approx_eq!(heights[499], 0.5, 1e-9);
+    }
+
+    #[test]
+    fn query_radius() {
+        for &hashed in &[false, true] {
+            let mut world = ParticleWorld::new(4);
+            if hashed {
+                world = world.with_spatial_hash(1.0);
+            }
+            let at = |x, y| Particle {
+                position: Vec3(x, y, 0.0),
+                ..Particle::default()
+            };
+            let handles: Vec<_> = [(0.0, 0.0), (2.0, 0.0), (0.0, -2.0), (1.5, 1.5), (10.0, 0.0)]
+                .iter()
+                .map(|&(x, y)| world.add_particle(at(x, y)))
+                .collect();
+            let center = Vec3(0.0_f64, 0.0, 0.0);
+            // Exactly 2 away is included; 2.12 away, and far away, aren't.
+            let expected: Vec<_> = handles[..3].iter().map(|h| h.index()).collect();
+            assert_eq!(world.query_radius(center, 2.0), expected);
+            world.start_frame();
+            world.run_physics(0.01);
+            assert_eq!(world.query_radius(center, 2.0), expected);
+            world.remove_particle(handles[1]);
+            let added = world.add_particle(at(-1.0, 1.0));
+            let near = world.query_radius(center, 2.0);
+            assert_eq!(near, vec![0, added.index(), 2]);
+            assert_eq!(world.query_radius(Vec3(10.0, 0.5, 0.0), 0.5), vec![4]);
+        }
+    }
+
+    #[test]
+    fn spatial_hash_nan() {
+        let mut world = ParticleWorld::new(4).with_spatial_hash(1.0);
+        world.add_particle(Particle::default());
+        world.add_particle(Particle {
+            position: Vec3(f64::NAN, 0.0, 0.0),
+            ..Particle::default()
+        });
+        world.start_frame();
+        world.run_physics(0.01);
+        assert_eq!(world.query_radius(Vec3(0.0, 0.0, 0.0), 1.0), vec![0]);
+        assert!(world.query_radius(Vec3(f64::NAN, 0.0, 0.0), 1.0).is_empty());
+    }
+
+    #[test]
+    fn radial_impulse() {
+        let mut world = ParticleWorld::new(4);
+        let positions = [
+            Vec3(1.0, 2.0, 3.0),
+            Vec3(2.0, 2.0, 3.0),
+            Vec3(1.0, 2.0, 1.0),
+            Vec3(1.0, 2.0, 0.5),
+        ];
+        let handles: Vec<_> = positions
+            .iter()
+            .map(|&position| {
+                world.add_particle(Particle {
+                    position,
+                    inverse_mass: 0.5,
+                    ..Particle::default()
+                })
+            })
+            .collect();
+        let velocity =
+            |world: &ParticleWorld<f64>, i: usize| world.particle(handles[i]).unwrap().velocity;
+        apply_radial_impulse(&mut world, positions[0], 2.0, 4.0, Falloff::Linear);
+        // The full impulse at the center, half of it halfway out, nothing at the edge or past it.
+        assert_eq!(velocity(&world, 0), Vec3(0.0, 2.0, 0.0));
+        assert_eq!(velocity(&world, 1), Vec3(1.0, 0.0, 0.0));
+        assert_eq!(velocity(&world, 2), Vec3(0.0, 0.0, 0.0));
+        assert_eq!(velocity(&world, 3), Vec3(0.0, 0.0, 0.0));
+        apply_radial_impulse(
+            &mut world,
+            positions[0],
+            2.0,
+            4.0,
+            Falloff::QuadraticFromEdge,
+        );
+        assert_eq!(velocity(&world, 0), Vec3(0.0, 4.0, 0.0));
+        assert_eq!(velocity(&world, 1), Vec3(1.5, 0.0, 0.0));
+    }
+}